@@ -0,0 +1,16 @@
+use metro::Event;
+use std::io;
+use std::time::Duration;
+
+fn main() {
+    let events = [
+        Event::station(0, "Checkout"),
+        Event::SplitTrack(0.into(), 1.into()),
+        Event::station(0, "Build"),
+        Event::station(1, "Lint"),
+        Event::JoinTrack(1.into(), 0.into()),
+        Event::station(0, "Deploy"),
+    ];
+
+    metro::replay(&events, Duration::from_millis(100), io::stdout()).unwrap();
+}
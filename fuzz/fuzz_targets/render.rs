@@ -0,0 +1,38 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use metro::{Event, Heat, RenderingSettings, TimeColumn};
+
+fuzz_target!(|input: (Vec<Event<'_>>, [u8; 3], [bool; 4])| {
+    let (events, [splat, header_every, page_break], flags) = input;
+    let [implicit_root, decorated, summary, ruler] = flags;
+
+    let mut rdr = RenderingSettings::default()
+        .splat(usize::from(splat % 8))
+        .implicit_root(implicit_root)
+        .summary(summary)
+        .ruler(ruler)
+        .header_every(usize::from(header_every % 8))
+        .page_break(usize::from(page_break % 8), "- - -\n");
+    if decorated {
+        rdr = rdr
+            .time_column(TimeColumn::Delta)
+            .heat(Heat::Text)
+            .row_numbers(true)
+            .detached_marker("○");
+    }
+
+    let mut metro = metro::events::Metro::with_settings(rdr.clone());
+    metro.extend(events);
+
+    // Rendering never panics, and has the size measured
+    let string = metro.to_string().unwrap();
+    // Unlike `str::lines`, keeps the carriage returns of station text
+    let lines = string.strip_suffix('\n').unwrap_or(&string);
+    let lines = lines.split('\n').filter(|_| !string.is_empty());
+    let width = lines
+        .clone()
+        .map(|line| metro::strip_ansi(line).chars().count())
+        .max()
+        .unwrap_or(0);
+    assert_eq!(metro.measure(&rdr), (width, lines.count()));
+});
@@ -0,0 +1,101 @@
+use crate::events::{for_each_tracks, Event, RenderingSettings, TrackId};
+use std::io::{self, Write};
+
+/// *See [`Metro::to_accessible_text`](struct.Metro.html#method.to_accessible_text).*
+pub(crate) fn to_accessible_text<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    // Text is read out, so it is never styled
+    let rdr = rdr.clone().color(false);
+    let track = |track_id: TrackId| format!("Track {}", rdr.label_of(track_id));
+
+    let mut alive = rdr.initial_tracks();
+    let mut before = Vec::with_capacity(events.len());
+    for_each_tracks(events, &rdr, |_, tracks| {
+        before.push(std::mem::replace(&mut alive, tracks.to_vec()));
+    });
+
+    // The subject of the sentence being written, if any
+    let mut subject: Option<String> = None;
+    for (event, alive) in events.iter().zip(before.iter()) {
+        let (line_subject, clause) = match event {
+            Event::StartTrack(track_id) if !alive.contains(track_id) => {
+                (track(*track_id), "starts".to_owned())
+            }
+            Event::StartTracks(track_ids) if !track_ids.is_empty() => {
+                (tracks(&rdr, track_ids), "start".to_owned())
+            }
+            Event::StopTrack(track_id) if alive.contains(track_id) => {
+                match rdr.get_abort_reason(*track_id) {
+                    Some(reason) => (track(*track_id), format!("aborts: {}", reason)),
+                    None => (track(*track_id), "ends".to_owned()),
+                }
+            }
+            Event::Station(_, station) | Event::SharedStation(_, station)
+                if rdr.is_hidden(station) =>
+            {
+                continue
+            }
+            Event::Station(track_id, station) => {
+                let text = rdr.station_lines(station).join(" ");
+                match alive.contains(track_id) {
+                    true => (track(*track_id), text),
+                    false => ("Detached station".to_owned(), text),
+                }
+            }
+            Event::SharedStation(track_ids, station) => {
+                let text = rdr.station_lines(station).join(" ");
+                (tracks(&rdr, track_ids), text)
+            }
+            Event::StationKv(track_id, pairs) => {
+                let text = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{} {}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (track(*track_id), text)
+            }
+            Event::SplitTrack(parent, child) if alive.contains(parent) => {
+                (track(*parent), format!("splits to {}", track(*child)))
+            }
+            Event::SplitTrack(_, child) => (track(*child), "starts".to_owned()),
+            Event::JoinTrack(child, target) if alive.contains(child) => {
+                (track(*child), format!("joins {}", track(*target)))
+            }
+            Event::Link(from, to) => (track(*from), format!("links to {}", track(*to))),
+            Event::Reference(from, to) => {
+                ("Reference".to_owned(), format!("{} refers to {}", from, to))
+            }
+            _ => continue,
+        };
+
+        if subject.as_ref() == Some(&line_subject) {
+            write!(w, "; {}", clause)?;
+        } else {
+            if subject.is_some() {
+                writeln!(w, ".")?;
+            }
+            write!(w, "{}: {}", line_subject, clause)?;
+            subject = Some(line_subject);
+        }
+    }
+    if subject.is_some() {
+        writeln!(w, ".")?;
+    }
+
+    Ok(())
+}
+
+/// The subject of a sentence about every track of `track_ids`.
+fn tracks(rdr: &RenderingSettings, track_ids: &[TrackId]) -> String {
+    let labels = track_ids
+        .iter()
+        .map(|&track_id| rdr.label_of(track_id).into_owned())
+        .collect::<Vec<_>>();
+    match labels.as_slice() {
+        [label] => format!("Track {}", label),
+        _ => format!("Tracks {}", labels.join(", ")),
+    }
+}
@@ -0,0 +1,33 @@
+//! Adapters laying out the graphs of other tools as events, e.g.
+//! to preview the jobs of a CI workflow in the terminal.
+//!
+//! Every adapter returns events that can be rendered as is, e.g.
+//! with [`to_string`], one station per task, in an order such that
+//! every task comes after the tasks it depends on.
+//!
+//! [`to_string`]: ../fn.to_string.html
+
+#[cfg(feature = "yaml")]
+pub mod argo;
+#[cfg(feature = "yaml")]
+pub mod gha;
+pub mod make;
+#[cfg(feature = "json")]
+pub mod plan;
+#[cfg(feature = "yaml")]
+pub mod tasks;
+
+#[cfg(feature = "yaml")]
+use crate::error::invalid_data;
+#[cfg(feature = "yaml")]
+use std::io;
+
+/// Parse the first document of `source` as YAML.
+#[cfg(feature = "yaml")]
+fn load_yaml(source: &str) -> io::Result<yaml_rust2::Yaml> {
+    let mut documents = yaml_rust2::YamlLoader::load_from_str(source).map_err(invalid_data)?;
+    if documents.is_empty() {
+        return Err(invalid_data("empty document"));
+    }
+    Ok(documents.swap_remove(0))
+}
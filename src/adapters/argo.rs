@@ -0,0 +1,89 @@
+//! [Argo Workflows] DAG templates.
+//!
+//! Requires the `yaml` feature.
+//!
+//! [Argo Workflows]: https://argo-workflows.readthedocs.io
+
+use std::io;
+
+use super::{load_yaml, tasks};
+use crate::error::invalid_data;
+use crate::events::Event;
+
+/// Parse an Argo `Workflow`, `WorkflowTemplate` or `CronWorkflow`,
+/// and lay out the tasks of its DAG template as events, one station
+/// per task, connected by tracks along their `dependencies` or
+/// `depends` expression.
+///
+/// The DAG template is the `entrypoint` if it is a DAG, and otherwise
+/// the first DAG template. Tasks are laid out as with
+/// [`tasks::from_yaml`].
+///
+/// ```
+/// let events = metro::adapters::argo::from_workflow("
+/// apiVersion: argoproj.io/v1alpha1
+/// kind: Workflow
+/// spec:
+///   entrypoint: diamond
+///   templates:
+///   - name: diamond
+///     dag:
+///       tasks:
+///       - name: A
+///         template: echo
+///       - name: B
+///         dependencies: [A]
+///         template: echo
+///       - name: C
+///         dependencies: [A]
+///         template: echo
+///       - name: D
+///         depends: B && C
+///         template: echo
+///   - name: echo
+///     container:
+///       image: alpine:3.7
+/// ").unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// let stations = string
+///     .lines()
+///     .filter_map(|line| line.split_whitespace().last())
+///     .filter(|word| word.chars().all(char::is_alphabetic))
+///     .collect::<Vec<_>>();
+/// assert_eq!(stations, ["A", "B", "C", "D"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `yaml` has no DAG
+/// template, or a task depends on a task that does not exist.
+///
+/// [`tasks::from_yaml`]: ../tasks/fn.from_yaml.html
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_workflow(yaml: &str) -> io::Result<Vec<Event<'static>>> {
+    let workflow = load_yaml(yaml)?;
+    let mut spec = &workflow["spec"];
+    // Cron workflows nest the spec of the workflows they start
+    if !spec["workflowSpec"].is_badvalue() {
+        spec = &spec["workflowSpec"];
+    }
+
+    let templates = spec["templates"]
+        .as_vec()
+        .ok_or_else(|| invalid_data("expected a list of `templates`"))?;
+    let entrypoint = spec["entrypoint"].as_str();
+    let template = templates
+        .iter()
+        .filter(|template| !template["dag"].is_badvalue())
+        .find(|template| template["name"].as_str() == entrypoint)
+        .or_else(|| {
+            templates
+                .iter()
+                .find(|template| !template["dag"].is_badvalue())
+        })
+        .ok_or_else(|| invalid_data("expected a DAG template"))?;
+
+    tasks::layout(&template["dag"]["tasks"])
+}
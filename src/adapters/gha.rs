@@ -0,0 +1,89 @@
+//! [GitHub Actions] workflows.
+//!
+//! Requires the `yaml` feature.
+//!
+//! [GitHub Actions]: https://docs.github.com/en/actions
+
+use std::collections::HashMap;
+use std::io;
+
+use yaml_rust2::Yaml;
+
+use super::load_yaml;
+use crate::dag;
+use crate::error::invalid_data;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Parse a GitHub Actions workflow, and lay out its jobs as events,
+/// one station per job, connected by tracks along their `needs`.
+///
+/// Jobs are rendered as their `name`, or their ID, in the order they
+/// are declared, unless they need a job declared after them, and get
+/// their ID as [station ID].
+///
+/// ```
+/// let events = metro::adapters::gha::from_workflow("
+/// on: push
+/// jobs:
+///   build:
+///     runs-on: ubuntu-latest
+///   test:
+///     needs: build
+///   lint:
+///     needs: build
+///   deploy:
+///     name: Deploy to prod
+///     needs: [test, lint]
+/// ").unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("Deploy to prod"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `yaml` is not a
+/// workflow, or a job needs a job that does not exist.
+///
+/// [station ID]: ../../struct.Station.html#method.with_id
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_workflow(yaml: &str) -> io::Result<Vec<Event<'static>>> {
+    let workflow = load_yaml(yaml)?;
+    let jobs = workflow["jobs"]
+        .as_hash()
+        .ok_or_else(|| invalid_data("expected a map of `jobs`"))?;
+
+    let mut ids = HashMap::new();
+    let mut stations = Vec::with_capacity(jobs.len());
+    for (i, (id, job)) in jobs.iter().enumerate() {
+        let id = id
+            .as_str()
+            .ok_or_else(|| invalid_data("expected job IDs to be strings"))?;
+        let name = job["name"].as_str().unwrap_or(id);
+        ids.insert(id, i);
+        stations.push(Station::new(name.to_owned()).with_id(id.to_owned()));
+    }
+
+    let mut edges = Vec::new();
+    for (i, (id, job)) in jobs.iter().enumerate() {
+        let needs = match &job["needs"] {
+            Yaml::String(need) => vec![need.as_str()],
+            Yaml::Array(needs) => needs.iter().filter_map(Yaml::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for need in needs {
+            let need = ids.get(need).ok_or_else(|| {
+                invalid_data(format!(
+                    "job `{}` needs unknown job `{}`",
+                    id.as_str().unwrap_or_default(),
+                    need
+                ))
+            })?;
+            edges.push((*need, i));
+        }
+    }
+
+    Ok(dag::layout(stations, &edges))
+}
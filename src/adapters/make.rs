@@ -0,0 +1,120 @@
+//! [GNU Make] databases, as printed by `make -pn`.
+//!
+//! [GNU Make]: https://www.gnu.org/software/make/manual/make.html
+
+use std::collections::HashMap;
+
+use crate::dag;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Parse the database printed by `make -pn`, and lay out its targets
+/// as events, one station per target, connected by tracks along
+/// their prerequisites, i.e. in an order make could build them.
+///
+/// Only the `# Files` section of the database is read, if any, such
+/// that the rules of a plain makefile can be given instead. Special
+/// targets, e.g. `.PHONY`, pattern rules, and prerequisites without
+/// rules, e.g. source files, are left out.
+///
+/// ```
+/// let events = metro::adapters::make::from_database("
+/// # Files
+///
+/// # Not a target:
+/// Makefile:
+/// #  Implicit rule search has been done.
+///
+/// all: app docs
+/// #  Phony target (prerequisite of .PHONY).
+///
+/// app: main.o util.o
+/// #  recipe to execute (from 'Makefile', line 4):
+/// \tcc -o app main.o util.o
+///
+/// main.o: main.c util.h
+///
+/// util.o: util.c util.h
+///
+/// docs: | app
+///
+/// .PHONY: all docs
+/// ");
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// let position = |target| string.find(target).unwrap();
+/// assert!(position("main.o") < position("app"));
+/// assert!(position("app") < position("docs"));
+/// assert!(position("docs") < position("all"));
+/// ```
+pub fn from_database(database: &str) -> Vec<Event<'static>> {
+    let files = match database.split_once("\n# Files\n") {
+        Some((_, files)) => files,
+        None => database,
+    };
+
+    let mut ids = HashMap::new();
+    // Every target with its prerequisites, in order of appearance
+    let mut targets: Vec<(&str, Vec<&str>)> = Vec::new();
+    let mut not_a_target = false;
+
+    for line in files.lines() {
+        if line.starts_with("# files hash-table stats") || line.starts_with("# VPATH") {
+            break;
+        }
+        if line == "# Not a target:" {
+            not_a_target = true;
+            continue;
+        }
+        // Comments and recipes
+        if line.trim().is_empty() || line.starts_with('#') || line.starts_with('\t') {
+            continue;
+        }
+        if std::mem::take(&mut not_a_target) {
+            continue;
+        }
+
+        let Some((names, prerequisites)) = line.split_once(':') else {
+            continue;
+        };
+        // Double-colon rules
+        let prerequisites = prerequisites.strip_prefix(':').unwrap_or(prerequisites);
+        // Variables, including target-specific ones
+        if names.contains('=') || prerequisites.contains('=') || names.contains('%') {
+            continue;
+        }
+        let prerequisites = prerequisites
+            .split_whitespace()
+            // Order-only prerequisites are still prerequisites
+            .filter(|&prerequisite| prerequisite != "|");
+
+        for name in names
+            .split_whitespace()
+            .filter(|name| !name.starts_with('.'))
+        {
+            let i = *ids.entry(name).or_insert_with(|| {
+                targets.push((name, Vec::new()));
+                targets.len() - 1
+            });
+            targets[i].1.extend(prerequisites.clone());
+        }
+    }
+
+    let edges = targets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, prerequisites))| {
+            prerequisites
+                .iter()
+                .filter_map(|prerequisite| ids.get(prerequisite))
+                .map(move |&prerequisite| (prerequisite, i))
+        })
+        .collect::<Vec<_>>();
+
+    let stations = targets
+        .iter()
+        .map(|&(name, _)| Station::new(name.to_owned()).with_id(name.to_owned()))
+        .collect();
+    dag::layout(stations, &edges)
+}
@@ -0,0 +1,126 @@
+//! Query plans, e.g. as printed by `EXPLAIN (FORMAT JSON)` in
+//! PostgreSQL, or any tree of nested JSON nodes.
+//!
+//! Requires the `json` feature.
+
+use std::fmt::Write;
+use std::io;
+
+use serde_json::{Map, Value};
+
+use crate::dag;
+use crate::error::invalid_data;
+use crate::events::Event;
+use crate::station::Station;
+
+/// The keys of the name of a node, in order of preference.
+const NAME_KEYS: [&str; 5] = ["Node Type", "node_type", "type", "name", "operation"];
+/// The keys of the children of a node, in order of preference.
+const CHILDREN_KEYS: [&str; 4] = ["Plans", "children", "inputs", "plans"];
+
+/// Parse a query plan, and lay out its nodes as events, one station
+/// per node with its cost, connected by tracks from the nodes
+/// producing rows to the nodes consuming them, i.e. bottom-up.
+///
+/// Nodes are JSON objects, named by their `Node Type`, `type`, or
+/// `name`, with their children under `Plans`, `children`, or
+/// `inputs`. Their cost is either PostgreSQL's `Startup Cost` and
+/// `Total Cost`, or a `cost`, followed by their `Plan Rows` or
+/// `rows`, if any. The plan is either the root node, or as printed
+/// by PostgreSQL, a list holding an object with the root `Plan`.
+///
+/// ```
+/// let events = metro::adapters::plan::from_json(r#"[{"Plan": {
+///     "Node Type": "Hash Join", "Startup Cost": 1.09, "Total Cost": 2.28, "Plan Rows": 5,
+///     "Plans": [
+///         {"Node Type": "Seq Scan", "Relation Name": "orders", "Total Cost": 1.05},
+///         {"Node Type": "Hash", "Total Cost": 1.04, "Plans": [
+///             {"Node Type": "Seq Scan", "Relation Name": "users", "Total Cost": 1.04}
+///         ]}
+///     ]
+/// }}]"#).unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("Seq Scan on users (cost=1.04)"));
+/// assert!(string.contains("Hash Join (cost=1.09..2.28 rows=5)"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `json` is not JSON,
+/// or its root is not an object.
+///
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_json(json: &str) -> io::Result<Vec<Event<'static>>> {
+    let plan = serde_json::from_str::<Value>(json).map_err(invalid_data)?;
+    let mut root = &plan;
+    if let Value::Array(plans) = root {
+        root = plans.first().unwrap_or(&Value::Null);
+    }
+    if let Some(inner) = root.get("Plan") {
+        root = inner;
+    }
+    let root = root
+        .as_object()
+        .ok_or_else(|| invalid_data("expected a plan node object"))?;
+
+    let mut stations = Vec::new();
+    let mut edges = Vec::new();
+    // Depth-first, such that independent subtrees
+    // are rendered in the order of the plan
+    let mut stack = vec![(root, None)];
+    while let Some((node, parent)) = stack.pop() {
+        let i = stations.len();
+        stations.push(Station::new(label(node)));
+        if let Some(parent) = parent {
+            edges.push((i, parent));
+        }
+        let children = CHILDREN_KEYS
+            .iter()
+            .find_map(|key| node.get(*key))
+            .and_then(Value::as_array);
+        for child in children.into_iter().flatten().rev() {
+            if let Some(child) = child.as_object() {
+                stack.push((child, Some(i)));
+            }
+        }
+    }
+
+    Ok(dag::layout(stations, &edges))
+}
+
+/// The name of `node`, followed by its cost and rows,
+/// e.g. `Seq Scan on users (cost=0.00..35.50 rows=2550)`.
+fn label(node: &Map<String, Value>) -> String {
+    let mut label = NAME_KEYS
+        .iter()
+        .find_map(|key| node.get(*key))
+        .map(|name| match name {
+            Value::String(name) => name.clone(),
+            name => name.to_string(),
+        })
+        .unwrap_or_else(|| "?".to_owned());
+    if let Some(relation) = node.get("Relation Name").and_then(Value::as_str) {
+        write!(label, " on {}", relation).unwrap();
+    }
+
+    let number = |keys: &[&str]| keys.iter().find_map(|key| node.get(*key)?.as_f64());
+    let startup = number(&["Startup Cost", "startup_cost"]);
+    let total = number(&["Total Cost", "total_cost", "cost"]);
+    let rows = number(&["Plan Rows", "rows"]);
+
+    let mut details = Vec::new();
+    match (startup, total) {
+        (Some(startup), Some(total)) => details.push(format!("cost={:.2}..{:.2}", startup, total)),
+        (None, Some(cost)) | (Some(cost), None) => details.push(format!("cost={:.2}", cost)),
+        (None, None) => {}
+    }
+    if let Some(rows) = rows {
+        details.push(format!("rows={}", rows));
+    }
+    if !details.is_empty() {
+        write!(label, " ({})", details.join(" ")).unwrap();
+    }
+    label
+}
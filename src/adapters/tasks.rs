@@ -0,0 +1,133 @@
+//! Generic task graphs, a list of tasks with their dependencies:
+//!
+//! ```yaml
+//! tasks:
+//!   - name: build
+//!   - name: test
+//!     dependencies: [build]
+//! ```
+//!
+//! Requires the `yaml` feature.
+
+use std::collections::HashMap;
+use std::io;
+
+use yaml_rust2::Yaml;
+
+use super::load_yaml;
+use crate::dag;
+use crate::error::invalid_data;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Parse a task graph, and lay out its `tasks` as events, one
+/// station per task, connected by tracks along their dependencies.
+///
+/// `tasks` is either a list of tasks with a `name`, or a map of
+/// tasks by name. Dependencies are either listed as `dependencies`
+/// or `needs`, or given as an Argo `depends` expression, e.g.
+/// `a && (b.Succeeded || c)`.
+///
+/// Tasks are rendered as their name, in the order they are declared,
+/// unless they depend on a task declared after them, and get their
+/// name as [station ID]. Dependencies closing a cycle are rendered
+/// as a [reference] back to the earlier task.
+///
+/// ```
+/// let events = metro::adapters::tasks::from_yaml("
+/// tasks:
+///   - name: fetch
+///   - name: build
+///     dependencies: [fetch]
+///   - name: test
+///     depends: build.Succeeded
+///   - name: report
+///     depends: test || build.Failed
+/// ").unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("report"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `yaml` is not a task
+/// graph, or a task depends on a task that does not exist.
+///
+/// [station ID]: ../../struct.Station.html#method.with_id
+/// [reference]: ../../enum.Event.html#variant.Reference
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_yaml(yaml: &str) -> io::Result<Vec<Event<'static>>> {
+    layout(&load_yaml(yaml)?["tasks"])
+}
+
+/// Lay out `tasks`, either a list of tasks with a `name`,
+/// or a map of tasks by name.
+pub(super) fn layout(tasks: &Yaml) -> io::Result<Vec<Event<'static>>> {
+    let tasks = match tasks {
+        Yaml::Array(tasks) => tasks
+            .iter()
+            .map(|task| match task["name"].as_str() {
+                Some(name) => Ok((name, task)),
+                None => Err(invalid_data("expected every task to have a `name`")),
+            })
+            .collect::<io::Result<Vec<_>>>()?,
+        Yaml::Hash(tasks) => tasks
+            .iter()
+            .map(|(name, task)| match name.as_str() {
+                Some(name) => Ok((name, task)),
+                None => Err(invalid_data("expected task names to be strings")),
+            })
+            .collect::<io::Result<Vec<_>>>()?,
+        _ => return Err(invalid_data("expected a list or map of `tasks`")),
+    };
+
+    let ids = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, _))| (name, i))
+        .collect::<HashMap<_, _>>();
+
+    let mut edges = Vec::new();
+    for (i, &(name, task)) in tasks.iter().enumerate() {
+        for dependency in dependencies(task) {
+            let dependency = ids.get(dependency).ok_or_else(|| {
+                invalid_data(format!(
+                    "task `{}` depends on unknown task `{}`",
+                    name, dependency
+                ))
+            })?;
+            edges.push((*dependency, i));
+        }
+    }
+
+    let stations = tasks
+        .iter()
+        .map(|&(name, _)| Station::new(name.to_owned()).with_id(name.to_owned()))
+        .collect();
+    Ok(dag::layout(stations, &edges))
+}
+
+/// The names of the tasks `task` depends on.
+fn dependencies(task: &Yaml) -> Vec<&str> {
+    let mut dependencies = Vec::new();
+    for key in ["dependencies", "needs"] {
+        match &task[key] {
+            Yaml::String(dependency) => dependencies.push(dependency.as_str()),
+            Yaml::Array(list) => dependencies.extend(list.iter().filter_map(Yaml::as_str)),
+            _ => {}
+        }
+    }
+    // Every task referenced by the expression, e.g. `a` and `b`
+    // in `a.Succeeded && !b`, whether it must succeed or not
+    if let Some(depends) = task["depends"].as_str() {
+        dependencies.extend(
+            depends
+                .split(|c: char| c.is_whitespace() || "&|!()".contains(c))
+                .filter_map(|term| term.split('.').next())
+                .filter(|name| !name.is_empty()),
+        );
+    }
+    dependencies
+}
@@ -0,0 +1,254 @@
+//! Detect mistakes in `&[`[`Event`]`]`, e.g. to debug why a graph
+//! built from raw events looks wrong.
+//!
+//! [`Event`]: ../enum.Event.html
+use crate::events::{Event, RenderingSettings, TrackId};
+use crate::station::Severity;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A mistake found in events.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LintKind {
+    /// The track is never stopped nor joined.
+    NeverStopped(TrackId),
+    /// A station is added to the track after it was stopped or joined,
+    /// and is rendered as not tied to any track.
+    StationAfterStop(TrackId),
+    /// The track is stopped, split, joined, or linked while not alive.
+    NotAlive(TrackId),
+    /// The track is started or split into while already alive.
+    AlreadyAlive(TrackId),
+    /// The track is split or joined into itself.
+    SelfJoin(TrackId),
+    /// A reference or an update refers to a station ID no station has.
+    UnknownStation(String),
+    /// Several stations have the same ID.
+    DuplicateStationId(String),
+}
+
+impl LintKind {
+    /// Returns how bad the mistake is: [`Severity::Info`] if valid but
+    /// possibly unintended, [`Severity::Warn`] if rendered but likely not
+    /// as intended, and [`Severity::Error`] if ignored or rendered
+    /// incorrectly.
+    ///
+    /// [`Severity::Info`]: ../enum.Severity.html#variant.Info
+    /// [`Severity::Warn`]: ../enum.Severity.html#variant.Warn
+    /// [`Severity::Error`]: ../enum.Severity.html#variant.Error
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::NeverStopped(_) => Severity::Info,
+            LintKind::StationAfterStop(_)
+            | LintKind::UnknownStation(_)
+            | LintKind::DuplicateStationId(_) => Severity::Warn,
+            LintKind::NotAlive(_) | LintKind::AlreadyAlive(_) | LintKind::SelfJoin(_) => {
+                Severity::Error
+            }
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintKind::NeverStopped(id) => write!(f, "track {} is never stopped", id),
+            LintKind::StationAfterStop(id) => write!(f, "station on track {} after it stopped", id),
+            LintKind::NotAlive(id) => write!(f, "track {} is not alive", id),
+            LintKind::AlreadyAlive(id) => write!(f, "track {} is already alive", id),
+            LintKind::SelfJoin(id) => write!(f, "track {} is split or joined into itself", id),
+            LintKind::UnknownStation(id) => write!(f, "no station has ID {:?}", id),
+            LintKind::DuplicateStationId(id) => write!(f, "several stations have ID {:?}", id),
+        }
+    }
+}
+
+/// A mistake found by [`lint`], at the event at `index`.
+///
+/// [`lint`]: fn.lint.html
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Lint {
+    /// The index of the offending event.
+    pub index: usize,
+    pub kind: LintKind,
+}
+
+impl Lint {
+    /// Returns how bad the mistake is.
+    ///
+    /// *See [`LintKind::severity`].*
+    ///
+    /// [`LintKind::severity`]: enum.LintKind.html#method.severity
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Info => "info",
+            Severity::Warn => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: event {}: {}", severity, self.index, self.kind)
+    }
+}
+
+/// Returns every mistake found in `events`, ordered by index,
+/// assuming the default track `0` is alive initially.
+///
+/// *See [`events::Metro::lint`] to lint with other settings.*
+///
+/// ```
+/// use metro::analysis::{Lint, LintKind};
+/// use metro::{Event, Severity};
+///
+/// let events = [
+///     Event::StopTrack(0.into()),
+///     Event::station(0, "Too late"),
+///     Event::JoinTrack(1.into(), 0.into()),
+/// ];
+///
+/// let lints = metro::analysis::lint(&events);
+/// assert_eq!(lints[0], Lint { index: 1, kind: LintKind::StationAfterStop(0.into()) });
+/// assert_eq!(lints[1].to_string(), "error: event 2: track 1 is not alive");
+/// assert!(lints.iter().all(|lint| lint.severity() >= Severity::Warn));
+/// ```
+///
+/// [`events::Metro::lint`]: ../events/struct.Metro.html#method.lint
+pub fn lint(events: &[Event]) -> Vec<Lint> {
+    lint_with(events, &RenderingSettings::default())
+}
+
+pub(crate) fn lint_with(events: &[Event], rdr: &RenderingSettings) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    // The index of the event starting each alive track
+    let mut alive: HashMap<TrackId, usize> = rdr
+        .initial_tracks()
+        .into_iter()
+        .map(|track_id| (track_id, 0))
+        .collect();
+    let mut stopped: HashSet<TrackId> = HashSet::new();
+    let mut station_ids: HashSet<&str> = HashSet::new();
+
+    for (index, event) in events.iter().enumerate() {
+        let mut found = Vec::new();
+        let mut report = |kind| found.push(kind);
+
+        match event {
+            Event::StartTrack(track_id) => {
+                if alive.insert(*track_id, index).is_some() {
+                    report(LintKind::AlreadyAlive(*track_id));
+                }
+            }
+            Event::StartTracks(track_ids) => {
+                for track_id in track_ids.iter() {
+                    if alive.insert(*track_id, index).is_some() {
+                        report(LintKind::AlreadyAlive(*track_id));
+                    }
+                }
+            }
+            Event::Station(track_id, station) => {
+                if !alive.contains_key(track_id) && stopped.contains(track_id) {
+                    report(LintKind::StationAfterStop(*track_id));
+                }
+                if let Some(id) = station.id() {
+                    if !station_ids.insert(id) {
+                        report(LintKind::DuplicateStationId(id.to_owned()));
+                    }
+                }
+            }
+            Event::SharedStation(track_ids, station) => {
+                for track_id in track_ids.iter() {
+                    if !alive.contains_key(track_id) && stopped.contains(track_id) {
+                        report(LintKind::StationAfterStop(*track_id));
+                    }
+                }
+                if let Some(id) = station.id() {
+                    if !station_ids.insert(id) {
+                        report(LintKind::DuplicateStationId(id.to_owned()));
+                    }
+                }
+            }
+            Event::StationKv(track_id, _) => {
+                if !alive.contains_key(track_id) && stopped.contains(track_id) {
+                    report(LintKind::StationAfterStop(*track_id));
+                }
+            }
+            Event::StopTrack(track_id) => {
+                if alive.remove(track_id).is_none() {
+                    report(LintKind::NotAlive(*track_id));
+                }
+                stopped.insert(*track_id);
+            }
+            Event::SplitTrack(parent, child) => {
+                if parent == child {
+                    report(LintKind::SelfJoin(*parent));
+                } else {
+                    if !alive.contains_key(parent) {
+                        report(LintKind::NotAlive(*parent));
+                    }
+                    if alive.insert(*child, index).is_some() {
+                        report(LintKind::AlreadyAlive(*child));
+                    }
+                }
+            }
+            Event::JoinTrack(child, target) => {
+                if child == target {
+                    report(LintKind::SelfJoin(*child));
+                } else {
+                    for track_id in [child, target] {
+                        if !alive.contains_key(track_id) {
+                            report(LintKind::NotAlive(*track_id));
+                        }
+                    }
+                    alive.remove(child);
+                    stopped.insert(*child);
+                }
+            }
+            Event::Link(from, to) => {
+                for track_id in [from, to] {
+                    if !alive.contains_key(track_id) {
+                        report(LintKind::NotAlive(*track_id));
+                    }
+                }
+            }
+            Event::NoEvent
+            | Event::Reference(..)
+            | Event::UpdateStation(..)
+            | Event::SetStatus(..) => {}
+        }
+        lints.extend(found.into_iter().map(|kind| Lint { index, kind }));
+    }
+
+    // References and updates may refer to stations coming later
+    for (index, event) in events.iter().enumerate() {
+        let ids = match event {
+            Event::Reference(from, to) => vec![from, to],
+            Event::UpdateStation(id, _) | Event::SetStatus(id, _) => vec![id],
+            _ => continue,
+        };
+        for id in ids {
+            if !station_ids.contains(id.as_ref()) {
+                lints.push(Lint {
+                    index,
+                    kind: LintKind::UnknownStation(id.to_string()),
+                });
+            }
+        }
+    }
+
+    let mut never_stopped = alive.into_iter().collect::<Vec<_>>();
+    never_stopped.sort();
+    for (track_id, index) in never_stopped {
+        lints.push(Lint {
+            index,
+            kind: LintKind::NeverStopped(track_id),
+        });
+    }
+
+    // Stable, so lints of the same event keep their order
+    lints.sort_by_key(|lint| lint.index);
+    lints
+}
@@ -0,0 +1,632 @@
+use crate::error::invalid_data;
+use crate::events::{Event, TrackId};
+use crate::station::{Level, Severity, Station, Status};
+use crate::styled::{Span, StyledText};
+use std::borrow::Cow;
+use std::io;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Every binary event stream starts with these bytes.
+const MAGIC: &[u8; 4] = b"MTRO";
+
+/// The current version of the binary format.
+pub(crate) const VERSION: u8 = 4;
+
+/// The deepest [nesting] of stations read, which bounds
+/// the recursion for corrupted streams.
+///
+/// [nesting]: struct.Station.html#method.with_nested
+const MAX_NESTING: usize = 64;
+
+const TAG_START_TRACK: u8 = 0;
+const TAG_START_TRACKS: u8 = 1;
+const TAG_STOP_TRACK: u8 = 2;
+const TAG_STATION: u8 = 3;
+const TAG_SPLIT_TRACK: u8 = 4;
+const TAG_JOIN_TRACK: u8 = 5;
+const TAG_NO_EVENT: u8 = 6;
+const TAG_REFERENCE: u8 = 7;
+const TAG_LINK: u8 = 8;
+const TAG_SHARED_STATION: u8 = 9;
+const TAG_STATION_KV: u8 = 10;
+const TAG_UPDATE_STATION: u8 = 11;
+const TAG_SET_STATUS: u8 = 12;
+
+/// Serialize `&[`[`Event`]`]` into a compact, versioned binary format.
+///
+/// The output starts with a magic number and a format version,
+/// followed by the number of events and the events themselves.
+/// All integers are LEB128 encoded, and all strings and lists
+/// are length-prefixed.
+///
+/// *See also [`from_bytes`].*
+///
+/// ```
+/// use metro::Event;
+///
+/// let events = [
+///     Event::station(0, "Station 1"),
+///     Event::SplitTrack(0.into(), 1.into()),
+///     Event::station(1, "Station 2"),
+///     Event::JoinTrack(1.into(), 0.into()),
+/// ];
+///
+/// let bytes = metro::to_bytes(&events);
+/// let decoded = metro::from_bytes(&bytes).unwrap();
+///
+/// assert_eq!(metro::to_string(&events).unwrap(), metro::to_string(&decoded).unwrap());
+/// ```
+///
+/// Every part of a [`Station`] is kept:
+///
+/// ```
+/// use metro::{Event, Level, Severity, Station, Status, StyledText};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let styled = StyledText::new().push("Deploy ").push("api").bold().color([0, 200, 0]);
+/// let station = Station::from(styled)
+///     .with_time(UNIX_EPOCH + Duration::from_millis(1_700_000_000_123))
+///     .with_duration(Duration::from_secs(3))
+///     .with_id("deploy")
+///     .with_link("https://ci.example.com/1")
+///     .with_badge(2)
+///     .with_severity(Severity::Warn)
+///     .with_status(Status::Running)
+///     .with_nested([Event::station(0, Station::block("Output", ["ok"]))])
+///     .with_field("env", "prod")
+///     .with_fields([("region", "eu")])
+///     .with_tag("ci")
+///     .with_level(Level::Info);
+/// let events = [Event::station(0, station), Event::SetStatus("deploy".into(), Status::Done)];
+///
+/// assert_eq!(metro::from_bytes(&metro::to_bytes(&events)).unwrap(), events);
+/// ```
+///
+/// [`Event`]: enum.Event.html
+/// [`Station`]: struct.Station.html
+/// [`from_bytes`]: fn.from_bytes.html
+pub fn to_bytes(events: &[Event]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 1 + events.len() * 4);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    write_uint(&mut bytes, events.len());
+
+    for event in events {
+        write_event(&mut bytes, event);
+    }
+
+    bytes
+}
+
+/// Deserialize events produced by [`to_bytes`].
+///
+/// Returns an error of kind [`InvalidData`] if `bytes` is not
+/// a valid event stream, or was written by an unsupported
+/// version of the format. Streams written by older versions
+/// are supported.
+///
+/// *See also [`to_bytes`].*
+///
+/// [`to_bytes`]: fn.to_bytes.html
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_bytes(bytes: &[u8]) -> io::Result<Vec<Event<'static>>> {
+    let mut r = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+
+    if r.take(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("not a metro event stream"));
+    }
+    let version = r.byte()?;
+    if version == 0 || version > VERSION {
+        return Err(invalid_data(format!(
+            "unsupported event stream version {}",
+            version
+        )));
+    }
+
+    let len = r.uint()?;
+    // Every event is at least one byte, which bounds
+    // the allocation for corrupted lengths
+    let mut events = Vec::with_capacity(len.min(bytes.len()));
+    for _ in 0..len {
+        events.push(r.event(version)?);
+    }
+
+    if r.pos != bytes.len() {
+        return Err(invalid_data("trailing bytes after event stream"));
+    }
+
+    Ok(events)
+}
+
+/// Serialize `event` as a record prefixed by its length,
+/// e.g. to append it to a journal.
+pub(crate) fn write_record(bytes: &mut Vec<u8>, event: &Event) {
+    let mut record = Vec::new();
+    write_event(&mut record, event);
+    write_uint(bytes, record.len());
+    bytes.extend_from_slice(&record);
+}
+
+/// Deserialize the records of `bytes` produced by [`write_record`]
+/// with the format `version`, up to the first incomplete one, e.g.
+/// one being written, or cut short by a crash.
+///
+/// Returns the events, along with the number of bytes they span.
+pub(crate) fn read_records(bytes: &[u8], version: u8) -> io::Result<(Vec<Event<'static>>, usize)> {
+    let mut r = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    let mut events = Vec::new();
+    loop {
+        let start = r.pos;
+        let record = match r.uint().and_then(|len| r.take(len)) {
+            Ok(record) => record,
+            Err(_) => return Ok((events, start)),
+        };
+        let mut record = Reader {
+            bytes: record,
+            pos: 0,
+            depth: 0,
+        };
+        events.push(record.event(version)?);
+        if record.pos != record.bytes.len() {
+            return Err(invalid_data("trailing bytes after journal record"));
+        }
+    }
+}
+
+fn write_event(bytes: &mut Vec<u8>, event: &Event) {
+    match event {
+        Event::StartTrack(track_id) => {
+            bytes.push(TAG_START_TRACK);
+            write_uint(bytes, track_id.0);
+        }
+        Event::StartTracks(track_ids) => {
+            bytes.push(TAG_START_TRACKS);
+            write_track_ids(bytes, track_ids);
+        }
+        Event::StopTrack(track_id) => {
+            bytes.push(TAG_STOP_TRACK);
+            write_uint(bytes, track_id.0);
+        }
+        Event::Station(track_id, station) => {
+            bytes.push(TAG_STATION);
+            write_uint(bytes, track_id.0);
+            write_station(bytes, station);
+        }
+        Event::StationKv(track_id, pairs) => {
+            bytes.push(TAG_STATION_KV);
+            write_uint(bytes, track_id.0);
+            write_uint(bytes, pairs.len());
+            for (key, value) in pairs {
+                write_str(bytes, key);
+                write_str(bytes, value);
+            }
+        }
+        Event::SharedStation(track_ids, station) => {
+            bytes.push(TAG_SHARED_STATION);
+            write_track_ids(bytes, track_ids);
+            write_station(bytes, station);
+        }
+        Event::SplitTrack(from, to) => {
+            bytes.push(TAG_SPLIT_TRACK);
+            write_uint(bytes, from.0);
+            write_uint(bytes, to.0);
+        }
+        Event::JoinTrack(from, to) => {
+            bytes.push(TAG_JOIN_TRACK);
+            write_uint(bytes, from.0);
+            write_uint(bytes, to.0);
+        }
+        Event::NoEvent => bytes.push(TAG_NO_EVENT),
+        Event::Link(from, to) => {
+            bytes.push(TAG_LINK);
+            write_uint(bytes, from.0);
+            write_uint(bytes, to.0);
+        }
+        Event::Reference(from, to) => {
+            bytes.push(TAG_REFERENCE);
+            write_str(bytes, from);
+            write_str(bytes, to);
+        }
+        Event::UpdateStation(id, text) => {
+            bytes.push(TAG_UPDATE_STATION);
+            write_str(bytes, id);
+            write_str(bytes, text);
+        }
+        Event::SetStatus(id, status) => {
+            bytes.push(TAG_SET_STATUS);
+            write_str(bytes, id);
+            bytes.push(status_byte(*status));
+        }
+    }
+}
+
+fn status_byte(status: Status) -> u8 {
+    match status {
+        Status::Pending => 0,
+        Status::Running => 1,
+        Status::Done => 2,
+        Status::Failed => 3,
+    }
+}
+
+fn status_of(byte: u8) -> io::Result<Status> {
+    match byte {
+        0 => Ok(Status::Pending),
+        1 => Ok(Status::Running),
+        2 => Ok(Status::Done),
+        3 => Ok(Status::Failed),
+        status => Err(invalid_data(format!("unknown station status {}", status))),
+    }
+}
+
+fn write_uint(bytes: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    write_uint(bytes, s.len());
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn write_track_ids(bytes: &mut Vec<u8>, track_ids: &[TrackId]) {
+    write_uint(bytes, track_ids.len());
+    for track_id in track_ids {
+        write_uint(bytes, track_id.0);
+    }
+}
+
+fn write_station(bytes: &mut Vec<u8>, station: &Station) {
+    write_str(bytes, station.text());
+    // Times before the epoch cannot be represented, and are dropped
+    let time = station
+        .time()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+    write_duration(bytes, time);
+    write_duration(bytes, station.duration());
+    match station.id() {
+        Some(id) => {
+            bytes.push(1);
+            write_str(bytes, id);
+        }
+        None => bytes.push(0),
+    }
+    bytes.push(match station.level() {
+        None => 0,
+        Some(Level::Trace) => 1,
+        Some(Level::Debug) => 2,
+        Some(Level::Info) => 3,
+        Some(Level::Warn) => 4,
+        Some(Level::Error) => 5,
+    });
+    write_uint(bytes, station.tags().count());
+    for tag in station.tags() {
+        write_str(bytes, tag);
+    }
+    write_uint(bytes, station.fields().count());
+    for (name, value) in station.fields() {
+        write_str(bytes, name);
+        write_str(bytes, value);
+    }
+    match station.styled() {
+        Some(styled) => {
+            bytes.push(1);
+            write_uint(bytes, styled.spans().len());
+            for span in styled.spans() {
+                write_span(bytes, span);
+            }
+        }
+        None => bytes.push(0),
+    }
+    match station.link() {
+        Some(link) => {
+            bytes.push(1);
+            write_str(bytes, link);
+        }
+        None => bytes.push(0),
+    }
+    match station.badge() {
+        Some(badge) => {
+            bytes.push(1);
+            write_uint(bytes, badge);
+        }
+        None => bytes.push(0),
+    }
+    bytes.push(match station.severity() {
+        None => 0,
+        Some(Severity::Info) => 1,
+        Some(Severity::Warn) => 2,
+        Some(Severity::Error) => 3,
+    });
+    match station.status() {
+        Some(status) => bytes.push(1 + status_byte(status)),
+        None => bytes.push(0),
+    }
+    write_uint(bytes, station.nested().len());
+    for event in station.nested() {
+        write_event(bytes, event);
+    }
+}
+
+fn write_span(bytes: &mut Vec<u8>, span: &Span) {
+    write_str(bytes, &span.text);
+    match span.color {
+        Some(color) => {
+            bytes.push(1);
+            bytes.extend_from_slice(&color);
+        }
+        None => bytes.push(0),
+    }
+    let styles = [span.bold, span.dim, span.italic, span.underline];
+    bytes.push(
+        styles
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &style)| bits | (u8::from(style) << i)),
+    );
+}
+
+fn write_duration(bytes: &mut Vec<u8>, duration: Option<Duration>) {
+    match duration {
+        Some(duration) => {
+            bytes.push(1);
+            write_uint(bytes, duration.as_secs() as usize);
+            write_uint(bytes, duration.subsec_nanos() as usize);
+        }
+        None => bytes.push(0),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// The nesting of the station being read.
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid_data("unexpected end of event stream"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self) -> io::Result<usize> {
+        let mut n = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as usize;
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(invalid_data("integer overflow in event stream"));
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.uint()?;
+        let s = std::str::from_utf8(self.take(len)?)
+            .map_err(|_| invalid_data("station text is not valid UTF-8"))?;
+        Ok(s.to_owned())
+    }
+
+    fn duration(&mut self) -> io::Result<Option<Duration>> {
+        if self.byte()? == 0 {
+            return Ok(None);
+        }
+        let secs = self.uint()? as u64;
+        let nanos = self.uint()?;
+        if nanos >= 1_000_000_000 {
+            return Err(invalid_data("invalid duration in event stream"));
+        }
+        Ok(Some(Duration::new(secs, nanos as u32)))
+    }
+
+    fn span(&mut self) -> io::Result<Span> {
+        let text = self.string()?;
+        let color = match self.byte()? {
+            0 => None,
+            _ => {
+                let color = self.take(3)?;
+                Some([color[0], color[1], color[2]])
+            }
+        };
+        let styles = self.byte()?;
+        Ok(Span {
+            text,
+            color,
+            bold: styles & 1 != 0,
+            dim: styles & 2 != 0,
+            italic: styles & 4 != 0,
+            underline: styles & 8 != 0,
+        })
+    }
+
+    fn track_id(&mut self) -> io::Result<TrackId> {
+        self.uint().map(TrackId)
+    }
+
+    fn track_ids(&mut self) -> io::Result<Vec<TrackId>> {
+        let len = self.uint()?;
+        // Every track ID is at least one byte, which bounds
+        // the allocation for corrupted lengths
+        let mut track_ids = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            track_ids.push(self.track_id()?);
+        }
+        Ok(track_ids)
+    }
+
+    fn event(&mut self, version: u8) -> io::Result<Event<'static>> {
+        let event = match self.byte()? {
+            TAG_START_TRACK => Event::StartTrack(self.track_id()?),
+            TAG_START_TRACKS => Event::StartTracks(Cow::Owned(self.track_ids()?)),
+            TAG_STOP_TRACK => Event::StopTrack(self.track_id()?),
+            TAG_STATION => Event::Station(self.track_id()?, self.station(version)?),
+            TAG_STATION_KV => {
+                let track_id = self.track_id()?;
+                let len = self.uint()?;
+                // Every pair is at least two bytes, which bounds
+                // the allocation for corrupted lengths
+                let mut pairs = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    pairs.push((self.string()?.into(), self.string()?.into()));
+                }
+                Event::StationKv(track_id, pairs)
+            }
+            TAG_SHARED_STATION => {
+                Event::SharedStation(Cow::Owned(self.track_ids()?), self.station(version)?)
+            }
+            TAG_SPLIT_TRACK => Event::SplitTrack(self.track_id()?, self.track_id()?),
+            TAG_JOIN_TRACK => Event::JoinTrack(self.track_id()?, self.track_id()?),
+            TAG_NO_EVENT => Event::NoEvent,
+            TAG_LINK => Event::Link(self.track_id()?, self.track_id()?),
+            TAG_REFERENCE => Event::Reference(self.string()?.into(), self.string()?.into()),
+            // Updates were added in version 3
+            TAG_UPDATE_STATION => {
+                Event::UpdateStation(self.string()?.into(), self.string()?.into())
+            }
+            TAG_SET_STATUS => {
+                let id = self.string()?.into();
+                Event::SetStatus(id, status_of(self.byte()?)?)
+            }
+            tag => return Err(invalid_data(format!("unknown event tag {}", tag))),
+        };
+        Ok(event)
+    }
+
+    fn station(&mut self, version: u8) -> io::Result<Station<'static>> {
+        let text = self.string()?;
+        let mut station = Station::new(text.clone());
+        if let Some(time) = self.duration()? {
+            let time = UNIX_EPOCH
+                .checked_add(time)
+                .ok_or_else(|| invalid_data("invalid station time"))?;
+            station = station.with_time(time);
+        }
+        if let Some(duration) = self.duration()? {
+            station = station.with_duration(duration);
+        }
+        // Station IDs, levels, tags and fields were added in version 2
+        if version >= 2 {
+            if self.byte()? != 0 {
+                station = station.with_id(self.string()?);
+            }
+            let level = match self.byte()? {
+                0 => None,
+                1 => Some(Level::Trace),
+                2 => Some(Level::Debug),
+                3 => Some(Level::Info),
+                4 => Some(Level::Warn),
+                5 => Some(Level::Error),
+                level => return Err(invalid_data(format!("unknown station level {}", level))),
+            };
+            if let Some(level) = level {
+                station = station.with_level(level);
+            }
+            for _ in 0..self.uint()? {
+                station = station.with_tag(self.string()?);
+            }
+            for _ in 0..self.uint()? {
+                station = station.with_field(self.string()?, self.string()?);
+            }
+        }
+        // Styled text, links, badges, severities, statuses and
+        // nested stations were added in version 4
+        if version >= 4 {
+            station = self.station_extras(station, text, version)?;
+        }
+        Ok(station)
+    }
+
+    fn station_extras(
+        &mut self,
+        mut station: Station<'static>,
+        text: String,
+        version: u8,
+    ) -> io::Result<Station<'static>> {
+        if self.byte()? != 0 {
+            let len = self.uint()?;
+            // Every span is at least three bytes, which bounds
+            // the allocation for corrupted lengths
+            let mut spans = Vec::with_capacity(len.min(self.bytes.len()));
+            for _ in 0..len {
+                spans.push(self.span()?);
+            }
+            let styled = StyledText::from_spans(spans);
+            if styled.to_plain() != text {
+                return Err(invalid_data("styled text does not match station text"));
+            }
+            station = station.with_styled(styled);
+        }
+        if self.byte()? != 0 {
+            station = station.with_link(self.string()?);
+        }
+        if self.byte()? != 0 {
+            station = station.with_badge(self.uint()?);
+        }
+        let severity = match self.byte()? {
+            0 => None,
+            1 => Some(Severity::Info),
+            2 => Some(Severity::Warn),
+            3 => Some(Severity::Error),
+            severity => {
+                return Err(invalid_data(format!(
+                    "unknown station severity {}",
+                    severity
+                )))
+            }
+        };
+        if let Some(severity) = severity {
+            station = station.with_severity(severity);
+        }
+        if let Some(status) = self.byte()?.checked_sub(1) {
+            station = station.with_status(status_of(status)?);
+        }
+        let len = self.uint()?;
+        if len > 0 {
+            if self.depth == MAX_NESTING {
+                return Err(invalid_data("stations nested too deeply"));
+            }
+            self.depth += 1;
+            // Every event is at least one byte, which bounds
+            // the allocation for corrupted lengths
+            let mut nested = Vec::with_capacity(len.min(self.bytes.len()));
+            for _ in 0..len {
+                nested.push(self.event(version)?);
+            }
+            self.depth -= 1;
+            station = station.with_nested(nested);
+        }
+        Ok(station)
+    }
+}
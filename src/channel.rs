@@ -0,0 +1,145 @@
+use crate::events::Event;
+use crate::sink::EventSink;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Create a [`MetroSender`], to emit events from many threads, and
+/// the [`MetroCollector`] receiving them, to render them afterwards.
+///
+/// ```
+/// use metro::Event;
+/// use std::thread;
+///
+/// let (sender, collector) = metro::channel();
+/// let workers = (0..3)
+///     .map(|i: usize| {
+///         let sender = sender.clone();
+///         thread::spawn(move || {
+///             sender.send(Event::StartTrack(i.into()));
+///             sender.send(Event::station(i, format!("Worker {}", i)));
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// drop(sender);
+///
+/// let events = collector.into_events();
+/// assert_eq!(events.len(), 6);
+/// assert!(metro::to_string(&events).unwrap().contains("Worker 2"));
+/// ```
+///
+/// [`MetroSender`]: struct.MetroSender.html
+/// [`MetroCollector`]: struct.MetroCollector.html
+pub fn channel() -> (MetroSender, MetroCollector) {
+    let (tx, rx) = mpsc::channel();
+    let sender = MetroSender {
+        tx,
+        sequence: Arc::new(AtomicUsize::new(0)),
+    };
+    let collector = MetroCollector {
+        rx,
+        received: Vec::new(),
+        by_time: false,
+    };
+    (sender, collector)
+}
+
+/// An event, numbered in the order it was sent across every sender.
+#[derive(Debug)]
+struct Message {
+    sequence: usize,
+    time: SystemTime,
+    event: Event<'static>,
+}
+
+/// The sending half of a [`channel`], which can be cloned
+/// and sent to other threads.
+///
+/// [`channel`]: fn.channel.html
+#[derive(Clone, Debug)]
+pub struct MetroSender {
+    tx: Sender<Message>,
+    /// The number of the next event sent, shared by every clone.
+    sequence: Arc<AtomicUsize>,
+}
+
+impl MetroSender {
+    /// Send `event` to the collector, or drop it if the
+    /// collector was dropped.
+    pub fn send(&self, event: Event<'static>) {
+        let message = Message {
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+            time: SystemTime::now(),
+            event,
+        };
+        let _ = self.tx.send(message);
+    }
+}
+
+impl EventSink<'static> for MetroSender {
+    fn emit(&mut self, event: Event<'static>) {
+        self.send(event);
+    }
+}
+
+/// The receiving half of a [`channel`], ordering events by the
+/// order they were sent in, across every sender, or by time.
+///
+/// [`channel`]: fn.channel.html
+#[derive(Debug)]
+pub struct MetroCollector {
+    rx: Receiver<Message>,
+    received: Vec<Message>,
+    by_time: bool,
+}
+
+impl MetroCollector {
+    /// Order events by time instead: the [time] of stations which
+    /// have one, and the time they were sent otherwise. Events sent
+    /// at the same time keep the order they were sent in.
+    ///
+    /// [time]: struct.Station.html#method.with_time
+    pub fn by_time(mut self, by_time: bool) -> Self {
+        self.by_time = by_time;
+        self
+    }
+
+    /// Returns the events received so far, in order,
+    /// without waiting for more.
+    pub fn events(&mut self) -> Vec<Event<'static>> {
+        self.received.extend(self.rx.try_iter());
+        self.sort();
+        self.received
+            .iter()
+            .map(|message| message.event.clone())
+            .collect()
+    }
+
+    /// Returns every event, in order, once every sender is dropped.
+    pub fn into_events(mut self) -> Vec<Event<'static>> {
+        self.received.extend(self.rx.iter());
+        self.sort();
+        self.received
+            .into_iter()
+            .map(|message| message.event)
+            .collect()
+    }
+
+    fn sort(&mut self) {
+        if self.by_time {
+            self.received.sort_by_key(|message| {
+                let time = match &message.event {
+                    Event::Station(_, station) | Event::SharedStation(_, station) => station.time(),
+                    _ => None,
+                };
+                (time.unwrap_or(message.time), message.sequence)
+            });
+        } else {
+            self.received.sort_by_key(|message| message.sequence);
+        }
+    }
+}
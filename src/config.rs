@@ -0,0 +1,181 @@
+use crate::error::invalid_data;
+use crate::events::{Heat, RenderingSettings, TimeColumn};
+use crate::station::{Level, Severity};
+use std::env;
+use std::io;
+
+/// The settings which can be configured by end users,
+/// along with the environment variable overriding each.
+const SETTINGS: &[(&str, &str)] = &[
+    ("splat", "METRO_SPLAT"),
+    ("color", "METRO_COLOR"),
+    ("rounded", "METRO_ROUNDED"),
+    ("time_column", "METRO_TIME_COLUMN"),
+    ("time_format", "METRO_TIME_FORMAT"),
+    ("heat", "METRO_HEAT"),
+    ("detached_marker", "METRO_DETACHED_MARKER"),
+    ("implicit_root", "METRO_IMPLICIT_ROOT"),
+    ("min_level", "METRO_MIN_LEVEL"),
+    ("row_numbers", "METRO_ROW_NUMBERS"),
+    ("ruler", "METRO_RULER"),
+    ("elastic_columns", "METRO_ELASTIC_COLUMNS"),
+    ("summary", "METRO_SUMMARY"),
+    ("info_color", "METRO_INFO_COLOR"),
+    ("warn_color", "METRO_WARN_COLOR"),
+    ("error_color", "METRO_ERROR_COLOR"),
+];
+
+impl RenderingSettings {
+    /// Create `RenderingSettings` from the `METRO_*` environment
+    /// variables, e.g. `METRO_SPLAT=2` or `METRO_TIME_COLUMN=delta`.
+    /// Settings without a variable keep their default.
+    ///
+    /// Following [NO_COLOR], color is disabled if `NO_COLOR`
+    /// is set to anything, unless `METRO_COLOR` is set too.
+    ///
+    /// Returns an error of kind [`InvalidData`] if a variable
+    /// holds an invalid value.
+    ///
+    /// *See [`RenderingSettings::from_toml`] for every setting.*
+    ///
+    /// [NO_COLOR]: https://no-color.org/
+    /// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+    /// [`RenderingSettings::from_toml`]: #method.from_toml
+    pub fn from_env() -> io::Result<Self> {
+        let mut rdr = Self::default();
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            rdr = rdr.color(false);
+        }
+        for (key, var) in SETTINGS {
+            if let Ok(value) = env::var(var) {
+                rdr = rdr.set(key, &value)?;
+            }
+        }
+        Ok(rdr)
+    }
+
+    /// Create `RenderingSettings` from a TOML document, e.g. a dotfile.
+    /// Settings missing from `document` keep their default.
+    ///
+    /// Only scalar settings are covered, along with the [severity colors]
+    /// as `#rrggbb`. Settings tied to track IDs, e.g. labels, are left
+    /// to the host program, and track colors and [status] markers are
+    /// fixed.
+    ///
+    /// Requires the `toml` feature.
+    ///
+    /// Returns an error of kind [`InvalidData`] if `document` is not valid
+    /// TOML, or holds an unknown setting or an invalid value.
+    ///
+    /// ```
+    /// use metro::RenderingSettings;
+    ///
+    /// let rdr = RenderingSettings::from_toml(r##"
+    ///     splat = 2
+    ///     color = false
+    ///     rounded = true
+    ///     time_column = "delta" # or "hidden", "absolute", "relative"
+    ///     time_format = "%H:%M"
+    ///     heat = "marker"       # or "off", "text"
+    ///     detached_marker = "○"
+    ///     implicit_root = true
+    ///     min_level = "info"    # or "trace", "debug", "warn", "error"
+    ///     row_numbers = true
+    ///     ruler = true
+    ///     elastic_columns = true
+    ///     summary = true
+    ///     info_color = "#0000ff"
+    ///     warn_color = "#ff8000"
+    ///     error_color = "#ff0000"
+    /// "##).unwrap();
+    /// assert_eq!(rdr.get_severity_color(metro::Severity::Warn), [255, 128, 0]);
+    ///
+    /// assert!(RenderingSettings::from_toml("splat = -1").is_err());
+    /// assert!(RenderingSettings::from_toml("colour = false").is_err());
+    /// assert!(RenderingSettings::from_toml("warn_color = \"orange\"").is_err());
+    /// ```
+    ///
+    /// [severity colors]: #method.severity_color
+    /// [status]: struct.Station.html#method.with_status
+    /// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+    #[cfg(feature = "toml")]
+    pub fn from_toml(document: &str) -> io::Result<Self> {
+        let table = document.parse::<toml::Table>().map_err(invalid_data)?;
+
+        let mut rdr = Self::default();
+        for (key, value) in table.iter() {
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Boolean(b) => b.to_string(),
+                _ => {
+                    return Err(invalid_data(format!(
+                        "invalid {}: {}",
+                        key,
+                        value.type_str()
+                    )))
+                }
+            };
+            rdr = rdr.set(key, &value)?;
+        }
+        Ok(rdr)
+    }
+
+    /// Apply the setting `key`, parsed from `value`.
+    fn set(self, key: &str, value: &str) -> io::Result<Self> {
+        let invalid = || invalid_data(format!("invalid {}: {:?}", key, value));
+        let parse_bool = |value: &str| match value {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(invalid()),
+        };
+        let rgb = |value: &str| parse_rgb(value).ok_or_else(invalid);
+
+        Ok(match key {
+            "splat" => self.splat(value.parse().map_err(|_| invalid())?),
+            "color" => self.color(parse_bool(value)?),
+            "rounded" => self.rounded(parse_bool(value)?),
+            "time_column" => self.time_column(match value {
+                "hidden" => TimeColumn::Hidden,
+                "absolute" => TimeColumn::Absolute,
+                "delta" => TimeColumn::Delta,
+                "relative" => TimeColumn::Relative,
+                _ => return Err(invalid()),
+            }),
+            "time_format" => self.time_format(value),
+            "heat" => self.heat(match value {
+                "off" => Heat::Off,
+                "marker" => Heat::Marker,
+                "text" => Heat::Text,
+                _ => return Err(invalid()),
+            }),
+            "detached_marker" => self.detached_marker(value),
+            "implicit_root" => self.implicit_root(parse_bool(value)?),
+            "min_level" => self.min_level(match value {
+                "trace" => Level::Trace,
+                "debug" => Level::Debug,
+                "info" => Level::Info,
+                "warn" => Level::Warn,
+                "error" => Level::Error,
+                _ => return Err(invalid()),
+            }),
+            "row_numbers" => self.row_numbers(parse_bool(value)?),
+            "ruler" => self.ruler(parse_bool(value)?),
+            "elastic_columns" => self.elastic_columns(parse_bool(value)?),
+            "summary" => self.summary(parse_bool(value)?),
+            "info_color" => self.severity_color(Severity::Info, rgb(value)?),
+            "warn_color" => self.severity_color(Severity::Warn, rgb(value)?),
+            "error_color" => self.severity_color(Severity::Error, rgb(value)?),
+            _ => return Err(invalid_data(format!("unknown setting {}", key))),
+        })
+    }
+}
+
+/// Parse a `#rrggbb` color.
+fn parse_rgb(value: &str) -> Option<[u8; 3]> {
+    let hex = value
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
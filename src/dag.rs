@@ -0,0 +1,168 @@
+use crate::events::{Event, TrackId};
+use crate::station::Station;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Lay out a directed graph as events, one station per node in
+/// topological order, e.g. to render graphs from other formats.
+///
+/// `edges` are `(from, to)` indices into `nodes`. Each node continues
+/// the track of its leftmost parent, joining the tracks of the other
+/// parents, and splits a track for each child after the first.
+///
+/// As tracks cannot go backwards, edges closing a cycle are rendered
+/// as an [`Event::Reference`] right after the station they leave,
+/// between the IDs of both stations, if they have one.
+///
+/// [`Event::Reference`]: ../enum.Event.html#variant.Reference
+pub(crate) fn layout<'a>(nodes: Vec<Station<'a>>, edges: &[(usize, usize)]) -> Vec<Event<'a>> {
+    let order = topological_order(nodes.len(), edges);
+    let mut position = vec![0; nodes.len()];
+    for (i, &node) in order.iter().enumerate() {
+        position[node] = i;
+    }
+
+    let mut children = vec![Vec::new(); nodes.len()];
+    let mut back_edges = vec![Vec::new(); nodes.len()];
+    for &(from, to) in edges.iter() {
+        if from >= nodes.len() || to >= nodes.len() {
+            continue;
+        }
+        let targets = match position[from] < position[to] {
+            true => &mut children[from],
+            false => &mut back_edges[from],
+        };
+        if !targets.contains(&to) {
+            targets.push(to);
+        }
+    }
+    for targets in children.iter_mut().chain(back_edges.iter_mut()) {
+        targets.sort_by_key(|&target| position[target]);
+    }
+    let ids = nodes
+        .iter()
+        .map(|station| station.id().map(|id| Cow::Owned(id.to_owned())))
+        .collect::<Vec<Option<Cow<'a, str>>>>();
+
+    let mut stations = nodes.into_iter().map(Some).collect::<Vec<_>>();
+    let mut events = Vec::new();
+    // The live tracks in rendering order, with the node each leads to
+    let mut lanes: Vec<(TrackId, usize)> = Vec::new();
+    let mut next_id = 0;
+
+    for node in order {
+        let mut pending = lanes
+            .iter()
+            .filter(|&&(_, target)| target == node)
+            .map(|&(track_id, _)| track_id);
+        let track_id = match pending.next() {
+            Some(track_id) => {
+                for other in pending.collect::<Vec<_>>() {
+                    events.push(Event::JoinTrack(other, track_id));
+                    lanes.retain(|&(t, _)| t != other);
+                }
+                track_id
+            }
+            None => {
+                let track_id = TrackId(next_id);
+                next_id += 1;
+                events.push(Event::StartTrack(track_id));
+                lanes.push((track_id, node));
+                track_id
+            }
+        };
+
+        events.push(Event::Station(track_id, stations[node].take().unwrap()));
+        for &target in back_edges[node].iter() {
+            if let (Some(from), Some(to)) = (&ids[node], &ids[target]) {
+                events.push(Event::Reference(from.clone(), to.clone()));
+            }
+        }
+
+        let lane = lanes.iter().position(|&(t, _)| t == track_id).unwrap();
+        match children[node].split_first() {
+            Some((&first, rest)) => {
+                lanes[lane].1 = first;
+                for &child in rest.iter() {
+                    let child_id = TrackId(next_id);
+                    next_id += 1;
+                    events.push(Event::SplitTrack(track_id, child_id));
+                    lanes.insert(lane + 1, (child_id, child));
+                }
+            }
+            None => {
+                events.push(Event::StopTrack(track_id));
+                lanes.remove(lane);
+            }
+        }
+    }
+
+    events
+}
+
+/// Order the nodes such that every node comes after its parents,
+/// preferring the order of `0..len` among independent nodes.
+///
+/// Cycles are broken by ignoring the edges leading back to a node
+/// being visited, when visiting nodes depth-first in that order.
+fn topological_order(len: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut children = vec![Vec::new(); len];
+    for &(from, to) in edges.iter() {
+        if from < len && to < len && from != to {
+            children[from].push(to);
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        Visiting,
+        Done,
+    }
+    let mut visits = vec![Visit::New; len];
+    let mut parents = vec![0usize; len];
+    for root in 0..len {
+        if visits[root] != Visit::New {
+            continue;
+        }
+        visits[root] = Visit::Visiting;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, i)) = stack.last_mut() {
+            let node = *node;
+            let Some(&child) = children[node].get(*i) else {
+                visits[node] = Visit::Done;
+                stack.pop();
+                continue;
+            };
+            if visits[child] == Visit::Visiting {
+                // Closes a cycle
+                children[node].remove(*i);
+                continue;
+            }
+            *i += 1;
+            parents[child] += 1;
+            if visits[child] == Visit::New {
+                visits[child] = Visit::Visiting;
+                stack.push((child, 0));
+            }
+        }
+    }
+
+    let mut ready = (0..len)
+        .filter(|&node| parents[node] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(len);
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for &child in children[node].iter() {
+            parents[child] -= 1;
+            if parents[child] == 0 {
+                ready.push(Reverse(child));
+            }
+        }
+    }
+
+    order
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::dag;
+use crate::error::invalid_line;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Parse a [Graphviz DOT] digraph, and lay it out as events, one
+/// station per node, connected by tracks along the edges.
+///
+/// Nodes are rendered in topological order, and otherwise in the
+/// order they first appear. A node is rendered as its `label`, or its
+/// ID, and gets its ID as [station ID], and its `URL` or `href` as
+/// [link]. Edges closing a cycle are rendered as a [reference] back
+/// to the earlier node.
+///
+/// Only a restricted subset of DOT is supported: a single, possibly
+/// `strict`, `digraph` of node, edge and attribute statements.
+/// Other attributes are ignored. Ports are ignored.
+///
+/// ```
+/// let events = metro::from_dot(r#"
+///     digraph build {
+///         checkout -> build -> test;
+///         build -> lint;
+///         test -> deploy [color=red];
+///         lint -> deploy;
+///         deploy [label="Deploy to prod"];
+///     }
+/// "#).unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("Deploy to prod"));
+///
+/// // Cycles are closed by a reference to the first node
+/// let events = metro::from_dot("digraph { a -> b -> c -> a; }").unwrap();
+/// assert!(events.contains(&metro::Event::Reference("c".into(), "a".into())));
+///
+/// let metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("c ↩ see #1 (a)"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `source` is not a
+/// digraph, or uses unsupported syntax, e.g. subgraphs.
+///
+/// [Graphviz DOT]: https://graphviz.org/doc/info/lang.html
+/// [station ID]: struct.Station.html#method.with_id
+/// [link]: struct.Station.html#method.with_link
+/// [reference]: enum.Event.html#variant.Reference
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_dot(source: &str) -> io::Result<Vec<Event<'static>>> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        ids: HashMap::new(),
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    parser.graph()?;
+
+    let stations = parser
+        .nodes
+        .into_iter()
+        .map(|(id, attrs)| {
+            let text = attrs.get("label").cloned().unwrap_or_else(|| id.clone());
+            let mut station = Station::new(text).with_id(id);
+            if let Some(link) = attrs.get("URL").or_else(|| attrs.get("href")) {
+                station = station.with_link(link.clone());
+            }
+            station
+        })
+        .collect();
+
+    Ok(dag::layout(stations, &parser.edges))
+}
+
+#[derive(PartialEq, Debug)]
+enum Token {
+    /// An identifier, numeral or quoted string.
+    Id(String),
+    /// A keyword, lowercased, as keywords are case-insensitive.
+    Keyword(&'static str),
+    Arrow,
+    UndirectedEdge,
+    Punct(char),
+}
+
+/// A token, with the line it starts on.
+struct Spanned {
+    token: Token,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> io::Result<Vec<Spanned>> {
+    const KEYWORDS: [&str; 6] = ["strict", "graph", "digraph", "node", "edge", "subgraph"];
+
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // Lines starting with `#` are preprocessor output
+            '#' | '/' if c == '#' || chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == Some('*') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            previous = Some(c);
+                        }
+                        None => return Err(invalid_line(line, "unterminated comment")),
+                    }
+                }
+                continue;
+            }
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                Token::Arrow
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                Token::UndirectedEdge
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Only quotes are escaped, other escapes are
+                        // label formatting, e.g. `\n`, kept as is
+                        Some('\\') if chars.peek() == Some(&'"') => {
+                            s.push(chars.next().unwrap());
+                        }
+                        // Line continuations
+                        Some('\\') if chars.peek() == Some(&'\n') => {
+                            chars.next();
+                            line += 1;
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            s.push(c);
+                        }
+                        None => return Err(invalid_line(line, "unterminated string")),
+                    }
+                }
+                Token::Id(s)
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || !c.is_ascii() => {
+                let mut s = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii())
+                {
+                    s.push(c);
+                }
+                match KEYWORDS.iter().find(|k| k.eq_ignore_ascii_case(&s)) {
+                    Some(keyword) => Token::Keyword(keyword),
+                    None => Token::Id(s),
+                }
+            }
+            '<' => return Err(invalid_line(line, "HTML strings are not supported")),
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' | '+' => Token::Punct(c),
+            c => return Err(invalid_line(line, format!("unexpected character `{}`", c))),
+        };
+        tokens.push(Spanned { token, line });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// The index of every node into `nodes`, by ID.
+    ids: HashMap<String, usize>,
+    /// Every node, with its attributes, in order of appearance.
+    nodes: Vec<(String, HashMap<String, String>)>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl Parser {
+    fn graph(&mut self) -> io::Result<()> {
+        self.eat(&Token::Keyword("strict"));
+        if self.eat(&Token::Keyword("graph")) {
+            return Err(self.error("undirected graphs are not supported"));
+        }
+        self.expect(&Token::Keyword("digraph"), "`digraph`")?;
+        if matches!(self.peek(), Some(Token::Id(_))) {
+            self.id()?;
+        }
+        self.expect(&Token::Punct('{'), "`{`")?;
+
+        while !self.eat(&Token::Punct('}')) {
+            self.statement()?;
+            self.eat(&Token::Punct(';'));
+        }
+
+        match self.tokens.get(self.pos) {
+            Some(token) => Err(invalid_line(token.line, "expected the end of the graph")),
+            None => Ok(()),
+        }
+    }
+
+    fn statement(&mut self) -> io::Result<()> {
+        match self.peek() {
+            Some(Token::Keyword("graph" | "node" | "edge")) => {
+                self.pos += 1;
+                self.attributes()?;
+            }
+            Some(Token::Keyword("subgraph") | Token::Punct('{')) => {
+                return Err(self.error("subgraphs are not supported"));
+            }
+            Some(Token::Id(_)) => {
+                let id = self.id()?;
+                if self.eat(&Token::Punct('=')) {
+                    // Graph attribute
+                    self.id()?;
+                    return Ok(());
+                }
+
+                let mut from = self.node(id);
+                self.port()?;
+                let mut nodes = vec![from];
+                loop {
+                    if self.eat(&Token::UndirectedEdge) {
+                        return Err(self.error("undirected edges are not supported"));
+                    }
+                    if !self.eat(&Token::Arrow) {
+                        break;
+                    }
+                    if matches!(
+                        self.peek(),
+                        Some(Token::Keyword("subgraph") | Token::Punct('{'))
+                    ) {
+                        return Err(self.error("subgraphs are not supported"));
+                    }
+                    let id = self.id()?;
+                    let to = self.node(id);
+                    self.port()?;
+                    self.edges.push((from, to));
+                    nodes.push(to);
+                    from = to;
+                }
+
+                let attrs = self.attributes()?;
+                // Attributes of edge statements are edge attributes
+                if nodes.len() == 1 {
+                    self.nodes[from].1.extend(attrs);
+                }
+            }
+            _ => return Err(self.error("expected a statement")),
+        }
+        Ok(())
+    }
+
+    /// Parse any number of attribute lists, e.g. `[a=1, b=2][c=3]`.
+    fn attributes(&mut self) -> io::Result<HashMap<String, String>> {
+        let mut attrs = HashMap::new();
+        while self.eat(&Token::Punct('[')) {
+            while !self.eat(&Token::Punct(']')) {
+                let key = self.id()?;
+                self.expect(&Token::Punct('='), "`=`")?;
+                let value = self.id()?;
+                attrs.insert(key, value);
+                if !self.eat(&Token::Punct(',')) {
+                    self.eat(&Token::Punct(';'));
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// Skip the port of a node, e.g. `:port:n`.
+    fn port(&mut self) -> io::Result<()> {
+        while self.eat(&Token::Punct(':')) {
+            self.id()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the node `id`, adding it if needed.
+    fn node(&mut self, id: String) -> usize {
+        let nodes = &mut self.nodes;
+        *self.ids.entry(id).or_insert_with_key(|id| {
+            nodes.push((id.clone(), HashMap::new()));
+            nodes.len() - 1
+        })
+    }
+
+    /// Parse an ID, concatenating quoted strings joined by `+`.
+    fn id(&mut self) -> io::Result<String> {
+        let mut id = match self.next() {
+            Some(Token::Id(id)) => id.clone(),
+            _ => return Err(self.error_before("expected an ID")),
+        };
+        while self.eat(&Token::Punct('+')) {
+            match self.next() {
+                Some(Token::Id(s)) => id.push_str(s),
+                _ => return Err(self.error_before("expected a string")),
+            }
+        }
+        Ok(id)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos).map(|t| &t.token);
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let eaten = self.peek() == Some(token);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, token: &Token, expected: &str) -> io::Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {}", expected)))
+        }
+    }
+
+    /// An error at the current token, or the end of the graph.
+    fn error<S: Into<String>>(&self, msg: S) -> io::Error {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |t| t.line);
+        invalid_line(line, msg)
+    }
+
+    /// An error at the token just consumed.
+    fn error_before<S: Into<String>>(&self, msg: S) -> io::Error {
+        let line = self
+            .tokens
+            .get(self.pos.saturating_sub(1))
+            .or_else(|| self.tokens.last())
+            .map_or(1, |t| t.line);
+        invalid_line(line, msg)
+    }
+}
@@ -0,0 +1,14 @@
+use std::io;
+
+/// An error for malformed input, e.g. a corrupt binary stream.
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// An error for malformed input at `line` of a text format, e.g. DOT.
+pub(crate) fn invalid_line<S: Into<String>>(line: usize, msg: S) -> io::Error {
+    invalid_data(format!("line {}: {}", line, msg.into()))
+}
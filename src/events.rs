@@ -1,28 +1,230 @@
-use compact_str::CompactString;
-use derive_more::{From, Into};
-use owo_colors::{OwoColorize, XtermColors};
+use crate::accessible;
+use crate::analysis::{self, Lint};
+use crate::export;
+use crate::html;
+use crate::keys::{TrackKey, TrackKeys};
+use crate::markup;
+use crate::minimap;
+use crate::station::{Level, Severity, Station, Status};
+use crate::stats::{self, Stats};
+use crate::template;
+#[cfg(feature = "color")]
+use owo_colors::{OwoColorize, Rgb, XtermColors};
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::{self, Write};
+use std::iter::FromIterator;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
 
+/// A rendered rail, which is short enough to
+/// not need allocating with `compact_str`.
+#[cfg(feature = "compact_str")]
+type RailString = compact_str::CompactString;
+#[cfg(not(feature = "compact_str"))]
+type RailString = String;
+
+/// Without the `color` feature nothing is colored,
+/// so this only stands in for `owo_colors::Rgb`.
+#[cfg(not(feature = "color"))]
 #[derive(Clone, Copy)]
-enum Rail {
+#[allow(dead_code)]
+struct Rgb(u8, u8, u8);
+
+/// Color `s` with `color`, if the `color` feature is enabled.
+fn paint<S: fmt::Display>(s: S, color: Rgb) -> String {
+    #[cfg(feature = "color")]
+    return s.color(color).to_string();
+    #[cfg(not(feature = "color"))]
+    {
+        let _ = color;
+        s.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Rail {
     Straight,
     Horizontal,
     Station,
     Ground,
+    /// The end of an [aborted](struct.RenderingSettings.html#method.abort_reason) track.
+    Aborted,
     ShiftRight,
     ShiftLeft,
+    TopLeft,
     TopRight,
     BottomRight,
     BottomtLeft,
     SplitRight,
     SplitLeft,
+    LinkEnd,
+    SharedStation,
+    SharedHorizontal,
+}
+
+impl Rail {
+    /// The name of this rail in structured renderings.
+    fn kind(&self) -> &'static str {
+        match self {
+            Rail::Straight => "straight",
+            Rail::Horizontal => "horizontal",
+            Rail::Station => "station",
+            Rail::Ground => "ground",
+            Rail::Aborted => "aborted",
+            Rail::ShiftRight => "shift_right",
+            Rail::ShiftLeft => "shift_left",
+            Rail::TopLeft => "top_left",
+            Rail::TopRight => "top_right",
+            Rail::BottomRight => "bottom_right",
+            Rail::BottomtLeft => "bottom_left",
+            Rail::SplitRight => "split_right",
+            Rail::SplitLeft => "split_left",
+            Rail::LinkEnd => "link_end",
+            Rail::SharedStation => "shared_station",
+            Rail::SharedHorizontal => "shared_horizontal",
+        }
+    }
+}
+
+/// What to show in the time column, left of the rails,
+/// for stations carrying a [time](struct.Station.html#method.with_time).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeColumn {
+    /// No time column is rendered.
+    Hidden,
+    /// The time (UTC) at which the station was reached, formatted by
+    /// [`time_format`], by default the time of day as `HH:MM:SS`.
+    ///
+    /// [`time_format`]: struct.RenderingSettings.html#method.time_format
+    Absolute,
+    /// The time elapsed since the previous station carrying a time.
+    Delta,
+    /// The time elapsed until rendering, e.g. `3m ago`.
+    Relative,
 }
 
+/// What to color by duration, for stations carrying a
+/// [duration](struct.Station.html#method.with_duration).
+///
+/// Stations are colored on a green to red gradient,
+/// by the percentile of their duration among all stations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Heat {
+    /// Stations are colored like their track.
+    Off,
+    /// The station marker is colored by duration.
+    Marker,
+    /// The station text is colored by duration.
+    Text,
+}
+
+/// How [`Event`]s are rendered.
+///
+/// `RenderingSettings` is a builder: every setting has a consuming
+/// setter, e.g. [`splat`], a `&mut` setter, e.g. [`set_splat`],
+/// and a getter, e.g. [`get_splat`].
+///
+/// ```
+/// use metro::RenderingSettings;
+///
+/// let mut rdr = RenderingSettings::default().splat(2).rounded(true);
+/// rdr.set_color(false);
+///
+/// assert_eq!(rdr.get_splat(), 2);
+/// assert!(rdr.get_rounded());
+/// assert!(!rdr.get_color());
+/// ```
+///
+/// ## Defaults
+///
+/// - `splat`: `5`
+/// - `color`: `true`
+/// - `rounded`: `false`
+/// - `time_column`: [`TimeColumn::Hidden`]
+/// - `time_format`: `%H:%M:%S`
+/// - `heat`: [`Heat::Off`]
+/// - `detached_marker`: none
+/// - `implicit_root`: `true`
+/// - `group`: no groups
+/// - `named_group`: no named groups
+/// - `swimlanes`: `false`
+/// - `track_order`: empty
+/// - `pin_left`: no pinned track
+/// - `alias_color`: no aliases
+/// - `inherit_colors`: `false`
+/// - `station_format`: none
+/// - `field_color`: no field colors
+/// - `hide_tags`: no hidden tags
+/// - `min_level`: [`Level::Trace`]
+/// - `row_numbers`: `false`
+/// - `prefix`: none
+/// - `inline_markup`: `false`
+/// - `time_spacing`: none
+/// - `track_weight`: `1` for every track
+/// - `track_lanes`: `1` for every track
+/// - `ghost_track`: no ghost tracks
+/// - `page_break`: none
+/// - `track_label`: the ID of every track
+/// - `track_link`: no track links
+/// - `header_every`: none
+/// - `ruler`: `false`
+/// - `track_spacing`: [`splat`] for every track
+/// - `elastic_columns`: `false`
+/// - `severity_color`: blue for [`Severity::Info`], yellow for
+///   [`Severity::Warn`] and red for [`Severity::Error`]
+/// - `abort_reason`: no aborted tracks
+/// - `summary`: `false`
+///
+/// [`Event`]: enum.Event.html
+/// [`splat`]: #method.splat
+/// [`set_splat`]: #method.set_splat
+/// [`get_splat`]: #method.get_splat
+/// [`TimeColumn::Hidden`]: enum.TimeColumn.html#variant.Hidden
+/// [`Heat::Off`]: enum.Heat.html#variant.Off
+/// [`Level::Trace`]: enum.Level.html#variant.Trace
+/// [`Severity::Info`]: enum.Severity.html#variant.Info
+/// [`Severity::Warn`]: enum.Severity.html#variant.Warn
+/// [`Severity::Error`]: enum.Severity.html#variant.Error
+#[derive(Clone, Debug)]
 pub struct RenderingSettings {
     splat: usize,
     color: bool,
     rounded: bool,
+    time_column: TimeColumn,
+    time_format: String,
+    heat: Heat,
+    detached_marker: Option<String>,
+    implicit_root: bool,
+    groups: Vec<Range<usize>>,
+    track_order: Vec<TrackId>,
+    pinned: Option<TrackId>,
+    color_aliases: Vec<(TrackId, TrackId)>,
+    inherit_colors: bool,
+    station_format: Option<String>,
+    field_colors: Vec<(String, [u8; 3])>,
+    hidden_tags: Vec<String>,
+    min_level: Level,
+    row_numbers: bool,
+    prefix: String,
+    inline_markup: bool,
+    time_spacing: Option<(Duration, usize)>,
+    track_weights: Vec<(TrackId, usize)>,
+    track_lanes: Vec<(TrackId, usize)>,
+    ghost_tracks: Vec<TrackId>,
+    page_break: Option<(usize, String)>,
+    track_labels: Vec<(TrackId, String)>,
+    track_links: Vec<(TrackId, String)>,
+    header_every: Option<usize>,
+    ruler: bool,
+    track_spacings: Vec<(TrackId, usize)>,
+    elastic_columns: bool,
+    severity_colors: [[u8; 3]; 3],
+    abort_reasons: Vec<(TrackId, String)>,
+    summary: bool,
+    group_names: Vec<(Range<usize>, String)>,
+    swimlanes: bool,
 }
 impl Default for RenderingSettings {
     fn default() -> Self {
@@ -30,469 +232,5034 @@ impl Default for RenderingSettings {
             splat: 5,
             color: true,
             rounded: false,
+            time_column: TimeColumn::Hidden,
+            time_format: "%H:%M:%S".to_owned(),
+            heat: Heat::Off,
+            detached_marker: None,
+            implicit_root: true,
+            groups: Vec::new(),
+            track_order: Vec::new(),
+            pinned: None,
+            color_aliases: Vec::new(),
+            inherit_colors: false,
+            station_format: None,
+            field_colors: Vec::new(),
+            hidden_tags: Vec::new(),
+            min_level: Level::Trace,
+            row_numbers: false,
+            prefix: String::new(),
+            inline_markup: false,
+            time_spacing: None,
+            track_weights: Vec::new(),
+            track_lanes: Vec::new(),
+            ghost_tracks: Vec::new(),
+            page_break: None,
+            track_labels: Vec::new(),
+            track_links: Vec::new(),
+            header_every: None,
+            ruler: false,
+            track_spacings: Vec::new(),
+            elastic_columns: false,
+            severity_colors: [[0x5f, 0x87, 0xd7], [0xd7, 0xaf, 0x00], [0xd7, 0x00, 0x00]],
+            abort_reasons: Vec::new(),
+            summary: false,
+            group_names: Vec::new(),
+            swimlanes: false,
         }
     }
 }
 impl RenderingSettings {
+    /// `RenderingSettings` for the narrowest rendering, where every
+    /// track is a single character wide, i.e. with a [`splat`] of `0`.
+    ///
+    /// Forks, merges, stopped tracks and detached stations keep
+    /// their shape, with horizontal rails spanning whole tracks.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(RenderingSettings::dense().color(false));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station"));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "├┐\n│╪   Station\n││\n");
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(RenderingSettings::dense().color(false));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::SplitTrack(1.into(), 2.into()));
+    /// metro.push(Event::station(2, "Fork"));
+    /// metro.push(Event::station(5, "Detached"));
+    /// metro.push(Event::JoinTrack(2.into(), 0.into()));
+    /// metro.push(Event::StopTrack(1.into()));
+    /// metro.push(Event::station(0, "Merge"));
+    ///
+    /// assert_eq!(
+    ///     metro.to_string().unwrap(),
+    ///     "├┐\n││\n│├┐\n││╪   Fork\n│││\n│││   Detached\n│││\n├─┘\n│┷\n╪     Merge\n│\n",
+    /// );
+    /// ```
+    ///
+    /// [`splat`]: #method.splat
+    pub fn dense() -> Self {
+        Self::default().splat(0)
+    }
+
+    /// Space tracks by `splat_factor` characters, i.e. render every track
+    /// `splat_factor + 1` characters wide. With `0`, tracks are adjacent.
     pub fn splat(mut self, splat_factor: usize) -> Self {
         self.splat = splat_factor;
         self
     }
 
+    /// Color every track, and stations by [`heat`].
+    ///
+    /// Has no effect without the `color` feature, enabled by default.
+    ///
+    /// [`heat`]: #method.heat
     pub fn color(mut self, colored: bool) -> Self {
         self.color = colored;
         self
     }
 
-    fn colorize<S: AsRef<str>>(&self, s: S, i: &TrackId) -> CompactString {
-        if self.color {
-            let color = XtermColors::from((((i.0 + 1) ^ 93) % 255) as u8);
-            s.as_ref().color(color).to_string().into()
-        } else {
-            s.as_ref().into()
-        }
+    /// Render corners rounded, e.g. `╭` instead of `┌`.
+    pub fn rounded(mut self, rounded: bool) -> Self {
+        self.rounded = rounded;
+        self
     }
 
-    fn rail_to_str(&self, rail: Rail) -> CompactString {
-        use std::fmt::Write;
-
-        let mut r = CompactString::with_capacity(self.splat + 2);
-        match rail {
-            Rail::Straight => write!(r, "│{}", " ".repeat(self.splat)),
-            Rail::Horizontal => write!(r, "{}", "─".repeat(self.splat + 1)),
-            Rail::Station => write!(r, "╪{}", " ".repeat(self.splat)),
-            Rail::Ground => write!(r, "┷{}", " ".repeat(self.splat)),
-            Rail::ShiftRight => write!(r, "└{}┐{}", "─".repeat(self.splat), " ".repeat(self.splat)),
-            Rail::ShiftLeft => write!(r, "┌{}┘", "─".repeat(self.splat)),
-            Rail::TopRight => write!(r, "{}┐{}", "─".repeat(self.splat), " ".repeat(self.splat)),
-            Rail::BottomRight => write!(r, "{}┘{}", "─".repeat(self.splat), " ".repeat(self.splat)),
-            Rail::BottomtLeft => write!(r, "└{}", "─".repeat(self.splat)),
-            Rail::SplitRight => write!(r, "├"),
-            Rail::SplitLeft => write!(r, "{}┤", "─".repeat(self.splat)),
-        }
-        .unwrap();
-        r
+    /// Render a column left of the rails, showing the time of
+    /// every station carrying one.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station, TimeColumn};
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let start = UNIX_EPOCH + Duration::from_secs(3600);
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .time_column(TimeColumn::Delta);
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Build").with_time(start)));
+    /// let end = start + Duration::from_millis(1500);
+    /// metro.push(Event::station(0, Station::new("Test").with_time(end)));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("+0.000s ╪"));
+    /// assert!(string.contains("+1.500s ╪"));
+    /// ```
+    pub fn time_column(mut self, time_column: TimeColumn) -> Self {
+        self.time_column = time_column;
+        self
     }
-}
 
-trait RenderStr {
-    fn render(&self, s: &RenderingSettings, i: &TrackId) -> CompactString;
-}
-impl RenderStr for Rail {
-    fn render(&self, s: &RenderingSettings, i: &TrackId) -> CompactString {
-        s.colorize(s.rail_to_str(*self), i)
+    /// Format the times of [`TimeColumn::Absolute`] with `format`, a
+    /// `strftime`-like pattern, in UTC.
+    ///
+    /// Supports `%Y`, `%y`, `%m`, `%d`, `%j`, `%H`, `%M`, `%S`, `%3f`
+    /// for milliseconds, `%s` for seconds since the epoch, and `%%`.
+    /// With the `chrono` feature, every pattern of [`chrono`] is
+    /// supported instead.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station, TimeColumn};
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .time_column(TimeColumn::Absolute)
+    ///     .time_format("%Y-%m-%d %H:%M");
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// metro.push(Event::station(0, Station::new("Build").with_time(time)));
+    ///
+    /// assert!(metro.to_string().unwrap().starts_with("2023-11-14 22:13 ╪"));
+    /// ```
+    ///
+    /// [`TimeColumn::Absolute`]: enum.TimeColumn.html#variant.Absolute
+    /// [`chrono`]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+    pub fn time_format<S: Into<String>>(mut self, format: S) -> Self {
+        self.time_format = format.into();
+        self
     }
-}
-
-#[derive(PartialEq, Eq, Hash, From, Into, Debug, Clone, Copy)]
-/// An ID referencing a `Track`
-pub struct TrackId(usize);
 
-#[derive(Clone, Debug)]
-pub enum Event<'a> {
-    /// `StartTrack(track_id)`
+    /// Color stations by their duration, making slow stages stand out.
     ///
-    /// - If `track_id` already exists, then this event does nothing.
+    /// Has no effect if [`color`] is disabled.
     ///
-    /// New `track_id`s are added rightmost.
+    /// [`color`]: #method.color
+    pub fn heat(mut self, heat: Heat) -> Self {
+        self.heat = heat;
+        self
+    }
+
+    /// Render `marker` before the text of stations
+    /// not tied to any existing track.
     ///
-    /// ## Output Example
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// Given 3 tracks `0, 1, 2` then `StartTrack(4)` would render as:
+    /// let rdr = RenderingSettings::default().color(false).detached_marker("○");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Attached"));
+    /// metro.push(Event::station(42, "Detached"));
     ///
-    /// ```text
-    /// | | |
-    /// | | | |
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.contains("   Attached"));
+    /// assert!(string.contains("   ○ Detached"));
     /// ```
-    StartTrack(TrackId),
+    pub fn detached_marker<S: Into<String>>(mut self, marker: S) -> Self {
+        self.detached_marker = Some(marker.into());
+        self
+    }
 
-    /// `StartTracks(track_ids)`
-    ///
-    /// - If a `track_id` from `track_ids` already exists, then it is ignored.
-    /// - If all `track_ids` already exists, then this event does nothing.
+    /// Whether rendering starts out with a default track `0`.
     ///
-    /// New `track_id`s are added rightmost.
+    /// Enabled by default. When disabled, every track
+    /// must be started explicitly, e.g. with [`Event::StartTrack`].
     ///
-    /// ## Output Example
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// Given 3 tracks `0, 1, 2` then `StartTracks(&[4, 5])` would render as:
+    /// let rdr = RenderingSettings::default().color(false).splat(1).implicit_root(false);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTrack(1.into()));
+    /// metro.push(Event::NoEvent);
     ///
-    /// ```text
-    /// | | |
-    /// | | | | |
+    /// assert_eq!(metro.to_string().unwrap(), "│ \n");
     /// ```
-    StartTracks(&'a [TrackId]),
+    ///
+    /// [`Event::StartTrack`]: enum.Event.html#variant.StartTrack
+    pub fn implicit_root(mut self, implicit_root: bool) -> Self {
+        self.implicit_root = implicit_root;
+        self
+    }
 
-    /// `StopTrack(track_id)`
+    /// Keep the tracks with IDs in `track_ids` adjacent: a track of the
+    /// group is started right of the other live tracks of the group,
+    /// instead of rightmost.
     ///
-    /// - If `track_id` does not exist, then this event does nothing.
+    /// *See also [`Metro::reserve_group`].*
     ///
-    /// All rails to the right of `track_id`, are pulled to the left.
+    /// [`Metro::reserve_group`]: struct.Metro.html#method.reserve_group
+    pub fn group(mut self, track_ids: Range<TrackId>) -> Self {
+        self.groups.push(track_ids.start.0..track_ids.end.0);
+        self
+    }
+
+    /// Like [`group`], and name the group `name`, which is drawn above
+    /// the rails within a bracket spanning the tracks of the group,
+    /// whenever their columns change.
     ///
-    /// ## Output Example
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// Given 3 tracks `0, 1, 2` then `StopTrack(1)` would render as:
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(9)
+    ///     .named_group(1.into()..3.into(), "workers");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTracks(vec![1.into(), 2.into()].into()));
+    /// metro.push(Event::station(1, "Job 1"));
     ///
-    /// ```text
-    /// | | |
-    /// | " |
-    /// |  /
-    /// | |
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("          ╭─workers─╮\n"));
     /// ```
-    StopTrack(TrackId),
-
-    /// `Station(track_id, text)`
     ///
-    /// - If the `track_id` does not exist, then `text` is still
-    /// rendered, just not tied to any track.
+    /// [`group`]: #method.group
+    pub fn named_group<S: Into<String>>(mut self, track_ids: Range<TrackId>, name: S) -> Self {
+        self.add_named_group(track_ids, name);
+        self
+    }
+
+    /// Start the rendering with a header naming every [named group]
+    /// over the columns of its tracks, like the lanes of a swimlane
+    /// diagram, instead of drawing brackets above the rails.
     ///
-    /// ## Output Example
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// Given 3 tracks `0, 1, 2` then `Station(1, "Hello World")` would render as:
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(5)
+    ///     .named_group(0.into()..1.into(), "ci")
+    ///     .named_group(1.into()..3.into(), "workers")
+    ///     .swimlanes(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTracks(vec![1.into(), 2.into()].into()));
+    /// metro.push(Event::station(1, "Job 1"));
     ///
-    /// ```text
-    /// | | |
-    /// | * | Hello World
-    /// | | |
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("ci    workers\n│     ╪     │        Job 1\n"));
     /// ```
     ///
-    /// Text with multiple lines is also allowed.
-    /// Given 3 tracks `0, 1, 2` then `Station(1, "Hello\nWorld")` would render as:
+    /// [named group]: #method.named_group
+    pub fn swimlanes(mut self, swimlanes: bool) -> Self {
+        self.swimlanes = swimlanes;
+        self
+    }
+
+    fn group_of(&self, track_id: TrackId) -> Option<&Range<usize>> {
+        self.groups.iter().find(|group| group.contains(&track_id.0))
+    }
+
+    /// Lay the tracks listed in `order` out from left to right in
+    /// that order, whatever the order they are started or split in.
+    ///
+    /// A listed track is started left of the live listed tracks coming
+    /// after it in `order`, and right of those coming before it;
+    /// other tracks are not affected.
     ///
-    /// ```text
-    /// | | |
-    /// | * | Hello
-    /// | | | World
-    /// | | |
     /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// If the `track_id` does not exist, then no rail is highlighted.
-    /// Thus `Station(10, "Hello World")` would render as:
+    /// let rdr = RenderingSettings::default().color(false).splat(1).track_order(vec![
+    ///     0.into(),
+    ///     2.into(),
+    ///     1.into(),
+    /// ]);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::SplitTrack(0.into(), 2.into()));
     ///
-    /// ```text
-    /// | | |
-    /// | | | Hello World
-    /// | | |
+    /// assert_eq!(metro.active_tracks_at(1), [0.into(), 2.into(), 1.into()]);
     /// ```
-    Station(TrackId, Cow<'a, str>),
+    pub fn track_order<I: IntoIterator<Item = TrackId>>(mut self, order: I) -> Self {
+        self.track_order = order.into_iter().collect();
+        self
+    }
 
-    /// `SplitTrack(from_track_id, new_track_id)`
+    /// Keep `track_id` in the leftmost column: it is started there,
+    /// and no other track is ever started left of it.
     ///
-    /// Creates a new track diverging from `from_track_id` to the right.
-    /// All rails to the right of `from_track_id`, are pushed to the
-    /// right to make space for the new track.
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// - If `from_track_id` does not exist, then this event is the
-    /// same as `StartTrack(new_track_id)`.
-    /// - If `new_track_id` already exists, then this event does nothing.
+    /// let rdr = RenderingSettings::default().pin_left(1.into());
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::StopTrack(0.into()));
+    /// metro.push(Event::StartTrack(2.into()));
     ///
-    /// ## Output Example
+    /// assert_eq!(metro.active_tracks_at(0), [1.into(), 0.into()]);
+    /// assert_eq!(metro.active_tracks_at(2), [1.into(), 2.into()]);
+    /// ```
+    pub fn pin_left(mut self, track_id: TrackId) -> Self {
+        self.pinned = Some(track_id);
+        self
+    }
+
+    /// Where to start `track_id` among `tracks`, if
+    /// it is pinned or part of the track order.
+    fn start_position(&self, tracks: &[TrackId], track_id: TrackId) -> Option<usize> {
+        if self.pinned == Some(track_id) {
+            return Some(0);
+        }
+        let position = self.ordered_position(tracks, track_id)?;
+        if position == 0 && self.pinned.is_some() && tracks.first() == self.pinned.as_ref() {
+            Some(1)
+        } else {
+            Some(position)
+        }
+    }
+
+    /// Where to start `track_id` among `tracks` according
+    /// to the track order, if it is part of it.
+    fn ordered_position(&self, tracks: &[TrackId], track_id: TrackId) -> Option<usize> {
+        let rank = |id: &TrackId| self.track_order.iter().position(|t| t == id);
+        let track_rank = rank(&track_id)?;
+        tracks
+            .iter()
+            .position(|t| rank(t).is_some_and(|r| r > track_rank))
+            .or_else(|| {
+                tracks
+                    .iter()
+                    .rposition(|t| rank(t).is_some_and(|r| r < track_rank))
+                    .map(|i| i + 1)
+            })
+    }
+
+    /// Color the track `new` like the track `existing`, e.g. to keep
+    /// the color of a logical lane across track ID changes.
     ///
-    /// Given 3 tracks `0, 1, 2` then `SplitTrack(1, 4)` would render as:
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// ```text
-    /// | | |
-    /// | |\ \
-    /// | | | |
+    /// let restart = |id: usize| {
+    ///     [Event::StopTrack(0.into()), Event::StartTrack(id.into()), Event::station(id, "Restarted")]
+    /// };
+    /// let rdr = RenderingSettings::default().alias_color(0.into(), 1.into());
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.extend(restart(1));
+    ///
+    /// let same_id = metro::events::Metro::from(restart(0).to_vec());
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), same_id.to_string().unwrap());
     /// ```
-    SplitTrack(TrackId, TrackId),
+    pub fn alias_color(mut self, existing: TrackId, new: TrackId) -> Self {
+        self.color_aliases.push((existing, new));
+        self
+    }
 
-    /// `JoinTrack(from_track_id, to_track_id)`
+    /// Color tracks split from another one like it,
+    /// instead of by their own ID.
+    pub fn inherit_colors(mut self, inherit_colors: bool) -> Self {
+        self.inherit_colors = inherit_colors;
+        self
+    }
+
+    /// Render stations with [fields] using `template`
+    /// instead of their own text, e.g. to let end users
+    /// customize it like `git log --format`.
     ///
-    /// Joins `from_track_id` and `to_track_id`
-    /// resulting in the `from_track_id` being removed.
+    /// Stations without fields are unaffected.
     ///
-    /// The rails are joined towards the leftmost rail.
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
     ///
-    /// - If `from_track_id` does not exist, then this event does nothing.
-    /// - If `to_track_id` does not exist, then it turns into `StopTrack(from_track_id)`.
-    /// - If `from_track_id` and `to_track_id` are the same, then it turns into `StopTrack(from_track_id)`
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(1)
+    ///     .station_format("[{author}] {msg}");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(
+    ///     0,
+    ///     Station::new("{msg}").with_field("msg", "Initial commit").with_field("author", "ada"),
+    /// ));
     ///
-    /// The track ID (`from_track_id`) can be reused for
-    /// a new track after this event.
+    /// assert!(metro.to_string().unwrap().starts_with("╪    [ada] Initial commit\n"));
+    /// ```
     ///
-    /// ## Output Example
+    /// [fields]: struct.Station.html#method.with_field
+    pub fn station_format<S: Into<String>>(mut self, template: S) -> Self {
+        self.station_format = Some(template.into());
+        self
+    }
+
+    /// Color the value of the station field `name` with the RGB `color`.
     ///
-    /// Given 3 tracks `0, 1, 2` then `JoinTrack(1, 0)` would render as:
+    /// Has no effect if [`color`] is disabled.
+    ///
+    /// [`color`]: #method.color
+    pub fn field_color<S: Into<String>>(mut self, name: S, color: [u8; 3]) -> Self {
+        self.field_colors.push((name.into(), color));
+        self
+    }
+
+    /// Hide every station tagged with any tag of `tags`, e.g. to
+    /// render both a terse and a detailed graph from the same events.
     ///
-    /// ```text
-    /// | | |
-    /// |/ /
-    /// | |
     /// ```
+    /// use metro::{Event, RenderingSettings, Station};
     ///
-    /// Given 6 tracks `0, 1, 2, 3, 4, 5` then `JoinTrack(4, 0)` would render as:
+    /// let events = [
+    ///     Event::station(0, "Build"),
+    ///     Event::station(0, Station::new("Fetch dependencies").with_tag("verbose")),
+    /// ];
     ///
-    /// ```text
-    /// | | | | | |
-    /// | |_|_|/ /
-    /// |/| | | |
-    /// | | | | |
+    /// let rdr = RenderingSettings::default().hide_tags(&["verbose"]);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.extend(events.iter().cloned());
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), metro::to_string(&events[..1]).unwrap());
     /// ```
-    JoinTrack(TrackId, TrackId),
+    pub fn hide_tags(mut self, tags: &[&str]) -> Self {
+        self.set_hide_tags(tags);
+        self
+    }
 
-    /// `NoEvent` produces one row of rails.
+    /// Hide every station with a [level] below `level`, e.g.
+    /// to render at different detail for users and developers.
     ///
-    /// ## Output Example
+    /// ```
+    /// use metro::{Event, Level, RenderingSettings, Station};
     ///
-    /// Given 3 tracks `0, 1, 2` then `NoEvent` would render as:
+    /// let events = [
+    ///     Event::station(0, Station::new("Request").with_level(Level::Info)),
+    ///     Event::station(0, Station::new("Cache miss").with_level(Level::Debug)),
+    /// ];
     ///
-    /// ```text
-    /// | | |
+    /// let rdr = RenderingSettings::default().min_level(Level::Info);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.extend(events.iter().cloned());
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), metro::to_string(&events[..1]).unwrap());
     /// ```
-    NoEvent,
-}
+    ///
+    /// [level]: struct.Station.html#method.with_level
+    pub fn min_level(mut self, level: Level) -> Self {
+        self.min_level = level;
+        self
+    }
 
-#[derive(Default)]
-pub struct Metro<'a> {
-    events: Vec<Event<'a>>,
-    rdr: RenderingSettings,
-}
-impl<'a> Metro<'a> {
-    pub fn with_settings(rdr: RenderingSettings) -> Self {
-        Self {
-            rdr,
-            ..Default::default()
-        }
+    /// Prefix every row with its number, starting at 1, in a dim
+    /// gutter, e.g. to refer to rows when discussing a rendering.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).row_numbers(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::station(0, "Station 2"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.lines().next().unwrap().starts_with("1 "));
+    /// assert!(string.lines().nth(2).unwrap().starts_with("3 "));
+    /// ```
+    pub fn row_numbers(mut self, row_numbers: bool) -> Self {
+        self.row_numbers = row_numbers;
+        self
     }
-    pub fn push(&mut self, event: Event<'a>) {
-        self.events.push(event);
+
+    /// Prepend `prefix` to every rendered row, e.g. to nest the
+    /// rendering in a quoted block or under a list item.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).prefix("> ");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Station 1"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.lines().all(|line| line.starts_with("> ")));
+    /// ```
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
     }
-    /// Write `&[`[`Event`]`]` to [`<W: io::Write>`].
-    /// Defines a default track with `track_id` of `0`.
+
+    /// Indent every rendered row by `width` spaces.
     ///
-    /// *[See also `Metro::to_writer`.][`Metro::to_writer`]*
+    /// *Shorthand for [`prefix`] with `width` spaces.*
     ///
-    /// *See also [`to_string`] and [`to_vec`].*
+    /// [`prefix`]: #method.prefix
+    pub fn indent(self, width: usize) -> Self {
+        self.prefix(" ".repeat(width))
+    }
+
+    /// Interpret inline markup in the text of stations: `**bold**`,
+    /// `_dim_`, and `` `code` ``, rendered underlined. Without [`color`],
+    /// the markup is removed.
     ///
-    /// [`to_vec`]: fn.to_vec.html
-    /// [`to_string`]: fn.to_string.html
+    /// Delimiters without a matching closing delimiter are rendered
+    /// as is, and so are underscores within words, e.g. in `snake_case`.
     ///
-    /// [`Event`]: enum.Event.html
+    /// ```
+    /// use metro::{Event, RenderingSettings};
     ///
-    /// [`Metro::to_writer`]: struct.Metro.html#method.to_writer
+    /// let rdr = RenderingSettings::default().color(false).inline_markup(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "**Deploy** `api` _staging_ my_service"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.contains("Deploy api staging my_service"));
+    /// ```
+    ///
+    /// [`color`]: #method.color
+    pub fn inline_markup(mut self, inline_markup: bool) -> Self {
+        self.inline_markup = inline_markup;
+        self
+    }
+
+    /// Space out stations by the time elapsed between them, adding a
+    /// row of rails for every `per_row` elapsed, up to `max_rows`, such
+    /// that gaps in a process are visible in the rendering.
+    ///
+    /// Only stations carrying a [time] are spaced out, from the
+    /// last station carrying one.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let at = |secs| Station::new("Tick").with_time(UNIX_EPOCH + Duration::from_secs(secs));
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(1)
+    ///     .time_spacing(Duration::from_secs(60), 5);
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, at(0)));
+    /// metro.push(Event::station(0, at(180)));
+    /// metro.push(Event::station(0, at(3600)));
+    ///
+    /// // 3 more rows after 3 minutes, and at most 5 more after an hour
+    /// assert_eq!(metro.to_string().unwrap().lines().count(), 2 + 3 + 2 + 5 + 2);
+    /// ```
+    ///
+    /// [time]: struct.Station.html#method.with_time
+    pub fn time_spacing(mut self, per_row: Duration, max_rows: usize) -> Self {
+        self.time_spacing = Some((per_row, max_rows));
+        self
+    }
+
+    /// Weigh `track_id` by `weight`, e.g. its traffic or importance:
+    /// tracks weighing more than `1` are rendered double-struck, e.g.
+    /// `║` instead of `│`, like a main branch among experiments.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1).track_weight(0.into(), 2);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(0, "Main"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("╠─┐ \n╬ │    Main"));
+    /// ```
+    pub fn track_weight(mut self, track_id: TrackId, weight: usize) -> Self {
+        self.set_track_weight(track_id, weight);
+        self
+    }
+
+    fn is_heavy(&self, track_id: TrackId) -> bool {
+        self.get_track_weight(track_id) > 1
+    }
+
+    /// Render `track_id` as a bundle of `lanes` adjacent rails, e.g. to
+    /// represent identical workers without as many tracks: the lanes
+    /// split, join and shift together, like a single track.
+    ///
+    /// Lanes are drawn within the column of their track, so there
+    /// are at most as many of them as the column is wide, i.e.
+    /// [`splat`]` + 1` unless [spaced out].
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(2).track_lanes(1.into(), 2);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Workers"));
+    /// metro.push(Event::JoinTrack(1.into(), 0.into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("├──┬┐ \n│  ╪╪    Workers\n│  ││ \n├──┴┘ \n"));
+    /// ```
+    ///
+    /// [`splat`]: #method.splat
+    /// [spaced out]: #method.track_spacing
+    pub fn track_lanes(mut self, track_id: TrackId, lanes: usize) -> Self {
+        self.set_track_lanes(track_id, lanes);
+        self
+    }
+
+    /// The number of lanes `track_id` is drawn with.
+    fn lanes_of(&self, track_id: TrackId) -> usize {
+        self.get_track_lanes(track_id).max(1)
+    }
+
+    /// Render `track_id` as a ghost track, e.g. an upstream branch shown
+    /// for context: its rails are dotted, and dimmed if [`color`]
+    /// is enabled, and it has no weight in the layout, as other tracks
+    /// are started left of the live ghost tracks, as if they were not
+    /// there.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1).ghost_track(1.into());
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTrack(1.into()));
+    /// metro.push(Event::StartTrack(2.into()));
+    /// metro.push(Event::NoEvent);
+    ///
+    /// assert_eq!(metro.active_tracks_at(1), [0.into(), 2.into(), 1.into()]);
+    /// assert_eq!(metro.to_string().unwrap(), "│ │ ┆ \n");
+    /// ```
+    ///
+    /// [`color`]: #method.color
+    pub fn ghost_track(mut self, track_id: TrackId) -> Self {
+        self.set_ghost_track(track_id, true);
+        self
+    }
+
+    /// Split the rendering into pages of `rows` rows, e.g. to print it:
+    /// `separator` is written between pages, e.g. `"\x0c"` for a form
+    /// feed, or a line of its own as a cut mark, and every page
+    /// starts with the rails continuing from the previous one.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1).page_break(2, "--- ✂ ---\n");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 1"));
+    /// metro.push(Event::station(0, "Station 2"));
+    ///
+    /// assert_eq!(
+    ///     metro.to_string().unwrap(),
+    ///     "├─┐ \n│ ╪    Station 1\n--- ✂ ---\n│ │ \n│ │ \n╪ │    Station 2\n--- ✂ ---\n│ │ \n│ │ \n",
+    /// );
+    /// ```
+    pub fn page_break<S: Into<String>>(mut self, rows: usize, separator: S) -> Self {
+        self.page_break = Some((rows, separator.into()));
+        self
+    }
+
+    /// Label `track_id` with `label` in headers, instead of its ID.
+    ///
+    /// *See [`header_every`].*
+    ///
+    /// [`header_every`]: #method.header_every
+    pub fn track_label<S: Into<String>>(mut self, track_id: TrackId, label: S) -> Self {
+        self.set_track_label(track_id, label);
+        self
+    }
+
+    /// Link the label of `track_id` to `url`, e.g. the page of a branch.
+    ///
+    /// With [`color`], the label is rendered as a [terminal hyperlink],
+    /// and as a link in the HTML of [`to_markdown`], like the text of
+    /// [linked stations].
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default()
+    ///     .splat(4)
+    ///     .ruler(true)
+    ///     .track_label(0.into(), "main")
+    ///     .track_link(0.into(), "https://example.com/main");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Station"));
+    ///
+    /// #[cfg(feature = "color")]
+    /// assert!(metro
+    ///     .to_markdown(true)
+    ///     .unwrap()
+    ///     .contains(r#"<a href="https://example.com/main">"#));
+    /// assert!(metro.render_plain().unwrap().starts_with("main\n"));
+    /// ```
+    ///
+    /// [`color`]: #method.color
+    /// [terminal hyperlink]: https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda
+    /// [`to_markdown`]: events/struct.Metro.html#method.to_markdown
+    /// [linked stations]: struct.Station.html#method.with_link
+    pub fn track_link<S: Into<String>>(mut self, track_id: TrackId, url: S) -> Self {
+        self.set_track_link(track_id, url);
+        self
+    }
+
+    /// Repeat a header every `rows` rows, labelling the tracks
+    /// continuing below it, so that readers deep in a long rendering
+    /// can tell the tracks apart.
+    ///
+    /// Labels are shortened to fit the columns of the tracks, i.e.
+    /// to [`splat`] characters.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(3)
+    ///     .track_label(0.into(), "main")
+    ///     .track_label(1.into(), "dev")
+    ///     .header_every(4);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 1"));
+    /// metro.push(Event::station(0, "Station 2"));
+    ///
+    /// assert_eq!(metro.to_string().unwrap().lines().nth(4), Some("mai dev"));
+    /// ```
+    ///
+    /// [`splat`]: #method.splat
+    pub fn header_every(mut self, rows: usize) -> Self {
+        self.header_every = Some(rows);
+        self
+    }
+
+    /// Render a ruler above and below the rails, labelling the tracks
+    /// at the top and the bottom, like [`header_every`] does, to help
+    /// correlate the rails of a tall rendering.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1).ruler(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 1"));
+    /// metro.push(Event::StopTrack(0.into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("0\n├─┐ \n"));
+    /// assert!(string.ends_with("┌─┘ \n1\n"));
+    /// ```
+    ///
+    /// [`header_every`]: #method.header_every
+    pub fn ruler(mut self, ruler: bool) -> Self {
+        self.ruler = ruler;
+        self
+    }
+
+    /// Space out the rails right of `track_id` by `spacing` characters
+    /// instead of [`splat`], e.g. to make room around the mainline.
+    ///
+    /// Every column is as wide as the widest spacing of the tracks
+    /// it holds over the rendering, so that rails stay aligned.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1).track_spacing(0.into(), 3);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Main"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Feature"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("╪       Main\n│   \n├───┐ \n│   ╪    Feature\n"));
+    /// ```
+    ///
+    /// [`splat`]: #method.splat
+    pub fn track_spacing(mut self, track_id: TrackId, spacing: usize) -> Self {
+        self.set_track_spacing(track_id, spacing);
+        self
+    }
+
+    /// Space out every track by its activity: the tracks with the
+    /// most stations are spaced out by twice [`splat`], to make room
+    /// for their markers and labels, the others proportionally less,
+    /// down to tracks without stations, which are squeezed together.
+    ///
+    /// Tracks with a [spacing] of their own keep it.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(2).elastic_columns(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::SplitTrack(0.into(), 2.into()));
+    /// metro.push(Event::station(0, "Station 2"));
+    /// metro.push(Event::station(2, "Station 3"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// // The busy track 0 is wide, and the idle track 1 is squeezed
+    /// assert!(string.contains("\n│    ╪  │   Station 3\n"));
+    /// ```
+    ///
+    /// [`splat`]: #method.splat
+    /// [spacing]: #method.track_spacing
+    pub fn elastic_columns(mut self, elastic_columns: bool) -> Self {
+        self.elastic_columns = elastic_columns;
+        self
+    }
+
+    /// Color the text of the stations of [severity] `severity` with
+    /// the RGB `color`, so that every source of stations, e.g. every
+    /// adapter, shares the same color scheme.
+    ///
+    /// Has no effect if [`color`] is disabled.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Severity, Station};
+    ///
+    /// let rdr = RenderingSettings::default().severity_color(Severity::Warn, [255, 128, 0]);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Disk almost full").with_severity(Severity::Warn)));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// #[cfg(feature = "color")]
+    /// assert!(string.contains("\x1b[38;2;255;128;0mDisk almost full"));
+    /// assert!(metro::strip_ansi(&string).contains("Disk almost full"));
+    /// ```
+    ///
+    /// [severity]: struct.Station.html#method.with_severity
+    /// [`color`]: #method.color
+    pub fn severity_color(mut self, severity: Severity, color: [u8; 3]) -> Self {
+        self.set_severity_color(severity, color);
+        self
+    }
+
+    /// Render the end of `track_id` as aborted because of `reason`,
+    /// e.g. a failed pipeline step: its terminator is a cross, in the
+    /// [color] of [`Severity::Error`], and `reason` is noted next to it.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(1)
+    ///     .abort_reason(0.into(), "Timed out");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Deploy"));
+    /// metro.push(Event::StopTrack(0.into()));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    Deploy\n│ \n✗    Timed out\n");
+    /// ```
+    ///
+    /// [color]: #method.severity_color
+    /// [`Severity::Error`]: enum.Severity.html#variant.Error
+    pub fn abort_reason<S: Into<String>>(mut self, track_id: TrackId, reason: S) -> Self {
+        self.set_abort_reason(track_id, reason);
+        self
+    }
+
+    /// Write a footer below the rendering, counting the tracks which
+    /// completed, were [aborted], or are still open, and the stations,
+    /// e.g. for CI logs.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default()
+    ///     .color(false)
+    ///     .splat(1)
+    ///     .abort_reason(1.into(), "Failed")
+    ///     .summary(true);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTracks(vec![1.into(), 2.into()].into()));
+    /// metro.push(Event::station(1, "Test"));
+    /// metro.push(Event::StopTrack(1.into()));
+    /// metro.push(Event::station(2, "Lint"));
+    /// metro.push(Event::StopTrack(2.into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.ends_with("\nTracks: 1 completed, 1 aborted, 1 open; stations: 2\n"));
+    /// ```
+    ///
+    /// [aborted]: #method.abort_reason
+    pub fn summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Whether a header is repeated before row `i`.
+    fn repeats_header(&self, i: usize) -> bool {
+        matches!(self.header_every, Some(rows) if rows > 0 && i > 0 && i % rows == 0)
+    }
+
+    /// Where to start `track_id` among `tracks` by default: rightmost
+    /// for ghost tracks, and right of the other tracks otherwise.
+    fn default_position(&self, tracks: &[TrackId], track_id: TrackId) -> usize {
+        if self.get_ghost_track(track_id) {
+            return tracks.len();
+        }
+        tracks
+            .iter()
+            .rposition(|&t| !self.get_ghost_track(t) || self.pinned == Some(t))
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Like [`splat`].
+    ///
+    /// [`splat`]: #method.splat
+    pub fn set_splat(&mut self, splat_factor: usize) -> &mut Self {
+        self.splat = splat_factor;
+        self
+    }
+
+    /// Like [`color`].
+    ///
+    /// [`color`]: #method.color
+    pub fn set_color(&mut self, colored: bool) -> &mut Self {
+        self.color = colored;
+        self
+    }
+
+    /// Like [`rounded`].
+    ///
+    /// [`rounded`]: #method.rounded
+    pub fn set_rounded(&mut self, rounded: bool) -> &mut Self {
+        self.rounded = rounded;
+        self
+    }
+
+    /// Like [`time_column`].
+    ///
+    /// [`time_column`]: #method.time_column
+    pub fn set_time_column(&mut self, time_column: TimeColumn) -> &mut Self {
+        self.time_column = time_column;
+        self
+    }
+
+    /// Like [`time_format`].
+    ///
+    /// [`time_format`]: #method.time_format
+    pub fn set_time_format<S: Into<String>>(&mut self, format: S) -> &mut Self {
+        self.time_format = format.into();
+        self
+    }
+
+    /// Like [`heat`].
+    ///
+    /// [`heat`]: #method.heat
+    pub fn set_heat(&mut self, heat: Heat) -> &mut Self {
+        self.heat = heat;
+        self
+    }
+
+    /// Unlike [`detached_marker`], this can also remove the marker.
+    ///
+    /// [`detached_marker`]: #method.detached_marker
+    pub fn set_detached_marker(&mut self, marker: Option<String>) -> &mut Self {
+        self.detached_marker = marker;
+        self
+    }
+
+    /// Like [`implicit_root`].
+    ///
+    /// [`implicit_root`]: #method.implicit_root
+    pub fn set_implicit_root(&mut self, implicit_root: bool) -> &mut Self {
+        self.implicit_root = implicit_root;
+        self
+    }
+
+    /// Like [`group`].
+    ///
+    /// [`group`]: #method.group
+    pub fn add_group(&mut self, track_ids: Range<TrackId>) -> &mut Self {
+        self.groups.push(track_ids.start.0..track_ids.end.0);
+        self
+    }
+
+    /// Like [`swimlanes`].
+    ///
+    /// [`swimlanes`]: #method.swimlanes
+    pub fn set_swimlanes(&mut self, swimlanes: bool) -> &mut Self {
+        self.swimlanes = swimlanes;
+        self
+    }
+
+    /// Like [`named_group`].
+    ///
+    /// [`named_group`]: #method.named_group
+    pub fn add_named_group<S: Into<String>>(
+        &mut self,
+        track_ids: Range<TrackId>,
+        name: S,
+    ) -> &mut Self {
+        let group = track_ids.start.0..track_ids.end.0;
+        self.groups.push(group.clone());
+        self.group_names.push((group, name.into()));
+        self
+    }
+
+    /// Like [`track_order`].
+    ///
+    /// [`track_order`]: #method.track_order
+    pub fn set_track_order<I: IntoIterator<Item = TrackId>>(&mut self, order: I) -> &mut Self {
+        self.track_order = order.into_iter().collect();
+        self
+    }
+
+    /// Unlike [`pin_left`], this can also unpin the pinned track.
+    ///
+    /// [`pin_left`]: #method.pin_left
+    pub fn set_pin_left(&mut self, track_id: Option<TrackId>) -> &mut Self {
+        self.pinned = track_id;
+        self
+    }
+
+    /// Like [`alias_color`].
+    ///
+    /// [`alias_color`]: #method.alias_color
+    pub fn add_color_alias(&mut self, existing: TrackId, new: TrackId) -> &mut Self {
+        self.color_aliases.push((existing, new));
+        self
+    }
+
+    /// Like [`inherit_colors`].
+    ///
+    /// [`inherit_colors`]: #method.inherit_colors
+    pub fn set_inherit_colors(&mut self, inherit_colors: bool) -> &mut Self {
+        self.inherit_colors = inherit_colors;
+        self
+    }
+
+    /// Unlike [`station_format`], this can also remove the format.
+    ///
+    /// [`station_format`]: #method.station_format
+    pub fn set_station_format(&mut self, template: Option<String>) -> &mut Self {
+        self.station_format = template;
+        self
+    }
+
+    /// Like [`field_color`].
+    ///
+    /// [`field_color`]: #method.field_color
+    pub fn add_field_color<S: Into<String>>(&mut self, name: S, color: [u8; 3]) -> &mut Self {
+        self.field_colors.push((name.into(), color));
+        self
+    }
+
+    /// Like [`hide_tags`].
+    ///
+    /// [`hide_tags`]: #method.hide_tags
+    pub fn set_hide_tags(&mut self, tags: &[&str]) -> &mut Self {
+        self.hidden_tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    /// Like [`min_level`].
+    ///
+    /// [`min_level`]: #method.min_level
+    pub fn set_min_level(&mut self, level: Level) -> &mut Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Like [`row_numbers`].
+    ///
+    /// [`row_numbers`]: #method.row_numbers
+    pub fn set_row_numbers(&mut self, row_numbers: bool) -> &mut Self {
+        self.row_numbers = row_numbers;
+        self
+    }
+
+    /// Like [`prefix`].
+    ///
+    /// [`prefix`]: #method.prefix
+    pub fn set_prefix<S: Into<String>>(&mut self, prefix: S) -> &mut Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Like [`indent`].
+    ///
+    /// [`indent`]: #method.indent
+    pub fn set_indent(&mut self, width: usize) -> &mut Self {
+        self.set_prefix(" ".repeat(width))
+    }
+
+    /// Like [`inline_markup`].
+    ///
+    /// [`inline_markup`]: #method.inline_markup
+    pub fn set_inline_markup(&mut self, inline_markup: bool) -> &mut Self {
+        self.inline_markup = inline_markup;
+        self
+    }
+
+    /// Unlike [`time_spacing`], this can also stop spacing out stations.
+    ///
+    /// [`time_spacing`]: #method.time_spacing
+    pub fn set_time_spacing(&mut self, spacing: Option<(Duration, usize)>) -> &mut Self {
+        self.time_spacing = spacing;
+        self
+    }
+
+    /// Like [`track_weight`].
+    ///
+    /// [`track_weight`]: #method.track_weight
+    pub fn set_track_weight(&mut self, track_id: TrackId, weight: usize) -> &mut Self {
+        self.track_weights.retain(|&(t, _)| t != track_id);
+        self.track_weights.push((track_id, weight));
+        self
+    }
+
+    /// Like [`track_lanes`].
+    ///
+    /// [`track_lanes`]: #method.track_lanes
+    pub fn set_track_lanes(&mut self, track_id: TrackId, lanes: usize) -> &mut Self {
+        self.track_lanes.retain(|&(t, _)| t != track_id);
+        self.track_lanes.push((track_id, lanes));
+        self
+    }
+
+    /// Like [`ghost_track`].
+    ///
+    /// [`ghost_track`]: #method.ghost_track
+    pub fn set_ghost_track(&mut self, track_id: TrackId, ghost: bool) -> &mut Self {
+        self.ghost_tracks.retain(|&t| t != track_id);
+        if ghost {
+            self.ghost_tracks.push(track_id);
+        }
+        self
+    }
+
+    /// Like [`track_label`].
+    ///
+    /// [`track_label`]: #method.track_label
+    pub fn set_track_label<S: Into<String>>(&mut self, track_id: TrackId, label: S) -> &mut Self {
+        self.track_labels.retain(|(t, _)| *t != track_id);
+        self.track_labels.push((track_id, label.into()));
+        self
+    }
+
+    /// Like [`track_link`].
+    ///
+    /// [`track_link`]: #method.track_link
+    pub fn set_track_link<S: Into<String>>(&mut self, track_id: TrackId, url: S) -> &mut Self {
+        self.track_links.retain(|(t, _)| *t != track_id);
+        self.track_links.push((track_id, url.into()));
+        self
+    }
+
+    /// Unlike [`header_every`], this can also stop repeating headers.
+    ///
+    /// [`header_every`]: #method.header_every
+    pub fn set_header_every(&mut self, rows: Option<usize>) -> &mut Self {
+        self.header_every = rows;
+        self
+    }
+
+    /// Like [`ruler`].
+    ///
+    /// [`ruler`]: #method.ruler
+    pub fn set_ruler(&mut self, ruler: bool) -> &mut Self {
+        self.ruler = ruler;
+        self
+    }
+
+    /// Like [`severity_color`].
+    ///
+    /// [`severity_color`]: #method.severity_color
+    pub fn set_severity_color(&mut self, severity: Severity, color: [u8; 3]) -> &mut Self {
+        self.severity_colors[severity as usize] = color;
+        self
+    }
+
+    /// Like [`summary`].
+    ///
+    /// [`summary`]: #method.summary
+    pub fn set_summary(&mut self, summary: bool) -> &mut Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Like [`abort_reason`].
+    ///
+    /// [`abort_reason`]: #method.abort_reason
+    pub fn set_abort_reason<S: Into<String>>(&mut self, track_id: TrackId, reason: S) -> &mut Self {
+        self.abort_reasons.retain(|(t, _)| *t != track_id);
+        self.abort_reasons.push((track_id, reason.into()));
+        self
+    }
+
+    /// Like [`elastic_columns`].
+    ///
+    /// [`elastic_columns`]: #method.elastic_columns
+    pub fn set_elastic_columns(&mut self, elastic_columns: bool) -> &mut Self {
+        self.elastic_columns = elastic_columns;
+        self
+    }
+
+    /// Like [`track_spacing`].
+    ///
+    /// [`track_spacing`]: #method.track_spacing
+    pub fn set_track_spacing(&mut self, track_id: TrackId, spacing: usize) -> &mut Self {
+        self.track_spacings.retain(|&(t, _)| t != track_id);
+        self.track_spacings.push((track_id, spacing));
+        self
+    }
+
+    /// Unlike [`page_break`], this can also stop splitting into pages.
+    ///
+    /// [`page_break`]: #method.page_break
+    pub fn set_page_break(&mut self, page_break: Option<(usize, String)>) -> &mut Self {
+        self.page_break = page_break;
+        self
+    }
+
+    /// Returns the splat factor, set by [`splat`].
+    ///
+    /// [`splat`]: #method.splat
+    pub fn get_splat(&self) -> usize {
+        self.splat
+    }
+
+    /// Returns whether tracks are colored, set by [`color`].
+    ///
+    /// [`color`]: #method.color
+    pub fn get_color(&self) -> bool {
+        self.color
+    }
+
+    /// Returns whether corners are rounded, set by [`rounded`].
+    ///
+    /// [`rounded`]: #method.rounded
+    pub fn get_rounded(&self) -> bool {
+        self.rounded
+    }
+
+    /// Returns what the time column shows, set by [`time_column`].
+    ///
+    /// [`time_column`]: #method.time_column
+    pub fn get_time_column(&self) -> TimeColumn {
+        self.time_column
+    }
+
+    /// Returns the format of absolute times, set by [`time_format`].
+    ///
+    /// [`time_format`]: #method.time_format
+    pub fn get_time_format(&self) -> &str {
+        &self.time_format
+    }
+
+    /// Returns what is colored by duration, set by [`heat`].
+    ///
+    /// [`heat`]: #method.heat
+    pub fn get_heat(&self) -> Heat {
+        self.heat
+    }
+
+    /// Returns the marker of detached stations, set by [`detached_marker`].
+    ///
+    /// [`detached_marker`]: #method.detached_marker
+    pub fn get_detached_marker(&self) -> Option<&str> {
+        self.detached_marker.as_deref()
+    }
+
+    /// Returns whether rendering starts with a track `0`, set by [`implicit_root`].
+    ///
+    /// [`implicit_root`]: #method.implicit_root
+    pub fn get_implicit_root(&self) -> bool {
+        self.implicit_root
+    }
+
+    /// Returns every group, set by [`group`].
+    ///
+    /// [`group`]: #method.group
+    pub fn get_groups(&self) -> Vec<Range<TrackId>> {
+        self.groups
+            .iter()
+            .map(|group| TrackId(group.start)..TrackId(group.end))
+            .collect()
+    }
+
+    /// Returns whether group names are rendered as a header, set by [`swimlanes`].
+    ///
+    /// [`swimlanes`]: #method.swimlanes
+    pub fn get_swimlanes(&self) -> bool {
+        self.swimlanes
+    }
+
+    /// Returns every group with a name, along with its name.
+    pub fn get_named_groups(&self) -> Vec<(Range<TrackId>, &str)> {
+        self.group_names
+            .iter()
+            .map(|(group, name)| (TrackId(group.start)..TrackId(group.end), name.as_str()))
+            .collect()
+    }
+
+    /// Returns the preferred order of tracks, set by [`track_order`].
+    ///
+    /// [`track_order`]: #method.track_order
+    pub fn get_track_order(&self) -> &[TrackId] {
+        &self.track_order
+    }
+
+    /// Returns the track pinned left, if any, set by [`pin_left`].
+    ///
+    /// [`pin_left`]: #method.pin_left
+    pub fn get_pin_left(&self) -> Option<TrackId> {
+        self.pinned
+    }
+
+    /// Returns every `(existing, new)` color alias.
+    pub fn get_color_aliases(&self) -> &[(TrackId, TrackId)] {
+        &self.color_aliases
+    }
+
+    /// Returns whether split tracks inherit colors, set by [`inherit_colors`].
+    ///
+    /// [`inherit_colors`]: #method.inherit_colors
+    pub fn get_inherit_colors(&self) -> bool {
+        self.inherit_colors
+    }
+
+    /// Returns the template of stations, if any, set by [`station_format`].
+    ///
+    /// [`station_format`]: #method.station_format
+    pub fn get_station_format(&self) -> Option<&str> {
+        self.station_format.as_deref()
+    }
+
+    /// Returns every `(name, color)` field color.
+    pub fn get_field_colors(&self) -> &[(String, [u8; 3])] {
+        &self.field_colors
+    }
+
+    /// Returns every hidden tag, set by [`hide_tags`].
+    ///
+    /// [`hide_tags`]: #method.hide_tags
+    pub fn get_hide_tags(&self) -> &[String] {
+        &self.hidden_tags
+    }
+
+    /// Returns the lowest rendered level, set by [`min_level`].
+    ///
+    /// [`min_level`]: #method.min_level
+    pub fn get_min_level(&self) -> Level {
+        self.min_level
+    }
+
+    /// Returns whether rows are numbered, set by [`row_numbers`].
+    ///
+    /// [`row_numbers`]: #method.row_numbers
+    pub fn get_row_numbers(&self) -> bool {
+        self.row_numbers
+    }
+
+    /// Returns the prefix of every line, set by [`prefix`].
+    ///
+    /// [`prefix`]: #method.prefix
+    pub fn get_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns whether inline markup is rendered, set by [`inline_markup`].
+    ///
+    /// [`inline_markup`]: #method.inline_markup
+    pub fn get_inline_markup(&self) -> bool {
+        self.inline_markup
+    }
+
+    /// Returns `(per_row, max_rows)`, if stations are spaced out.
+    pub fn get_time_spacing(&self) -> Option<(Duration, usize)> {
+        self.time_spacing
+    }
+
+    /// Returns the weight of `track_id`, set by [`track_weight`].
+    ///
+    /// [`track_weight`]: #method.track_weight
+    pub fn get_track_weight(&self, track_id: TrackId) -> usize {
+        self.track_weights
+            .iter()
+            .find(|&&(t, _)| t == track_id)
+            .map_or(1, |&(_, weight)| weight)
+    }
+
+    /// Returns the lanes of `track_id`, set by [`track_lanes`].
+    ///
+    /// [`track_lanes`]: #method.track_lanes
+    pub fn get_track_lanes(&self, track_id: TrackId) -> usize {
+        self.track_lanes
+            .iter()
+            .find(|&&(t, _)| t == track_id)
+            .map_or(1, |&(_, lanes)| lanes)
+    }
+
+    /// Returns whether `track_id` is a ghost track, set by [`ghost_track`].
+    ///
+    /// [`ghost_track`]: #method.ghost_track
+    pub fn get_ghost_track(&self, track_id: TrackId) -> bool {
+        self.ghost_tracks.contains(&track_id)
+    }
+
+    /// Returns the label of `track_id`, if it has one besides its ID.
+    pub fn get_track_label(&self, track_id: TrackId) -> Option<&str> {
+        self.track_labels
+            .iter()
+            .find(|(t, _)| *t == track_id)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// The URL the label of `track_id` links to, if any.
+    pub fn get_track_link(&self, track_id: TrackId) -> Option<&str> {
+        self.track_links
+            .iter()
+            .find(|(t, _)| *t == track_id)
+            .map(|(_, url)| url.as_str())
+    }
+
+    /// The label of `track_id` in headers.
+    pub(crate) fn label_of(&self, track_id: TrackId) -> Cow<'_, str> {
+        match self.get_track_label(track_id) {
+            Some(label) => Cow::Borrowed(label),
+            None => Cow::Owned(track_id.to_string()),
+        }
+    }
+
+    /// Whether the weight of `track_id` is set, even to the default.
+    pub(crate) fn has_track_weight(&self, track_id: TrackId) -> bool {
+        self.track_weights.iter().any(|&(t, _)| t == track_id)
+    }
+
+    /// Whether the lanes of `track_id` are set, even to the default.
+    pub(crate) fn has_track_lanes(&self, track_id: TrackId) -> bool {
+        self.track_lanes.iter().any(|&(t, _)| t == track_id)
+    }
+
+    /// Whether the spacing of `track_id` is set, even to the default.
+    pub(crate) fn has_track_spacing(&self, track_id: TrackId) -> bool {
+        self.track_spacings.iter().any(|&(t, _)| t == track_id)
+    }
+
+    /// Returns the rows between headers, if any, set by [`header_every`].
+    ///
+    /// [`header_every`]: #method.header_every
+    pub fn get_header_every(&self) -> Option<usize> {
+        self.header_every
+    }
+
+    /// Returns whether rulers are rendered, set by [`ruler`].
+    ///
+    /// [`ruler`]: #method.ruler
+    pub fn get_ruler(&self) -> bool {
+        self.ruler
+    }
+
+    /// Returns the color of `severity`, set by [`severity_color`].
+    ///
+    /// [`severity_color`]: #method.severity_color
+    pub fn get_severity_color(&self, severity: Severity) -> [u8; 3] {
+        self.severity_colors[severity as usize]
+    }
+
+    /// Returns whether a summary is rendered, set by [`summary`].
+    ///
+    /// [`summary`]: #method.summary
+    pub fn get_summary(&self) -> bool {
+        self.summary
+    }
+
+    /// Returns the reason `track_id` was aborted, if it was.
+    pub fn get_abort_reason(&self, track_id: TrackId) -> Option<&str> {
+        self.abort_reasons
+            .iter()
+            .find(|(t, _)| *t == track_id)
+            .map(|(_, reason)| reason.as_str())
+    }
+
+    /// Returns whether columns are elastic, set by [`elastic_columns`].
+    ///
+    /// [`elastic_columns`]: #method.elastic_columns
+    pub fn get_elastic_columns(&self) -> bool {
+        self.elastic_columns
+    }
+
+    /// Returns the spacing of `track_id`, set by [`track_spacing`].
+    ///
+    /// [`track_spacing`]: #method.track_spacing
+    pub fn get_track_spacing(&self, track_id: TrackId) -> usize {
+        self.track_spacings
+            .iter()
+            .find(|&&(t, _)| t == track_id)
+            .map_or(self.splat, |&(_, spacing)| spacing)
+    }
+
+    /// Returns `(rows, separator)`, if the rendering is split into pages.
+    pub fn get_page_break(&self) -> Option<(usize, &str)> {
+        self.page_break
+            .as_ref()
+            .map(|(rows, separator)| (*rows, separator.as_str()))
+    }
+
+    /// Whether a page starts at row `i`, after the first page.
+    fn starts_page(&self, i: usize) -> bool {
+        matches!(self.page_break, Some((rows, _)) if rows > 0 && i > 0 && i % rows == 0)
+    }
+
+    /// Whether `station` is hidden, and not rendered at all.
+    pub(crate) fn is_hidden(&self, station: &Station) -> bool {
+        station.level().is_some_and(|level| level < self.min_level)
+            || station
+                .tags()
+                .any(|tag| self.hidden_tags.iter().any(|hidden| hidden == tag))
+    }
+
+    /// The lines of the text of `station`, with its fields resolved.
+    pub(crate) fn station_lines<'e>(&self, station: &'e Station) -> Vec<Cow<'e, str>> {
+        let lines: Vec<Cow<str>> = if station.has_fields() {
+            let template = self.station_format.as_deref().unwrap_or(station.text());
+            let text = template::resolve(template, station.fields(), |name, value| {
+                let color = self.field_colors.iter().find(|(n, _)| n == name);
+                match color {
+                    Some((_, [r, g, b])) if self.color => paint(value, Rgb(*r, *g, *b)),
+                    _ => value.to_owned(),
+                }
+            });
+            text.lines()
+                .map(|line| Cow::Owned(line.to_owned()))
+                .collect()
+        } else {
+            match station.styled() {
+                Some(styled) if cfg!(feature = "color") && self.color => styled
+                    .to_ansi()
+                    .lines()
+                    .map(|line| Cow::Owned(line.to_owned()))
+                    .collect(),
+                _ => station.text().lines().map(Cow::Borrowed).collect(),
+            }
+        };
+
+        let styled = cfg!(feature = "color") && self.color;
+        let lines: Vec<Cow<str>> = if self.inline_markup {
+            lines
+                .iter()
+                .map(|line| Cow::Owned(markup::apply(line, styled)))
+                .collect()
+        } else {
+            lines
+        };
+        let lines: Vec<Cow<str>> = match station.severity() {
+            Some(severity) if styled => {
+                let [r, g, b] = self.get_severity_color(severity);
+                lines
+                    .iter()
+                    .map(|line| Cow::Owned(paint(line, Rgb(r, g, b))))
+                    .collect()
+            }
+            _ => lines,
+        };
+
+        let mut lines: Vec<Cow<str>> = match station.link() {
+            // OSC 8 hyperlinks, which terminals without support ignore
+            Some(url) if styled => lines
+                .iter()
+                .map(|line| Cow::Owned(format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, line)))
+                .collect(),
+            _ => lines,
+        };
+
+        if let Some(status) = station.status() {
+            let marker = match status {
+                Status::Failed if styled => {
+                    let [r, g, b] = self.get_severity_color(Severity::Error);
+                    paint(status.marker(), Rgb(r, g, b))
+                }
+                _ => status.marker().to_owned(),
+            };
+            match lines.first_mut() {
+                Some(line) => *line = Cow::Owned(format!("{} {}", marker, line)),
+                None => lines.push(Cow::Owned(marker)),
+            }
+        }
+
+        if let Some(badge) = station.badge() {
+            let badge = match styled {
+                true => format!("\x1b[1m({})\x1b[22m", badge),
+                false => format!("({})", badge),
+            };
+            match lines.first_mut() {
+                Some(line) => *line = Cow::Owned(format!("{} {}", line, badge)),
+                None => lines.push(Cow::Owned(badge)),
+            }
+        }
+
+        // Embedded metros are rendered below the text, indented
+        if !station.nested().is_empty() {
+            let mut rdr = self.clone().row_numbers(false).ruler(false).prefix("");
+            rdr.set_page_break(None).set_header_every(None);
+            let mut nested = Vec::new();
+            if render(&mut nested, station.nested(), &rdr).is_ok() {
+                let nested = rendered_to_string(nested);
+                lines.extend(nested.lines().map(|line| Cow::Owned(format!("  {}", line))));
+            }
+        }
+        lines
+    }
+
+    fn colorize<S: AsRef<str>>(&self, s: S, i: &TrackId) -> RailString {
+        #[cfg(feature = "color")]
+        if self.color {
+            use std::fmt::Write;
+
+            let color = XtermColors::from(track_xterm_color(*i));
+            let mut r = RailString::default();
+            write!(r, "{}", s.as_ref().color(color)).unwrap();
+            return r;
+        }
+        #[cfg(not(feature = "color"))]
+        let _ = i;
+        s.as_ref().into()
+    }
+
+    /// The RGB color rails of `track_id` are rendered in.
+    pub(crate) fn track_rgb(&self, track_id: TrackId) -> (u8, u8, u8) {
+        html::xterm_to_rgb(track_xterm_color(track_id))
+    }
+
+    pub(crate) fn initial_tracks(&self) -> Vec<TrackId> {
+        if self.implicit_root {
+            vec![TrackId(0)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The marker to render before the text of `row`, if any.
+    fn marker_for(&self, row: &Row) -> Option<&str> {
+        self.detached_marker.as_deref().filter(|_| row.detached)
+    }
+
+    /// The width of every column of `rows`: one more than the widest
+    /// [spacing] of the tracks it holds, at any row, or than their
+    /// [elastic] spacing. Empty if every column is as wide as
+    /// [`splat`] makes it.
+    ///
+    /// [spacing]: #method.track_spacing
+    /// [elastic]: #method.elastic_columns
+    /// [`splat`]: #method.splat
+    fn column_widths(&self, rows: &[Row]) -> Vec<usize> {
+        let mut widths = Vec::new();
+        self.widen_columns(rows, &mut HashMap::new(), &mut widths);
+        widths
+    }
+
+    /// Widen the [column widths] of the rows so far, with `stations`
+    /// stations per track, for `rows` following them.
+    ///
+    /// [column widths]: #method.column_widths
+    fn widen_columns(
+        &self,
+        rows: &[Row],
+        stations: &mut HashMap<TrackId, usize>,
+        widths: &mut Vec<usize>,
+    ) {
+        if self.track_spacings.is_empty() && !self.elastic_columns {
+            return;
+        }
+
+        // The number of stations of every track, for elastic columns
+        if self.elastic_columns {
+            for row in rows.iter().filter(|row| row.starts_station) {
+                for &(rail, track_id) in row.rails.iter() {
+                    if matches!(rail, Rail::Station | Rail::SharedStation) {
+                        *stations.entry(track_id).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        let busiest = stations.values().copied().max().unwrap_or(0);
+        let spacing =
+            |track_id: TrackId| match self.track_spacings.iter().find(|&&(t, _)| t == track_id) {
+                Some(&(_, spacing)) => spacing,
+                None if self.elastic_columns => {
+                    let stations = stations.get(&track_id).copied().unwrap_or(0);
+                    (2 * self.splat * stations).div_ceil(busiest.max(1))
+                }
+                None => self.splat,
+            };
+
+        for row in rows {
+            let mut column = 0;
+            for &(rail, track_id) in row.rails.iter() {
+                let spanned = match rail {
+                    Rail::ShiftLeft | Rail::ShiftRight => 2,
+                    _ => 1,
+                };
+                // Rails crossing a column are colored like another track
+                if !matches!(rail, Rail::Horizontal | Rail::SharedHorizontal) {
+                    if widths.len() < column + spanned {
+                        widths.resize(column + spanned, 1);
+                    }
+                    let width = spacing(track_id) + 1;
+                    for w in widths[column..column + spanned].iter_mut() {
+                        *w = (*w).max(width);
+                    }
+                }
+                column += spanned;
+            }
+        }
+    }
+
+    /// Render every rail of `rails`, laid out in columns of `widths`.
+    ///
+    /// Rails leading right, e.g. [`Rail::SplitRight`], leave the rest
+    /// of their column to the next rail, which draws it first.
+    fn rails_to_str(&self, rails: &[(Rail, TrackId)], widths: &[usize]) -> Vec<RailString> {
+        let width = |column: usize| widths.get(column).copied().unwrap_or(self.splat + 1);
+        let mut column = 0;
+        let mut open = 0;
+        rails
+            .iter()
+            .map(|&(rail, track_id)| {
+                let r = self.rail_to_str(rail, track_id, open, width(column), width(column + 1));
+                open = match rail {
+                    Rail::SplitRight | Rail::Horizontal => width(column) - 1,
+                    _ => 0,
+                };
+                column += match rail {
+                    Rail::ShiftLeft | Rail::ShiftRight => 2,
+                    _ => 1,
+                };
+                r
+            })
+            .collect()
+    }
+
+    /// Render `rail`, double-struck if it belongs to a [heavy] track,
+    /// dotted if it belongs to a [ghost] track, and as a bundle of
+    /// rails if it belongs to a track with [lanes].
+    ///
+    /// The rail is drawn in a column `width` characters wide, after
+    /// `open` characters left of it by the previous rail, and shifts
+    /// cross the next column too, `next` characters wide.
+    ///
+    /// [heavy]: #method.track_weight
+    /// [ghost]: #method.ghost_track
+    /// [lanes]: #method.track_lanes
+    fn rail_to_str(
+        &self,
+        rail: Rail,
+        track_id: TrackId,
+        open: usize,
+        width: usize,
+        next: usize,
+    ) -> RailString {
+        use std::fmt::Write;
+
+        let ghost = self.get_ghost_track(track_id);
+        let heavy = !ghost && self.is_heavy(track_id);
+        let (top_left, top_right, bottom_left, bottom_right) = if heavy {
+            ('╔', '╗', '╚', '╝')
+        } else if self.rounded {
+            ('╭', '╮', '╰', '╯')
+        } else {
+            ('┌', '┐', '└', '┘')
+        };
+        let (straight, horizontal, station, ground, split_right, split_left) = if heavy {
+            ("║", "═", "╬", "╨", '╠', '╣')
+        } else if ghost {
+            ("┆", "┄", "╪", "┷", '├', '┤')
+        } else {
+            ("│", "─", "╪", "┷", '├', '┤')
+        };
+        let (tee_down, tee_up) = if heavy {
+            ("╦", "╩")
+        } else {
+            ("┬", "┴")
+        };
+
+        // Lanes are drawn within the column of their track, so
+        // the first lane is drawn as usual, and the others after it
+        let spacing = width - 1;
+        let more = match rail {
+            Rail::ShiftLeft | Rail::ShiftRight => spacing.min(next - 1),
+            _ => spacing,
+        };
+        let more = (self.lanes_of(track_id) - 1).min(more);
+        let gap = spacing - more;
+        let next_gap = next.saturating_sub(1 + more);
+
+        let mut r = RailString::with_capacity(open + width + next);
+        r.push_str(&horizontal.repeat(open));
+        match rail {
+            Rail::Straight => write!(r, "{}{}", straight.repeat(more + 1), " ".repeat(gap)),
+            Rail::Horizontal => write!(r, "{}", horizontal),
+            Rail::Station => write!(r, "{}{}", station.repeat(more + 1), " ".repeat(gap)),
+            Rail::SharedStation => write!(r, "{}{}", station.repeat(more + 1), "═".repeat(gap)),
+            Rail::SharedHorizontal => write!(r, "{}", "═".repeat(width)),
+            Rail::Ground => write!(r, "{}{}", ground.repeat(more + 1), " ".repeat(gap)),
+            Rail::Aborted => write!(r, "{}{}", "✗".repeat(more + 1), " ".repeat(gap)),
+            Rail::ShiftRight => write!(
+                r,
+                "{bottom_left}{}{}{}{top_right}{}",
+                tee_up.repeat(more),
+                horizontal.repeat(gap),
+                tee_down.repeat(more),
+                " ".repeat(next_gap)
+            ),
+            Rail::ShiftLeft => write!(
+                r,
+                "{top_left}{}{}{}{bottom_right}{}",
+                tee_down.repeat(more),
+                horizontal.repeat(gap),
+                tee_up.repeat(more),
+                " ".repeat(next_gap)
+            ),
+            Rail::TopRight => write!(r, "{}{top_right}{}", tee_down.repeat(more), " ".repeat(gap)),
+            Rail::BottomRight => write!(
+                r,
+                "{}{bottom_right}{}",
+                tee_up.repeat(more),
+                " ".repeat(gap)
+            ),
+            Rail::TopLeft => write!(
+                r,
+                "{top_left}{}{}",
+                tee_down.repeat(more),
+                horizontal.repeat(gap)
+            ),
+            Rail::BottomtLeft => write!(
+                r,
+                "{bottom_left}{}{}",
+                tee_up.repeat(more),
+                horizontal.repeat(gap)
+            ),
+            Rail::SplitRight => write!(r, "{split_right}"),
+            Rail::SplitLeft | Rail::LinkEnd => write!(
+                r,
+                "{split_left}{}{}",
+                straight.repeat(more),
+                " ".repeat(gap)
+            ),
+        }
+        .unwrap();
+        r
+    }
+
+    /// The rails of the row above the rendered `rail` leading into it,
+    /// or of the row below it if not `upwards`, drawn straight.
+    fn rail_continued(&self, rail: &str, track_id: TrackId, upwards: bool) -> RailString {
+        let straight = self
+            .rail_to_str(Rail::Straight, track_id, 0, 1, 1)
+            .chars()
+            .next();
+        rail.chars()
+            .map(|c| match c {
+                // Every glyph connected upwards
+                '│' | '║' | '┆' | '╪' | '╬' | '├' | '╠' | '┤' | '╣' => {
+                    straight.unwrap_or(c)
+                }
+                '┷' | '╨' | '└' | '╚' | '╰' | '┘' | '╝' | '╯' | '┴' | '╩' if upwards => {
+                    straight.unwrap_or(c)
+                }
+                // Every glyph connected downwards
+                '┌' | '╔' | '╭' | '┐' | '╗' | '╮' | '┬' | '╦' if !upwards => {
+                    straight.unwrap_or(c)
+                }
+                _ => ' ',
+            })
+            .collect()
+    }
+
+    /// The offset of every track continuing into `rails` from the row
+    /// above, or from `rails` into the row below if not `upwards`,
+    /// from left to right.
+    fn track_columns(
+        &self,
+        rails: &[(Rail, TrackId)],
+        widths: &[usize],
+        upwards: bool,
+    ) -> Vec<(usize, TrackId)> {
+        let mut columns = Vec::new();
+        let mut offset = 0;
+        let rendered = self.rails_to_str(rails, widths);
+        for ((_, track_id), rail) in rails.iter().zip(rendered.iter()) {
+            let continued = self.rail_continued(rail, *track_id, upwards);
+            if let Some(i) = continued.chars().position(|c| c != ' ') {
+                columns.push((offset + i, *track_id));
+            }
+            offset += continued.chars().count();
+        }
+        columns
+    }
+
+    /// The line of brackets to draw above every station of `rows`, if
+    /// the columns of the [named groups] changed since the last line,
+    /// `drawn` before `rows` and then updated.
+    ///
+    /// [named groups]: #method.named_group
+    fn group_brackets(
+        &self,
+        rows: &[Row],
+        widths: &[usize],
+        drawn: &mut Vec<(usize, usize, String)>,
+    ) -> Vec<Option<String>> {
+        let mut brackets = Vec::with_capacity(rows.len());
+        for row in rows {
+            // Tracks move around in between stations
+            if self.group_names.is_empty() || self.swimlanes || !row.starts_station {
+                brackets.push(None);
+                continue;
+            }
+            let columns = self.track_columns(&row.rails, widths, true);
+            let mut spans = self
+                .group_names
+                .iter()
+                .filter_map(|(group, name)| {
+                    let mut offsets = columns
+                        .iter()
+                        .filter(|(_, t)| group.contains(&t.0))
+                        .map(|&(offset, _)| offset);
+                    let first = offsets.next()?;
+                    Some((first, offsets.next_back().unwrap_or(first), name.as_str()))
+                })
+                .collect::<Vec<_>>();
+            spans.sort_unstable();
+            if spans
+                .iter()
+                .copied()
+                .eq(drawn.iter().map(|(f, l, n)| (*f, *l, n.as_str())))
+            {
+                brackets.push(None);
+                continue;
+            }
+            brackets.push(Some(bracket_line(&spans)).filter(|_| !spans.is_empty()));
+            *drawn = spans
+                .into_iter()
+                .map(|(first, last, name)| (first, last, name.to_owned()))
+                .collect();
+        }
+        brackets
+    }
+
+    /// The [swimlane] header of `rows`, naming every named group over
+    /// every column its tracks take, if any.
+    ///
+    /// [swimlane]: #method.swimlanes
+    fn swimlane_header(&self, rows: &[Row], widths: &[usize]) -> Option<String> {
+        if !self.swimlanes || self.group_names.is_empty() {
+            return None;
+        }
+        let columns = rows
+            .iter()
+            .flat_map(|row| self.track_columns(&row.rails, widths, true))
+            .collect::<Vec<_>>();
+        let mut spans = self
+            .group_names
+            .iter()
+            .filter_map(|(group, name)| {
+                let offsets = columns
+                    .iter()
+                    .filter(|(_, t)| group.contains(&t.0))
+                    .map(|&(offset, _)| offset);
+                let first = offsets.clone().min()?;
+                Some((first, offsets.max().unwrap_or(first), name.as_str()))
+            })
+            .collect::<Vec<_>>();
+        spans.sort_unstable();
+
+        let mut line = String::new();
+        let mut width = 0;
+        for (i, &(first, last, name)) in spans.iter().enumerate() {
+            if first < width {
+                continue;
+            }
+            // Centered over its lane, unless it overflows it
+            let length = name.chars().count();
+            let start = first + (last + 1 - first).saturating_sub(length) / 2;
+            let room = match spans.get(i + 1) {
+                Some(&(next, _, _)) => next.saturating_sub(start + 1).max(1),
+                None => usize::MAX,
+            };
+            line.push_str(&" ".repeat(start - width));
+            line.extend(name.chars().take(room));
+            width = start + length.min(room);
+        }
+        Some(line)
+    }
+
+    /// The labels of the tracks continuing into `rails` from the row
+    /// above, or from `rails` into the row below if not `upwards`,
+    /// each at the column of its rail, laid out in columns of `widths`.
+    fn labels(
+        &self,
+        rails: &[(Rail, TrackId)],
+        widths: &[usize],
+        colors: &ColorAliases,
+        upwards: bool,
+    ) -> String {
+        let columns = self.track_columns(rails, widths, upwards);
+        let mut labels = String::new();
+        let mut width = 0;
+        for (i, &(offset, track_id)) in columns.iter().enumerate() {
+            let room = match columns.get(i + 1) {
+                Some(&(next, _)) => next - offset - 1,
+                None => self.get_track_spacing(track_id),
+            };
+            let label = self
+                .label_of(track_id)
+                .chars()
+                .take(room.clamp(1, self.get_track_spacing(track_id).max(1)))
+                .collect::<String>();
+            labels.push_str(&" ".repeat(offset.saturating_sub(width)));
+            width = offset.max(width) + label.chars().count();
+            let label = self.colorize(label, &colors.resolve(track_id));
+            match self.get_track_link(track_id) {
+                // OSC 8 hyperlinks, like the text of linked stations
+                Some(url) if cfg!(feature = "color") && self.color => {
+                    labels.push_str(&format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, label))
+                }
+                _ => labels.push_str(&label),
+            }
+        }
+        labels
+    }
+
+    /// Color a rendered rail of `track_id`, colored like `color`.
+    fn paint_rail(&self, rail: RailString, track_id: TrackId, color: &TrackId) -> RailString {
+        let rail = self.colorize(rail, color);
+        if cfg!(feature = "color") && self.color && self.get_ghost_track(track_id) {
+            let mut dimmed = RailString::with_capacity(rail.len() + 9);
+            dimmed.push_str("\x1b[2m");
+            dimmed.push_str(&rail);
+            dimmed.push_str("\x1b[22m");
+            dimmed
+        } else {
+            rail
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+/// An ID referencing a `Track`
+pub struct TrackId(pub(crate) usize);
+
+impl fmt::Display for TrackId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<usize> for TrackId {
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TrackId> for usize {
+    fn from(id: TrackId) -> Self {
+        id.0
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Event<'a> {
+    /// `StartTrack(track_id)`
+    ///
+    /// - If `track_id` already exists, then this event does nothing.
+    ///
+    /// New `track_id`s are added rightmost.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `StartTrack(4)` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | | | |
+    /// ```
+    ///
+    /// Starting a track twice is harmless, which makes it
+    /// safe to render machine-generated streams:
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let events = [Event::StartTrack(1.into()), Event::StartTrack(1.into()), Event::NoEvent];
+    /// assert_eq!(
+    ///     metro::to_string(&events).unwrap(),
+    ///     metro::to_string(&events[1..]).unwrap(),
+    /// );
+    /// ```
+    StartTrack(TrackId),
+
+    /// `StartTracks(track_ids)`
+    ///
+    /// - If a `track_id` from `track_ids` already exists, then it is ignored.
+    /// - If all `track_ids` already exists, then this event does nothing.
+    ///
+    /// New `track_id`s are added rightmost.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `StartTracks([4, 5])` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | | | | |
+    /// ```
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let events = [
+    ///     Event::StartTrack(1.into()),
+    ///     Event::StartTracks(vec![0.into(), 1.into(), 2.into()].into()),
+    ///     Event::NoEvent,
+    /// ];
+    /// assert_eq!(
+    ///     metro::to_string(&events).unwrap(),
+    ///     metro::to_string(&[Event::StartTracks(vec![1.into(), 2.into()].into()), Event::NoEvent]).unwrap(),
+    /// );
+    /// ```
+    StartTracks(Cow<'a, [TrackId]>),
+
+    /// `StopTrack(track_id)`
+    ///
+    /// - If `track_id` does not exist, then this event does nothing.
+    ///
+    /// All rails to the right of `track_id`, are pulled to the left.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `StopTrack(1)` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | " |
+    /// |  /
+    /// | |
+    /// ```
+    ///
+    /// ## Rendering
+    ///
+    /// Only the stopped track is grounded, the rails to its right
+    /// are then pulled to the left one at a time.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, TrackId};
+    ///
+    /// let render = |tracks: usize, stopped: usize| {
+    ///     let rdr = RenderingSettings::default().color(false).splat(1);
+    ///     let mut metro = metro::events::Metro::with_settings(rdr);
+    ///     let track_ids = (0..tracks).map(TrackId::from).collect::<Vec<_>>();
+    ///     metro.push(Event::StartTracks(track_ids.into()));
+    ///     metro.push(Event::StopTrack(stopped.into()));
+    ///     metro.to_string().unwrap()
+    /// };
+    ///
+    /// assert_eq!(render(1, 0), "┷ \n");
+    /// assert_eq!(render(2, 1), "│ ┷ \n");
+    /// assert_eq!(render(2, 0), "┷ │ \n┌─┘ \n");
+    /// assert_eq!(
+    ///     render(5, 2),
+    ///     "│ │ ┷ │ │ \n\
+    ///      │ │ ┌─┘ │ \n\
+    ///      │ │ │ ┌─┘ \n",
+    /// );
+    /// ```
+    StopTrack(TrackId),
+
+    /// `Station(track_id, text)`
+    ///
+    /// - If the `track_id` does not exist, then `text` is still
+    ///   rendered, just not tied to any track.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `Station(1, "Hello World")` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | * | Hello World
+    /// | | |
+    /// ```
+    ///
+    /// Text with multiple lines is also allowed.
+    /// Given 3 tracks `0, 1, 2` then `Station(1, "Hello\nWorld")` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | * | Hello
+    /// | | | World
+    /// | | |
+    /// ```
+    ///
+    /// If the `track_id` does not exist, then no rail is highlighted.
+    /// Thus `Station(10, "Hello World")` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | | | Hello World
+    /// | | |
+    /// ```
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(RenderingSettings::default().color(false));
+    /// metro.push(Event::station(10, "Hello World"));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "│        Hello World\n│     \n");
+    /// ```
+    ///
+    /// Besides its text, a [`Station`] can carry metadata such as
+    /// the time at which it was reached.
+    ///
+    /// [`Station`]: struct.Station.html
+    Station(TrackId, Station<'a>),
+
+    /// `SplitTrack(from_track_id, new_track_id)`
+    ///
+    /// Creates a new track diverging from `from_track_id` to the right.
+    /// All rails to the right of `from_track_id`, are pushed to the
+    /// right to make space for the new track.
+    ///
+    /// - If `from_track_id` does not exist, then this event is the
+    ///   same as `StartTrack(new_track_id)`.
+    /// - If `new_track_id` already exists, then this event does nothing.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `SplitTrack(1, 4)` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// | |\ \
+    /// | | | |
+    /// ```
+    ///
+    /// ## Fallbacks
+    ///
+    /// Streams from external sources, e.g. decoded with
+    /// [`from_bytes`], may reference missing tracks:
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let recorded = metro::to_bytes(&[Event::SplitTrack(7.into(), 1.into()), Event::NoEvent]);
+    /// let events = metro::from_bytes(&recorded).unwrap();
+    ///
+    /// assert_eq!(
+    ///     metro::to_string(&events).unwrap(),
+    ///     metro::to_string(&[Event::StartTrack(1.into()), Event::NoEvent]).unwrap(),
+    /// );
+    /// ```
+    ///
+    /// [`from_bytes`]: crate::from_bytes
+    SplitTrack(TrackId, TrackId),
+
+    /// `JoinTrack(from_track_id, to_track_id)`
+    ///
+    /// Joins `from_track_id` and `to_track_id`
+    /// resulting in the `from_track_id` being removed.
+    ///
+    /// The rails are joined towards the leftmost rail.
+    ///
+    /// - If `from_track_id` does not exist, then this event does nothing.
+    /// - If `to_track_id` does not exist, then it turns into `StopTrack(from_track_id)`.
+    /// - If `from_track_id` and `to_track_id` are the same, then it turns into `StopTrack(from_track_id)`
+    ///
+    /// The track ID (`from_track_id`) can be reused for
+    /// a new track after this event.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `JoinTrack(1, 0)` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// |/ /
+    /// | |
+    /// ```
+    ///
+    /// Given 6 tracks `0, 1, 2, 3, 4, 5` then `JoinTrack(4, 0)` would render as:
+    ///
+    /// ```text
+    /// | | | | | |
+    /// | |_|_|/ /
+    /// |/| | | |
+    /// | | | | |
+    /// ```
+    ///
+    /// ## Fallbacks
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let render = |events: &[Event]| {
+    ///     let mut metro = metro::events::Metro::with_settings(RenderingSettings::default().color(false));
+    ///     for event in events {
+    ///         metro.push(event.clone());
+    ///     }
+    ///     metro.to_string().unwrap()
+    /// };
+    /// let split = Event::SplitTrack(0.into(), 1.into());
+    ///
+    /// // A missing `from_track_id` does nothing
+    /// assert_eq!(
+    ///     render(&[split.clone(), Event::JoinTrack(2.into(), 0.into())]),
+    ///     render(&[split.clone()]),
+    /// );
+    ///
+    /// // A missing `to_track_id` stops `from_track_id`
+    /// assert_eq!(
+    ///     render(&[split.clone(), Event::JoinTrack(1.into(), 2.into())]),
+    ///     render(&[split.clone(), Event::StopTrack(1.into())]),
+    /// );
+    ///
+    /// // Joining a track into itself stops it
+    /// assert_eq!(
+    ///     render(&[split.clone(), Event::JoinTrack(1.into(), 1.into())]),
+    ///     render(&[split.clone(), Event::StopTrack(1.into())]),
+    /// );
+    /// ```
+    JoinTrack(TrackId, TrackId),
+
+    /// `NoEvent` produces one row of rails.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `NoEvent` would render as:
+    ///
+    /// ```text
+    /// | | |
+    /// ```
+    NoEvent,
+
+    /// `Reference(from_station_id, to_station_id)`
+    ///
+    /// Relates two stations without tying their tracks together,
+    /// e.g. a deployment using an artifact built elsewhere. Stations
+    /// are identified by their [ID](struct.Station.html#method.with_id).
+    ///
+    /// Produces one row of rails, noting the text of both stations,
+    /// or their ID if no such station exists.
+    ///
+    /// References can go backwards, e.g. to express cycles, which no
+    /// track can: a reference to a station rendered earlier notes the
+    /// line that station is printed on, counting from 1, or its number
+    /// with [`row_numbers`].
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `Reference("deploy", "build")`
+    /// would render as either of:
+    ///
+    /// ```text
+    /// | | |   Deploy ⇢ Build
+    /// | | |   Deploy ↩ see #12 (Build)
+    /// ```
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr.clone());
+    /// metro.push(Event::station(0, Station::new("Build").with_id("build")));
+    /// metro.push(Event::Reference("build".into(), "deploy".into()));
+    /// metro.push(Event::station(0, Station::new("Deploy").with_id("deploy")));
+    /// metro.push(Event::Reference("deploy".into(), "build".into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.contains("│    Build ⇢ Deploy\n"));
+    /// assert!(string.ends_with("│    Deploy ↩ see #1 (Build)\n"));
+    ///
+    /// // Below a ruler, stations are printed a line lower
+    /// let mut metro = metro::events::Metro::with_settings(rdr.ruler(true));
+    /// let tracks = vec![0.into()];
+    /// metro.push(Event::SharedStation(tracks.into(), Station::new("Test").with_id("test")));
+    /// metro.push(Event::Reference("retry".into(), "test".into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.contains("│    retry ↩ see #2 (Test)\n"));
+    /// ```
+    ///
+    /// [`row_numbers`]: struct.RenderingSettings.html#method.row_numbers
+    Reference(Cow<'a, str>, Cow<'a, str>),
+
+    /// `Link(from_track_id, to_track_id)`
+    ///
+    /// Connects two tracks on one row, without merging them, e.g. to
+    /// show that a thread waits on, or signals, another one.
+    ///
+    /// - If either track does not exist, or both are the same,
+    ///   then this event renders like `NoEvent`.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `Link(0, 2)` would render as:
+    ///
+    /// ```text
+    /// |-+-|
+    /// ```
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTracks(vec![1.into(), 2.into()].into()));
+    /// metro.push(Event::Link(2.into(), 0.into()));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "├───┤ \n");
+    /// ```
+    Link(TrackId, TrackId),
+
+    /// `SharedStation(track_ids, station)`
+    ///
+    /// A single station belonging to several tracks at once,
+    /// e.g. a checkpoint synchronizing threads, connecting
+    /// the rails of all of them.
+    ///
+    /// - Missing tracks among `track_ids` are ignored.
+    /// - If no track from `track_ids` exists, then it renders
+    ///   like a `Station` on a missing track.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 3 tracks `0, 1, 2` then `SharedStation([0, 2], "Sync")`
+    /// would render as:
+    ///
+    /// ```text
+    /// *===*   Sync
+    /// | | |
+    /// ```
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::StartTracks(vec![1.into(), 2.into()].into()));
+    /// metro.push(Event::SharedStation(vec![0.into(), 2.into()].into(), "Sync".into()));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪═══╪    Sync\n│ │ │ \n");
+    /// ```
+    SharedStation(Cow<'a, [TrackId]>, Station<'a>),
+
+    /// `StationKv(track_id, pairs)`
+    ///
+    /// A station holding tabular data as `(key, value)` pairs, e.g.
+    /// a stage, its duration and its status. The pairs of all
+    /// `StationKv`s are aligned into columns, one per key.
+    ///
+    /// Behaves like `Station` otherwise.
+    ///
+    /// ## Output Example
+    ///
+    /// Given 1 track `0` then `StationKv(0, [("stage", "test"), ("status", "ok")])`
+    /// following `StationKv(0, [("stage", "build"), ("status", "ok")])`
+    /// would render as:
+    ///
+    /// ```text
+    /// *   stage: build  status: ok
+    /// |
+    /// *   stage: test   status: ok
+    /// |
+    /// ```
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let kv = |stage, status| {
+    ///     Event::StationKv(0.into(), vec![("stage".into(), stage), ("status".into(), status)])
+    /// };
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(kv("build".into(), "ok".into()));
+    /// metro.push(kv("test".into(), "failed".into()));
+    ///
+    /// assert_eq!(
+    ///     metro.to_string().unwrap(),
+    ///     "╪    stage: build  status: ok\n│ \n╪    stage: test   status: failed\n│ \n",
+    /// );
+    /// ```
+    StationKv(TrackId, Vec<(Cow<'a, str>, Cow<'a, str>)>),
+
+    /// `UpdateStation(station_id, text)`
+    ///
+    /// Replaces the text of the station with the [ID] `station_id`,
+    /// wherever it is, e.g. from `Deploying` to `Deployed`. The last
+    /// update of a station wins.
+    ///
+    /// Produces no row. [Live renderers] rendering in place redraw
+    /// the updated station.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Deploying").with_id("deploy")));
+    /// metro.push(Event::UpdateStation("deploy".into(), "Deployed".into()));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    Deployed\n│ \n");
+    /// ```
+    ///
+    /// [ID]: struct.Station.html#method.with_id
+    /// [Live renderers]: struct.LiveRenderer.html#method.in_place
+    UpdateStation(Cow<'a, str>, Cow<'a, str>),
+
+    /// `SetStatus(station_id, status)`
+    ///
+    /// Sets the [status] of the station with the [ID] `station_id`,
+    /// wherever it is, like [`UpdateStation`] does its text.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station, Status};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Tests").with_id("tests")));
+    /// metro.push(Event::SetStatus("tests".into(), Status::Failed));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    ✗ Tests\n│ \n");
+    /// ```
+    ///
+    /// [status]: struct.Station.html#method.with_status
+    /// [ID]: struct.Station.html#method.with_id
+    /// [`UpdateStation`]: #variant.UpdateStation
+    SetStatus(Cow<'a, str>, Status),
+}
+impl<'a> Event<'a> {
+    /// Call `f` with every track ID referenced by this event.
+    pub(crate) fn for_each_track_id<F: FnMut(TrackId)>(&self, mut f: F) {
+        match self {
+            Event::StartTrack(id)
+            | Event::StopTrack(id)
+            | Event::Station(id, _)
+            | Event::StationKv(id, _) => f(*id),
+            Event::StartTracks(ids) | Event::SharedStation(ids, _) => {
+                ids.iter().copied().for_each(f)
+            }
+            Event::SplitTrack(a, b) | Event::JoinTrack(a, b) | Event::Link(a, b) => {
+                f(*a);
+                f(*b);
+            }
+            Event::NoEvent
+            | Event::Reference(..)
+            | Event::UpdateStation(..)
+            | Event::SetStatus(..) => {}
+        }
+    }
+
+    /// Returns this event with every track ID replaced by `f(track_id)`.
+    pub(crate) fn map_track_ids<F: FnMut(TrackId) -> TrackId>(&self, mut f: F) -> Self {
+        match self {
+            Event::StartTrack(id) => Event::StartTrack(f(*id)),
+            Event::StopTrack(id) => Event::StopTrack(f(*id)),
+            Event::Station(id, station) => Event::Station(f(*id), station.clone()),
+            Event::StationKv(id, fields) => Event::StationKv(f(*id), fields.clone()),
+            Event::StartTracks(ids) => Event::StartTracks(ids.iter().map(|id| f(*id)).collect()),
+            Event::SharedStation(ids, station) => {
+                Event::SharedStation(ids.iter().map(|id| f(*id)).collect(), station.clone())
+            }
+            Event::SplitTrack(a, b) => Event::SplitTrack(f(*a), f(*b)),
+            Event::JoinTrack(a, b) => Event::JoinTrack(f(*a), f(*b)),
+            Event::Link(a, b) => Event::Link(f(*a), f(*b)),
+            Event::NoEvent
+            | Event::Reference(..)
+            | Event::UpdateStation(..)
+            | Event::SetStatus(..) => self.clone(),
+        }
+    }
+
+    /// Shorthand for creating an [`Event::Station`].
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let event = Event::station(0, "Hello World");
+    /// ```
+    ///
+    /// [`Event::Station`]: enum.Event.html#variant.Station
+    pub fn station<I: Into<TrackId>, S: Into<Station<'a>>>(track_id: I, station: S) -> Self {
+        Event::Station(track_id.into(), station.into())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Metro<'a> {
+    events: Vec<Event<'a>>,
+    rdr: RenderingSettings,
+    keys: TrackKeys,
+}
+impl<'a> From<Vec<Event<'a>>> for Metro<'a> {
+    fn from(events: Vec<Event<'a>>) -> Self {
+        let mut keys = TrackKeys::default();
+        for event in events.iter() {
+            event.for_each_track_id(|id| keys.reserve(id));
+        }
+        Self {
+            events,
+            keys,
+            ..Default::default()
+        }
+    }
+}
+impl<'a> Extend<Event<'a>> for Metro<'a> {
+    /// Push every event of `iter`.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::with_capacity(3);
+    /// metro.extend((1..=3).map(|i| Event::station(0, format!("Station {}", i))));
+    /// ```
+    fn extend<I: IntoIterator<Item = Event<'a>>>(&mut self, iter: I) {
+        for event in iter {
+            self.push(event);
+        }
+    }
+}
+impl<'a> FromIterator<Event<'a>> for Metro<'a> {
+    fn from_iter<I: IntoIterator<Item = Event<'a>>>(iter: I) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+impl<'a> Metro<'a> {
+    pub fn with_settings(rdr: RenderingSettings) -> Self {
+        Self {
+            rdr,
+            ..Default::default()
+        }
+    }
+    /// Create an empty `Metro` with room for `capacity`
+    /// events before reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: Vec::with_capacity(capacity),
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, event: Event<'a>) {
+        event.for_each_track_id(|id| self.keys.reserve(id));
+        self.events.push(event);
+    }
+
+    /// Returns the [`TrackId`] of the track identified by `key`,
+    /// assigning it an unused ID the first time `key` is seen.
+    ///
+    /// This avoids managing IDs manually for dynamically
+    /// discovered tracks, e.g. branches or threads.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// let main = metro.track_id("main");
+    /// let feature = metro.track_id("feature");
+    /// metro.push(Event::SplitTrack(main, feature));
+    /// metro.push(Event::station(feature, "Commit"));
+    ///
+    /// assert_eq!(metro.track_id("feature"), feature);
+    /// assert_ne!(metro.track_id(1234), feature);
+    /// ```
+    ///
+    /// [`TrackId`]: struct.TrackId.html
+    pub fn track_id<K: TrackKey>(&mut self, key: K) -> TrackId {
+        self.keys.id(key)
+    }
+
+    /// Reserve `n` consecutive track IDs, which are neither used
+    /// by any event so far, nor handed out by [`Metro::track_id`].
+    ///
+    /// [`Metro::track_id`]: #method.track_id
+    pub fn reserve_ids(&mut self, n: usize) -> Range<TrackId> {
+        self.keys.reserve_ids(n)
+    }
+
+    /// Reserve `n` consecutive track IDs like [`Metro::reserve_ids`],
+    /// and keep their tracks adjacent when rendering, like
+    /// [`RenderingSettings::group`].
+    ///
+    /// This keeps the rails of e.g. each subsystem together.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().implicit_root(false);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// let workers = metro.reserve_group(2);
+    /// let (worker1, worker2) = (workers.start, (usize::from(workers.start) + 1).into());
+    /// let logger = metro.track_id("logger");
+    ///
+    /// metro.push(Event::StartTrack(worker1));
+    /// metro.push(Event::StartTrack(logger));
+    /// metro.push(Event::StartTrack(worker2));
+    ///
+    /// assert_eq!(metro.active_tracks_at(2), [worker1, worker2, logger]);
+    /// ```
+    ///
+    /// [`Metro::reserve_ids`]: #method.reserve_ids
+    /// [`RenderingSettings::group`]: struct.RenderingSettings.html#method.group
+    pub fn reserve_group(&mut self, n: usize) -> Range<TrackId> {
+        let ids = self.reserve_ids(n);
+        self.rdr.groups.push(ids.start.0..ids.end.0);
+        ids
+    }
+
+    /// Returns the events pushed so far.
+    pub fn events(&self) -> &[Event<'a>] {
+        &self.events
+    }
+
+    /// Returns the settings used for rendering.
+    pub fn settings(&self) -> &RenderingSettings {
+        &self.rdr
+    }
+
+    /// Returns the settings used for rendering, to change them.
+    ///
+    /// ```
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.settings_mut().set_color(false).set_splat(1);
+    /// ```
+    pub fn settings_mut(&mut self) -> &mut RenderingSettings {
+        &mut self.rdr
+    }
+
+    /// Lay tracks out according to `order`.
+    ///
+    /// *See [`RenderingSettings::track_order`].*
+    ///
+    /// [`RenderingSettings::track_order`]: struct.RenderingSettings.html#method.track_order
+    pub fn set_track_order(&mut self, order: &[TrackId]) {
+        self.rdr.track_order = order.to_vec();
+    }
+
+    /// Color the track `new` like the track `existing`.
+    ///
+    /// *See [`RenderingSettings::alias_color`].*
+    ///
+    /// [`RenderingSettings::alias_color`]: struct.RenderingSettings.html#method.alias_color
+    pub fn alias_color(&mut self, existing: TrackId, new: TrackId) {
+        self.rdr.color_aliases.push((existing, new));
+    }
+
+    /// Returns the key of the track, if its ID was
+    /// assigned by [`Metro::track_id`].
+    ///
+    /// [`Metro::track_id`]: #method.track_id
+    pub fn track_key(&self, track_id: TrackId) -> Option<&dyn TrackKey> {
+        self.keys.key(track_id)
+    }
+
+    /// Returns statistics about the shape of the graph,
+    /// e.g. to warn before rendering something too big.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 2"));
+    ///
+    /// let stats = metro.stats();
+    /// assert_eq!(stats.tracks_created, 2);
+    /// assert_eq!(stats.max_concurrent_tracks, 2);
+    /// assert_eq!(stats.stations_per_track[&1.into()], 1);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        stats::stats(&self.events, &self.rdr)
+    }
+
+    /// Returns the `(width, height)` in characters that rendering
+    /// with `rdr` would produce, including station text,
+    /// without rendering anything.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().color(false);
+    /// let mut metro = metro::events::Metro::with_settings(rdr.clone());
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// let (width, height) = metro.measure(&rdr);
+    /// assert_eq!(width, string.lines().map(|line| line.chars().count()).max().unwrap());
+    /// assert_eq!(height, string.lines().count());
+    /// ```
+    pub fn measure(&self, rdr: &RenderingSettings) -> (usize, usize) {
+        measure(&self.events, rdr)
+    }
+
+    /// Returns a compact overview of the graph, e.g. for TUIs to show
+    /// next to a scrollbar: at most `height` lines of `width`
+    /// characters, each summarizing as many rows of the rendering.
+    ///
+    /// The length of a line is proportional to the number of tracks
+    /// alive in its rows, and its shade, from `░` to `█`, to the
+    /// density of stations in them.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// for i in 0..100 {
+    ///     metro.push(Event::station(0, format!("Commit {}", i)));
+    /// }
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// for i in 0..50 {
+    ///     metro.push(Event::station(1, format!("Fix {}", i)));
+    /// }
+    ///
+    /// let minimap = metro.minimap(4, 10);
+    /// assert_eq!(minimap.lines().count(), 10);
+    /// assert_eq!(minimap.lines().next(), Some("██  "));
+    /// assert_eq!(minimap.lines().last(), Some("████"));
+    /// ```
+    pub fn minimap(&self, width: usize, height: usize) -> String {
+        minimap::minimap(&self.events, &self.rdr, width, height)
+    }
+
+    /// Render only the rows within `range` of the full rendering,
+    /// e.g. to render the visible part of a huge graph in a viewport.
+    ///
+    /// The rails entering the window from above are rendered
+    /// exactly as in the full rendering.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let mut metro = metro::events::Metro::with_settings(RenderingSettings::default().color(false));
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 2"));
+    ///
+    /// let full = metro.to_string().unwrap();
+    /// let window = metro.render_range(2..4).unwrap();
+    /// assert_eq!(window.lines().collect::<Vec<_>>(), full.lines().skip(2).take(2).collect::<Vec<_>>());
+    /// ```
+    pub fn render_range(&self, range: Range<usize>) -> io::Result<String> {
+        let mut vec = Vec::new();
+        render_range(&mut vec, &self.events, &self.rdr, range)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Render the layout of the graph as [newline-delimited JSON], one
+    /// object per row, e.g. for other renderers to consume.
+    ///
+    /// Each object holds the `row` index, its `cells` from left to right,
+    /// whether it starts a `station`, whether it is `detached` from every
+    /// track, and its `time` column label and `text`, or `null`. Each cell
+    /// holds the `track` it belongs to, its `kind`, e.g. `"straight"`
+    /// or `"station"`, and its uncolored `glyph`.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Station 1"));
+    ///
+    /// let structured = metro.to_structured().unwrap();
+    /// assert_eq!(
+    ///     structured.lines().next().unwrap(),
+    ///     r#"{"row": 0, "cells": [{"track": 0, "kind": "station", "glyph": "╪     "}], "station": true, "detached": false, "time": null, "text": "Station 1"}"#,
+    /// );
+    /// ```
+    ///
+    /// [newline-delimited JSON]: https://github.com/ndjson/ndjson-spec
+    pub fn to_structured(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        render_structured(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Render like [`Metro::to_string`], highlighting every occurrence
+    /// of `pattern` in the text of stations, and return the rendering
+    /// along with the index of every row containing `pattern`.
+    ///
+    /// Matches are highlighted in reverse video, unless [`color`]
+    /// is disabled.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, StyledText};
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Build"));
+    /// metro.push(Event::station(0, "Test"));
+    /// metro.push(Event::station(0, "Build docs"));
+    /// metro.push(Event::station(0, StyledText::new().push("Bui").bold().push("ld api")));
+    ///
+    /// let (string, rows) = metro.render_highlighting("Build").unwrap();
+    /// assert_eq!(rows, [0, 4, 6]);
+    /// #[cfg(feature = "color")]
+    /// assert!(string.contains("\x1b[7mBuild\x1b[27m docs"));
+    /// #[cfg(feature = "color")]
+    /// assert_eq!(string.matches("\x1b[7m").count(), 3);
+    /// assert!(metro::strip_ansi(&string).contains("Build api"));
+    /// ```
+    ///
+    /// [`Metro::to_string`]: #method.to_string
+    /// [`color`]: struct.RenderingSettings.html#method.color
+    pub fn render_highlighting(&self, pattern: &str) -> io::Result<(String, Vec<usize>)> {
+        let mut vec = Vec::new();
+        let rows = render_highlighting(&mut vec, &self.events, &self.rdr, pattern)?;
+        Ok((rendered_to_string(vec), rows))
+    }
+
+    /// Returns every mistake found in the events, taking
+    /// [`implicit_root`] into account.
+    ///
+    /// *See [`analysis::lint`].*
+    ///
+    /// [`implicit_root`]: struct.RenderingSettings.html#method.implicit_root
+    /// [`analysis::lint`]: ../analysis/fn.lint.html
+    pub fn lint(&self) -> Vec<Lint> {
+        analysis::lint_with(&self.events, &self.rdr)
+    }
+
+    /// Returns a new `Metro` with the same settings, holding only
+    /// the paths from the station with ID `from` to the station
+    /// with ID `to`.
+    ///
+    /// *See [`slice_between`].*
+    ///
+    /// [`slice_between`]: ../fn.slice_between.html
+    pub fn slice_between(&self, from: &str, to: &str) -> Option<Self> {
+        let events = crate::slice_between(&self.events, from, to)?;
+        let mut sliced = Self::from(events);
+        sliced.rdr = self.rdr.clone();
+        Some(sliced)
+    }
+
+    /// Returns the span of events `(start, end)` during which
+    /// `track_id` is alive, i.e. it is alive after every event
+    /// in `start..end`, or `None` if the track never exists.
+    ///
+    /// `start` is the index of the event creating the track, or `0`
+    /// for the default track `0`. `end` is the index of the event
+    /// stopping or joining the track, or the number of events if the
+    /// track is never stopped. If a track ID is reused after being
+    /// stopped, only its first track is considered.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 2"));
+    /// metro.push(Event::JoinTrack(1.into(), 0.into()));
+    ///
+    /// assert_eq!(metro.track_span(1.into()), Some((1, 3)));
+    /// assert_eq!(metro.track_span(0.into()), Some((0, 4)));
+    /// assert_eq!(metro.track_span(2.into()), None);
+    /// ```
+    pub fn track_span(&self, track_id: TrackId) -> Option<(usize, usize)> {
+        track_span(&self.events, &self.rdr, track_id)
+    }
+
+    /// Returns the tracks alive after the event at `index`,
+    /// from left to right.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::StopTrack(0.into()));
+    ///
+    /// assert_eq!(metro.active_tracks_at(0), [0.into(), 1.into()]);
+    /// assert_eq!(metro.active_tracks_at(1), [1.into()]);
+    /// ```
+    pub fn active_tracks_at(&self, index: usize) -> Vec<TrackId> {
+        active_tracks_at(&self.events, &self.rdr, index)
+    }
+
+    /// Returns the minimal events starting every track alive after the
+    /// events so far, from left to right, e.g. to resume rendering a
+    /// stream after truncating it. Track labels are kept by the
+    /// [settings](#method.settings).
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::SplitTrack(1.into(), 2.into()));
+    /// metro.push(Event::StopTrack(1.into()));
+    ///
+    /// assert_eq!(
+    ///     metro.state_prelude(),
+    ///     [Event::StartTracks(vec![0.into(), 2.into()].into())]
+    /// );
+    /// ```
+    pub fn state_prelude(&self) -> Vec<Event<'a>> {
+        state_prelude(&self.events, &self.rdr)
+    }
+    /// Write `&[`[`Event`]`]` to [`<W: io::Write>`].
+    /// Defines a default track with `track_id` of `0`.
+    ///
+    /// *[See also `Metro::to_writer`.][`Metro::to_writer`]*
+    ///
+    /// *See also [`to_string`] and [`to_vec`].*
+    ///
+    /// [`to_vec`]: fn.to_vec.html
+    /// [`to_string`]: fn.to_string.html
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`Metro::to_writer`]: struct.Metro.html#method.to_writer
     ///
     /// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
     pub fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
-        let mut tracks = vec![0.into()];
-        let widest_track = self
-            .events
-            .iter()
-            .fold((1, 1), |(current, max), e| {
-                let current = match e {
-                    Event::StartTrack(_) => current + 1,
-                    Event::StartTracks(track_ids) => current + track_ids.len(),
-                    Event::StopTrack(_) => current - 1,
-                    Event::SplitTrack(_, _) => current + 1,
-                    Event::JoinTrack(_, _) => current - 1,
-                    _ => current,
+        render(w, &self.events, &self.rdr)
+    }
+
+    /// Write `&[`[`Event`]`]` to [`Vec<u8>`].
+    /// Defines a default track with `track_id` of `0`.
+    ///
+    /// *[See also `Metro::to_vec`.][`Metro::to_vec`]*
+    ///
+    /// *See also [`to_string`] and [`to_writer`].*
+    ///
+    /// [`to_writer`]: fn.to_writer.html
+    /// [`to_string`]: fn.to_string.html
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`Metro::to_vec`]: struct.Metro.html#method.to_vec
+    ///
+    /// [`Vec<u8>`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.to_writer(&mut vec)?;
+        Ok(vec)
+    }
+
+    /// Write `&[`[`Event`]`]` to [`String`].
+    /// Defines a default track with `track_id` of `0`.
+    ///
+    /// *[See also `Metro::to_string`.][`Metro::to_string`]*
+    ///
+    /// *See also [`to_vec`] and [`to_writer`].*
+    ///
+    /// [`to_writer`]: fn.to_writer.html
+    /// [`to_vec`]: fn.to_vec.html
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`Metro::to_string`]: struct.Metro.html#method.to_string
+    ///
+    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+    pub fn to_string(&self) -> io::Result<String> {
+        let vec = self.to_vec()?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Describe the graph as text, e.g. for screen readers, as an
+    /// alternative to the rendering: one sentence per run of events
+    /// on the same track, naming tracks by their [label].
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let rdr = RenderingSettings::default().track_label(1.into(), "feature");
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, "Station 1"));
+    /// metro.push(Event::SplitTrack(0.into(), 1.into()));
+    /// metro.push(Event::station(1, "Station 2"));
+    /// metro.push(Event::JoinTrack(1.into(), 0.into()));
+    ///
+    /// assert_eq!(
+    ///     metro.to_accessible_text().unwrap(),
+    ///     "Track 0: Station 1; splits to Track feature.\n\
+    ///      Track feature: Station 2; joins Track 0.\n",
+    /// );
+    /// ```
+    ///
+    /// [label]: ../struct.RenderingSettings.html#method.track_label
+    pub fn to_accessible_text(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        accessible::to_accessible_text(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Export as a [D2] diagram, one shape per station, connected
+    /// along tracks, e.g. to edit the graph in a diagramming tool.
+    ///
+    /// Shapes are outlined in the color of their track, and
+    /// link to the [link] of their station, if any. Like every
+    /// export, stations are exported as last [updated].
+    ///
+    /// ```
+    /// use metro::{Event, Station};
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, Station::new("Building").with_id("build")));
+    /// metro.push(Event::station(0, "Test"));
+    /// metro.push(Event::UpdateStation("build".into(), "Built".into()));
+    ///
+    /// let d2 = metro.to_d2().unwrap();
+    /// assert!(d2.contains("s0: \"Built\"\n"));
+    /// assert!(d2.ends_with("s0 -> s1\n"));
+    /// ```
+    ///
+    /// [D2]: https://d2lang.com
+    /// [link]: ../struct.Station.html#method.with_link
+    /// [updated]: ../enum.Event.html#variant.UpdateStation
+    pub fn to_d2(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_d2(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Export as an [Excalidraw] scene, one text element per station
+    /// in the column of its track, connected by arrows, e.g. to edit
+    /// the graph in Excalidraw.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Build"));
+    /// metro.push(Event::station(0, "Test"));
+    ///
+    /// let scene = metro.to_excalidraw().unwrap();
+    /// assert!(scene.starts_with(r#"{"type": "excalidraw""#));
+    /// assert!(scene.contains(r#""type": "arrow""#));
+    /// ```
+    ///
+    /// [Excalidraw]: https://excalidraw.com
+    pub fn to_excalidraw(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_excalidraw(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Export as a [GraphML] document, one node per station with its
+    /// label, link, tracks and color as data, connected along tracks,
+    /// e.g. to analyze the graph in Gephi, Cytoscape or yEd.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Build"));
+    /// metro.push(Event::station(0, "Test & deploy"));
+    ///
+    /// let graphml = metro.to_graphml().unwrap();
+    /// assert!(graphml.contains(r#"<data key="label">Test &amp; deploy</data>"#));
+    /// assert!(graphml.contains(r#"<edge source="s0" target="s1"/>"#));
+    /// ```
+    ///
+    /// [GraphML]: http://graphml.graphdrawing.org
+    pub fn to_graphml(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_graphml(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Export in the [JSON Graph Format], one node per station with
+    /// its link, tracks and color as metadata, connected along tracks.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Build"));
+    /// metro.push(Event::station(0, "Test"));
+    ///
+    /// let jgf = metro.to_jgf().unwrap();
+    /// assert!(jgf.starts_with(r#"{"graph": {"directed": true"#));
+    /// assert!(jgf.contains(r#"{"source": "s0", "target": "s1"}"#));
+    /// ```
+    ///
+    /// [JSON Graph Format]: https://jsongraphformat.info
+    pub fn to_jgf(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_jgf(&mut vec, &self.events, &self.rdr)?;
+        Ok(rendered_to_string(vec))
+    }
+
+    /// Render as a fenced code block, e.g. to paste into an issue
+    /// or a pull request description.
+    ///
+    /// The code block holds the plain rendering. If `colored` is `true`,
+    /// it is followed by the colored rendering, as HTML in a collapsed
+    /// `<details>` block.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Station 1"));
+    ///
+    /// let markdown = metro.to_markdown(false).unwrap();
+    /// assert_eq!(markdown, "```text\n╪        Station 1\n│     \n```\n");
+    /// assert!(metro.to_markdown(true).unwrap().contains("<details>"));
+    /// ```
+    pub fn to_markdown(&self, colored: bool) -> io::Result<String> {
+        let colored = if colored {
+            Some(self.to_string()?)
+        } else {
+            None
+        };
+        Ok(to_markdown(&self.render_plain()?, colored.as_deref()))
+    }
+
+    /// Render to every sink of `sinks`, each styled with its own
+    /// settings, e.g. colored for a terminal and plain for a log file,
+    /// laying out the graph only once.
+    ///
+    /// The settings of sinks only style the rendering, e.g. [`color`],
+    /// [`splat`], or [`time_column`]. Everything affecting the layout,
+    /// e.g. hidden stations or the order of tracks, is taken from
+    /// the settings of this `Metro`.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings};
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, "Station 1"));
+    ///
+    /// let colored = RenderingSettings::default();
+    /// let plain = RenderingSettings::default().color(false);
+    /// let (mut terminal, mut file) = (Vec::new(), Vec::new());
+    /// metro.to_writers(&mut [(&mut terminal, &colored), (&mut file, &plain)]).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(file).unwrap(), metro.render_plain().unwrap());
+    /// ```
+    ///
+    /// [`color`]: struct.RenderingSettings.html#method.color
+    /// [`splat`]: struct.RenderingSettings.html#method.splat
+    /// [`time_column`]: struct.RenderingSettings.html#method.time_column
+    pub fn to_writers(&self, sinks: &mut [(&mut dyn Write, &RenderingSettings)]) -> io::Result<()> {
+        render_to_sinks(sinks, &self.events, &self.rdr)
+    }
+
+    /// Render to [`String`] without any ANSI escape sequence, whatever
+    /// the settings, and even if stations hold escape sequences.
+    ///
+    /// *See also [`strip_ansi`].*
+    ///
+    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+    /// [`strip_ansi`]: ../fn.strip_ansi.html
+    pub fn render_plain(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        render(&mut vec, &self.events, &self.rdr.clone().color(false))?;
+        Ok(strip_ansi(&rendered_to_string(vec)))
+    }
+}
+
+/// Write `&[`[`Event`]`]` to [`<W: io::Write>`] using the
+/// default [`RenderingSettings`].
+/// Defines a default track with `track_id` of `0`.
+///
+/// *[See also `Metro::to_writer`.][`Metro::to_writer`]*
+///
+/// *See also [`to_string`] and [`to_vec`].*
+///
+/// [`to_vec`]: fn.to_vec.html
+/// [`to_string`]: fn.to_string.html
+///
+/// [`Event`]: enum.Event.html
+/// [`RenderingSettings`]: struct.RenderingSettings.html
+///
+/// [`Metro::to_writer`]: struct.Metro.html#method.to_writer
+///
+/// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+pub fn to_writer<W: Write>(mut w: W, events: &[Event]) -> io::Result<()> {
+    render(&mut w, events, &RenderingSettings::default())
+}
+
+/// Write `&[`[`Event`]`]` to [`Vec<u8>`] using the
+/// default [`RenderingSettings`].
+/// Defines a default track with `track_id` of `0`.
+///
+/// *[See also `Metro::to_vec`.][`Metro::to_vec`]*
+///
+/// *See also [`to_string`] and [`to_writer`].*
+///
+/// [`to_writer`]: fn.to_writer.html
+/// [`to_string`]: fn.to_string.html
+///
+/// [`Event`]: enum.Event.html
+/// [`RenderingSettings`]: struct.RenderingSettings.html
+///
+/// [`Metro::to_vec`]: struct.Metro.html#method.to_vec
+///
+/// [`Vec<u8>`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
+pub fn to_vec(events: &[Event]) -> io::Result<Vec<u8>> {
+    let mut vec = Vec::new();
+    to_writer(&mut vec, events)?;
+    Ok(vec)
+}
+
+/// Write `&[`[`Event`]`]` to [`String`] using the
+/// default [`RenderingSettings`].
+/// Defines a default track with `track_id` of `0`.
+///
+/// *[See also `Metro::to_string`.][`Metro::to_string`]*
+///
+/// *See also [`to_vec`] and [`to_writer`].*
+///
+/// [`to_writer`]: fn.to_writer.html
+/// [`to_vec`]: fn.to_vec.html
+///
+/// [`Event`]: enum.Event.html
+/// [`RenderingSettings`]: struct.RenderingSettings.html
+///
+/// [`Metro::to_string`]: struct.Metro.html#method.to_string
+///
+/// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+pub fn to_string(events: &[Event]) -> io::Result<String> {
+    let vec = to_vec(events)?;
+    Ok(rendered_to_string(vec))
+}
+
+/// Convert the output of the renderer into a `String`.
+pub(crate) fn rendered_to_string(vec: Vec<u8>) -> String {
+    // Metro only writes `str`s and `String`s to the `vec`
+    // which are always valid UTF-8, so this is safe.
+    #[allow(unsafe_code)]
+    unsafe {
+        String::from_utf8_unchecked(vec)
+    }
+}
+
+/// A single rendered row: one rail per live track,
+/// optionally followed by a line of station text.
+pub(crate) struct Row<'e> {
+    /// Each rail, along with the track it is colored as.
+    pub(crate) rails: Vec<(Rail, TrackId)>,
+    pub(crate) text: Option<Cow<'e, str>>,
+    /// The station, on the first row of a station.
+    pub(crate) station: Option<&'e Station<'e>>,
+    /// Whether this is the first row of a station.
+    pub(crate) starts_station: bool,
+    /// Whether the station is not tied to any live track.
+    pub(crate) detached: bool,
+    /// The row of the station an [`Event::Reference`] refers back to,
+    /// along with the labels of both stations.
+    ///
+    /// [`Event::Reference`]: enum.Event.html#variant.Reference
+    pub(crate) back_reference: Option<(usize, String, String)>,
+}
+
+impl<'e> Row<'e> {
+    fn rails<I: IntoIterator<Item = (Rail, TrackId)>>(rails: I) -> Self {
+        Self {
+            rails: rails.into_iter().collect(),
+            text: None,
+            station: None,
+            starts_station: false,
+            detached: false,
+            back_reference: None,
+        }
+    }
+
+    fn straight(tracks: &[TrackId]) -> Self {
+        Self::rails(tracks.iter().map(|&t| (Rail::Straight, t)))
+    }
+}
+
+/// Lay out `events` into rows, without rendering them.
+pub(crate) fn layout<'e>(events: &'e [Event], rdr: &RenderingSettings) -> Vec<Row<'e>> {
+    let patches = station_patches(events);
+    let mut stations = Stations::default();
+    let mut layout = Layout::new(rdr);
+    // Stations are referred to, and key-value pairs aligned,
+    // by what comes after them as well
+    for event in events {
+        layout.note(event, &mut stations);
+    }
+
+    let mut rows = Vec::new();
+    for event in events {
+        layout.push(event, rdr, &patches, &mut stations, &mut rows);
+    }
+    rows
+}
+
+/// What rows refer to of the stations before them, kept aside from
+/// the state of laying out and writing rows, so that a copy of that
+/// state can lay out and write the same events again, e.g. to redraw
+/// them, without copying every station.
+#[derive(Debug, Default)]
+pub(crate) struct Stations {
+    /// The first line of the text of every station with an ID, by ID.
+    labels: HashMap<String, String>,
+    /// The row of every station with an ID laid out, by ID, along
+    /// with the index of its event.
+    rows: HashMap<String, (usize, usize)>,
+    /// The line the row of every station with an ID was printed on,
+    /// by row, counting from 1.
+    lines: HashMap<usize, usize>,
+    /// The duration of every station, sorted, for heat colors.
+    durations: Vec<Duration>,
+    /// The number of events the durations of which are known.
+    timed: usize,
+}
+
+impl Stations {
+    /// Take the durations of the stations of `rows` into account.
+    fn time(&mut self, rows: &[Row], rdr: &RenderingSettings) {
+        if !heats(rdr) {
+            return;
+        }
+        for duration in rows.iter().filter_map(|row| row.station?.duration()) {
+            let i = self.durations.partition_point(|&d| d <= duration);
+            self.durations.insert(i, duration);
+        }
+    }
+}
+
+/// The state of laying out events into rows, one at a time,
+/// e.g. to lay out events as they are emitted.
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    tracks: Vec<TrackId>,
+    kv_columns: KvColumns,
+    last_time: Option<SystemTime>,
+    /// The number of events laid out so far.
+    events: usize,
+    /// The number of rows laid out so far.
+    rows: usize,
+}
+
+impl Layout {
+    pub(crate) fn new(rdr: &RenderingSettings) -> Self {
+        Self {
+            tracks: rdr.initial_tracks(),
+            kv_columns: KvColumns::default(),
+            last_time: None,
+            events: 0,
+            rows: 0,
+        }
+    }
+
+    /// Take the label of the station of `event`, or the keys of its
+    /// key-value pairs, into account for the events laid out next.
+    pub(crate) fn note(&mut self, event: &Event, stations: &mut Stations) {
+        match event {
+            Event::Station(_, station) | Event::SharedStation(_, station) => {
+                if let Some(id) = station.id() {
+                    if !stations.labels.contains_key(id) {
+                        let text = station.text().lines().next().unwrap_or("");
+                        stations.labels.insert(id.to_owned(), text.to_owned());
+                    }
+                }
+            }
+            Event::StationKv(_, pairs) => self.kv_columns.note(pairs),
+            _ => {}
+        }
+    }
+
+    /// Lay out `event` into `rows`, with the updates of `patches`
+    /// applied to its station, if any.
+    pub(crate) fn push<'e>(
+        &mut self,
+        event: &'e Event,
+        rdr: &RenderingSettings,
+        patches: &Patches,
+        stations: &mut Stations,
+        rows: &mut Vec<Row<'e>>,
+    ) {
+        let start = rows.len();
+        let tracks = &mut self.tracks;
+        match event {
+            Event::Reference(from, to) => {
+                // Like station labels, references span a single line
+                let label = |id: &str| {
+                    let label = stations.labels.get(id).map_or(id, String::as_str);
+                    label.lines().next().unwrap_or("").to_owned()
                 };
-                (current, max.max(current))
-            })
-            .1;
+                let mut row = Row::straight(tracks);
+                // Stations laid out before, even when laid out again
+                let target = stations
+                    .rows
+                    .get(to.as_ref())
+                    .filter(|&&(event, _)| event < self.events);
+                let text = match target {
+                    // Renumbered by the line the station is printed on
+                    Some(&(_, target)) => {
+                        let text = back_reference_text(&label(from), &label(to), target + 1);
+                        row.back_reference = Some((target, label(from), label(to)));
+                        text
+                    }
+                    None => format!("{} ⇢ {}", label(from), label(to)),
+                };
+                row.text = Some(text.into());
+                rows.push(row);
+            }
+            Event::Station(_, station) | Event::SharedStation(_, station)
+                if !rdr.is_hidden(station) =>
+            {
+                if let (Some((per_row, max_rows)), Some(time)) = (rdr.time_spacing, station.time())
+                {
+                    let elapsed = self
+                        .last_time
+                        .and_then(|last| time.duration_since(last).ok());
+                    let spacing = match elapsed {
+                        Some(elapsed) if !per_row.is_zero() => {
+                            (elapsed.as_nanos() / per_row.as_nanos()).min(max_rows as u128) as usize
+                        }
+                        _ => 0,
+                    };
+                    rows.extend((0..spacing).map(|_| Row::straight(tracks)));
+                    self.last_time = Some(time);
+                }
+                if let Some(id) = station.id() {
+                    let row = self.rows + rows.len() - start;
+                    // The first station with the ID, laid out again
+                    // where its rows moved, e.g. on a longer update
+                    match stations.rows.get_mut(id) {
+                        Some((event, _)) if *event < self.events => {}
+                        Some(laid_out) => *laid_out = (self.events, row),
+                        None => {
+                            stations.rows.insert(id.to_owned(), (self.events, row));
+                        }
+                    }
+                }
+                match station.id().and_then(|id| patches.get(id)) {
+                    Some(patch) => {
+                        let patched = patch.apply(station);
+                        let lines = rdr
+                            .station_lines(&patched)
+                            .into_iter()
+                            .map(|line| Cow::Owned(line.into_owned()))
+                            .collect();
+                        let targets: &[TrackId] = match event {
+                            Event::Station(target_id, _) => std::slice::from_ref(target_id),
+                            Event::SharedStation(target_ids, _) => target_ids,
+                            _ => &[],
+                        };
+                        station_rows(tracks, targets, lines, Some(station), rows);
+                    }
+                    None => layout_event(tracks, event, rdr, rows),
+                }
+            }
+            Event::StationKv(target_id, pairs) => {
+                let text = self.kv_columns.format(pairs);
+                let lines = text.lines().map(|line| line.to_owned().into()).collect();
+                let targets = std::slice::from_ref(target_id);
+                station_rows(tracks, targets, lines, None, rows);
+            }
+            _ => layout_event(tracks, event, rdr, rows),
+        }
+        self.events += 1;
+        self.rows += rows.len() - start;
+    }
+}
+
+/// The columns of every key of [`Event::StationKv`]s,
+/// in order of appearance, along with their width.
+///
+/// [`Event::StationKv`]: enum.Event.html#variant.StationKv
+#[derive(Clone, Debug, Default)]
+struct KvColumns(Vec<(String, usize)>);
+
+impl KvColumns {
+    fn note(&mut self, pairs: &[(Cow<str>, Cow<str>)]) {
+        for (key, value) in pairs.iter() {
+            let width = key.chars().count() + 2 + value.chars().count();
+            match self.0.iter_mut().find(|(k, _)| k == key) {
+                Some((_, w)) => *w = (*w).max(width),
+                None => self.0.push((key.to_string(), width)),
+            }
+        }
+    }
+
+    fn format(&self, pairs: &[(Cow<str>, Cow<str>)]) -> String {
+        let mut text = String::new();
+        for (key, width) in self.0.iter() {
+            if !text.is_empty() {
+                text.push_str("  ");
+            }
+            let cell = match pairs.iter().find(|(k, _)| k == key) {
+                Some((key, value)) => format!("{}: {}", key, value),
+                None => String::new(),
+            };
+            text.push_str(&format!("{:width$}", cell, width = width));
+        }
+        text.trim_end().to_owned()
+    }
+}
+
+/// The updates of a station, from [`Event::UpdateStation`]
+/// and [`Event::SetStatus`].
+///
+/// [`Event::UpdateStation`]: enum.Event.html#variant.UpdateStation
+/// [`Event::SetStatus`]: enum.Event.html#variant.SetStatus
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Patch {
+    text: Option<String>,
+    status: Option<Status>,
+}
+
+impl Patch {
+    pub(crate) fn apply<'e>(&self, station: &Station<'e>) -> Station<'e> {
+        let mut station = station.clone();
+        if let Some(text) = &self.text {
+            station.set_text(Cow::Owned(text.clone()));
+        }
+        if let Some(status) = self.status {
+            station.set_status(status);
+        }
+        station
+    }
+}
+
+/// The last updates of every station updated, by ID.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Patches(HashMap<String, Patch>);
+
+impl Patches {
+    /// Take the updates of `event`, if any, into account.
+    pub(crate) fn note(&mut self, event: &Event) {
+        match event {
+            Event::UpdateStation(id, text) => {
+                self.entry(id).text = Some(text.to_string());
+            }
+            Event::SetStatus(id, status) => self.entry(id).status = Some(*status),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<&Patch> {
+        self.0.get(id)
+    }
+
+    fn entry(&mut self, id: &str) -> &mut Patch {
+        // Only allocates the ID of stations updated for the first time
+        if !self.0.contains_key(id) {
+            self.0.insert(id.to_owned(), Patch::default());
+        }
+        self.0.get_mut(id).unwrap()
+    }
+}
+
+/// The last updates of every station updated in `events`, by ID.
+pub(crate) fn station_patches(events: &[Event]) -> Patches {
+    let mut patches = Patches::default();
+    for event in events {
+        patches.note(event);
+    }
+    patches
+}
+
+/// The text of a reference from the station labelled `from` back to
+/// the station labelled `to`, printed on `line`, counting from 1.
+fn back_reference_text(from: &str, to: &str, line: usize) -> String {
+    format!("{} ↩ see #{} ({})", from, line, to)
+}
+
+/// Call `f` with the index of every event, and the tracks
+/// alive after that event, from left to right.
+pub(crate) fn for_each_tracks<F: FnMut(usize, &[TrackId])>(
+    events: &[Event],
+    rdr: &RenderingSettings,
+    mut f: F,
+) {
+    let mut tracks = rdr.initial_tracks();
+    let mut rows = Vec::new();
+
+    for (i, event) in events.iter().enumerate() {
+        layout_event(&mut tracks, event, rdr, &mut rows);
+        rows.clear();
+        f(i, &tracks);
+    }
+}
+
+/// Lay out a single event, updating the live `tracks`.
+fn layout_event<'e>(
+    tracks: &mut Vec<TrackId>,
+    event: &'e Event,
+    rdr: &RenderingSettings,
+    rows: &mut Vec<Row<'e>>,
+) {
+    match event {
+        Event::StartTrack(track_id) => start_track(tracks, *track_id, rdr),
+        Event::StartTracks(track_ids) => {
+            for track_id in track_ids.iter() {
+                start_track(tracks, *track_id, rdr);
+            }
+        }
+        Event::StopTrack(stopped) => stop_track(tracks, *stopped, rdr, rows),
+        Event::Station(_, station) | Event::SharedStation(_, station) if rdr.is_hidden(station) => {
+        }
+        Event::Station(target_id, station) => {
+            let lines = rdr.station_lines(station);
+            station_rows(
+                tracks,
+                std::slice::from_ref(target_id),
+                lines,
+                Some(station),
+                rows,
+            )
+        }
+        Event::SharedStation(target_ids, station) => {
+            let lines = rdr.station_lines(station);
+            station_rows(tracks, target_ids, lines, Some(station), rows)
+        }
+        // Laid out by `layout`, which knows about every station
+        Event::StationKv(target_id, _) => station_rows(
+            tracks,
+            std::slice::from_ref(target_id),
+            Vec::new(),
+            None,
+            rows,
+        ),
+        Event::SplitTrack(parent, child) => {
+            if tracks.contains(child) {
+                return;
+            }
+            let Some(parent_position) = tracks.iter().position(|t| t == parent) else {
+                return start_track(tracks, *child, rdr);
+            };
+            let child_position = rdr
+                .start_position(tracks, *child)
+                .unwrap_or(parent_position + 1);
+            if tracks.len() > 1 {
+                for l_i in 0..(tracks.len() - child_position + 1) {
+                    rows.push(Row::rails(tracks.iter().enumerate().map(|(i, t)| {
+                        if tracks.len() - i == l_i {
+                            (Rail::ShiftRight, *t)
+                        } else {
+                            (Rail::Straight, *t)
+                        }
+                    })));
+                }
+            }
+            tracks.insert(child_position, *child);
+            // The parent moved right if the child was started left of it
+            let parent_position = tracks.iter().position(|t| t == parent).unwrap();
+            let min_position = parent_position.min(child_position);
+            let max_position = parent_position.max(child_position);
+            rows.push(Row::rails(tracks.iter().enumerate().map(|(i, t)| {
+                if i == child_position {
+                    if child_position > parent_position {
+                        (Rail::TopRight, *t)
+                    } else {
+                        (Rail::TopLeft, *t)
+                    }
+                } else if i == parent_position {
+                    if child_position > parent_position {
+                        (Rail::SplitRight, *t)
+                    } else {
+                        (Rail::SplitLeft, *t)
+                    }
+                } else if i > min_position && i < max_position {
+                    (Rail::Horizontal, *child)
+                } else {
+                    (Rail::Straight, *t)
+                }
+            })));
+        }
+        Event::JoinTrack(child, target) => {
+            let Some(child_position) = tracks.iter().position(|t| t == child) else {
+                return;
+            };
+            let target_position = match tracks.iter().position(|t| t == target) {
+                Some(target_position) if child != target => target_position,
+                _ => return stop_track(tracks, *child, rdr, rows),
+            };
+            let min_position = target_position.min(child_position);
+            let max_position = target_position.max(child_position);
+            rows.push(Row::rails(tracks.iter().enumerate().map(|(i, t)| {
+                if i == target_position {
+                    if child_position > target_position {
+                        (Rail::SplitRight, *t)
+                    } else {
+                        (Rail::SplitLeft, *t)
+                    }
+                } else if i == child_position {
+                    if child_position > target_position {
+                        (Rail::BottomRight, *child)
+                    } else {
+                        (Rail::BottomtLeft, *child)
+                    }
+                } else if i > min_position && i < max_position {
+                    (Rail::Horizontal, *child)
+                } else {
+                    (Rail::Straight, *t)
+                }
+            })));
+            tracks.retain(|t| t != child);
+            shift_left(tracks, child_position, rows);
+        }
+        Event::NoEvent => rows.push(Row::straight(tracks)),
+        Event::Link(from, to) => {
+            let from_position = tracks.iter().position(|t| t == from);
+            let to_position = tracks.iter().position(|t| t == to);
+            let (Some(from_position), Some(to_position)) = (from_position, to_position) else {
+                return rows.push(Row::straight(tracks));
+            };
+            let min_position = from_position.min(to_position);
+            let max_position = from_position.max(to_position);
+            rows.push(Row::rails(tracks.iter().enumerate().map(|(i, t)| {
+                if min_position == max_position {
+                    (Rail::Straight, *t)
+                } else if i == min_position {
+                    (Rail::SplitRight, *t)
+                } else if i == max_position {
+                    (Rail::LinkEnd, *t)
+                } else if i > min_position && i < max_position {
+                    (Rail::Horizontal, *from)
+                } else {
+                    (Rail::Straight, *t)
+                }
+            })));
+        }
+        // Laid out by `layout`, which knows about every station
+        Event::Reference(..) => rows.push(Row::straight(tracks)),
+        // Applied by `layout` to the stations they patch
+        Event::UpdateStation(..) | Event::SetStatus(..) => {}
+    }
+}
+
+/// Lay out the `lines` of a station, shared by
+/// the live tracks among `targets`.
+fn station_rows<'e>(
+    tracks: &[TrackId],
+    targets: &[TrackId],
+    lines: Vec<Cow<'e, str>>,
+    station: Option<&'e Station>,
+    rows: &mut Vec<Row<'e>>,
+) {
+    let positions = tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| targets.contains(t))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    // Stations on missing tracks are still rendered,
+    // just without highlighting any rail
+    let detached = positions.is_empty();
+    let first = positions.first().copied().unwrap_or(0);
+    let last = positions.last().copied().unwrap_or(0);
+
+    for (i, line) in lines.into_iter().enumerate() {
+        let mut row = Row::rails(tracks.iter().enumerate().map(|(position, t)| {
+            if i > 0 || position < first || position > last {
+                (Rail::Straight, *t)
+            } else if position == last {
+                (Rail::Station, *t)
+            } else if positions.contains(&position) {
+                (Rail::SharedStation, *t)
+            } else {
+                (Rail::SharedHorizontal, tracks[first])
+            }
+        }));
+        if detached && i == 0 {
+            row.rails
+                .iter_mut()
+                .for_each(|(rail, _)| *rail = Rail::Straight);
+        }
+        row.text = Some(line);
+        if i == 0 {
+            row.station = station;
+            row.starts_station = true;
+            row.detached = detached;
+        }
+        rows.push(row);
+    }
+    rows.push(Row::straight(tracks));
+}
+
+/// Start a track where pinning or the track order puts it, or
+/// right of the other live tracks of its group, or else rightmost.
+fn start_track(tracks: &mut Vec<TrackId>, track_id: TrackId, rdr: &RenderingSettings) {
+    if tracks.contains(&track_id) {
+        return;
+    }
+    let group_end = rdr.group_of(track_id).and_then(|group| {
+        tracks
+            .iter()
+            .rposition(|t| group.contains(&t.0))
+            .map(|i| i + 1)
+    });
+    let position = rdr
+        .start_position(tracks, track_id)
+        .or(group_end)
+        .unwrap_or_else(|| rdr.default_position(tracks, track_id));
+    tracks.insert(position, track_id);
+}
+
+fn stop_track(
+    tracks: &mut Vec<TrackId>,
+    stopped: TrackId,
+    rdr: &RenderingSettings,
+    rows: &mut Vec<Row>,
+) {
+    let Some(position) = tracks.iter().position(|t| *t == stopped) else {
+        return;
+    };
+    let reason = rdr.get_abort_reason(stopped);
+    let mut row = Row::rails(tracks.iter().map(|&t| match (t == stopped, reason) {
+        (true, Some(_)) => (Rail::Aborted, t),
+        (true, None) => (Rail::Ground, t),
+        (false, _) => (Rail::Straight, t),
+    }));
+    if let Some(reason) = reason {
+        let reason = match cfg!(feature = "color") && rdr.color {
+            true => {
+                let [r, g, b] = rdr.get_severity_color(Severity::Error);
+                paint(reason, Rgb(r, g, b))
+            }
+            false => reason.to_owned(),
+        };
+        row.text = Some(reason.into());
+    }
+    rows.push(row);
+    tracks.remove(position);
+    shift_left(tracks, position, rows);
+}
+
+/// Pull the rails from `first` onwards to the left,
+/// one per row, to fill the gap left by a removed track.
+fn shift_left(tracks: &[TrackId], first: usize, rows: &mut Vec<Row>) {
+    for i in first..tracks.len() {
+        rows.push(Row::rails(tracks.iter().enumerate().map(|(j, t)| {
+            if j == i {
+                (Rail::ShiftLeft, *t)
+            } else {
+                (Rail::Straight, *t)
+            }
+        })));
+    }
+}
+
+/// *See [`Metro::track_span`](struct.Metro.html#method.track_span).*
+pub(crate) fn track_span(
+    events: &[Event],
+    rdr: &RenderingSettings,
+    track_id: TrackId,
+) -> Option<(usize, usize)> {
+    let mut start = if rdr.initial_tracks().contains(&track_id) {
+        Some(0)
+    } else {
+        None
+    };
+    let mut end = None;
+    for_each_tracks(events, rdr, |i, tracks| {
+        let alive = tracks.contains(&track_id);
+        match (start, end) {
+            (None, _) if alive => start = Some(i),
+            (Some(_), None) if !alive => end = Some(i),
+            _ => {}
+        }
+    });
+    start.map(|start| (start, end.unwrap_or(events.len())))
+}
 
-        for event in self.events.iter() {
-            match event {
-                Event::StartTrack(track_id) => {
-                    assert!(!tracks.contains(track_id));
-                    tracks.push(*track_id);
-                }
-                Event::StartTracks(track_ids) => {
-                    for track_id in track_ids.iter() {
-                        assert!(!tracks.contains(track_id));
-                        tracks.push(*track_id);
+/// Returns the tracks alive after the event at `index`, from left to right.
+pub(crate) fn active_tracks_at(
+    events: &[Event],
+    rdr: &RenderingSettings,
+    index: usize,
+) -> Vec<TrackId> {
+    let mut active = Vec::new();
+    for_each_tracks(
+        &events[..(index + 1).min(events.len())],
+        rdr,
+        |i, tracks| {
+            if i == index {
+                active = tracks.to_vec();
+            }
+        },
+    );
+    active
+}
+
+/// Returns the events starting the tracks alive after `events`,
+/// from left to right.
+pub(crate) fn state_prelude<'a>(events: &[Event], rdr: &RenderingSettings) -> Vec<Event<'a>> {
+    let alive = match events.len().checked_sub(1) {
+        Some(last) => active_tracks_at(events, rdr, last),
+        None => rdr.initial_tracks(),
+    };
+    match alive.is_empty() {
+        true => Vec::new(),
+        false => vec![Event::StartTracks(alive.into())],
+    }
+}
+
+/// Returns the `(width, height)` in characters of the rendering
+/// of `events`, without rendering it.
+pub(crate) fn measure(events: &[Event], rdr: &RenderingSettings) -> (usize, usize) {
+    let rows = layout(events, rdr);
+    let widest_track = widest_track(&rows);
+    let time_width = time_width(&time_column(&rows, &mut None, rdr));
+    let number_width = number_width(rows.len(), rdr);
+    let widths = rdr.column_widths(&rows);
+    let colors = ColorAliases::new(events, rdr);
+    let brackets = rdr.group_brackets(&rows, &widths, &mut Vec::new());
+    let gutters = visible_width(&rdr.prefix) + gutters_width(number_width, time_width);
+    let labels = |row: &Row, upwards| {
+        gutters + visible_width(&rdr.labels(&row.rails, &widths, &colors, upwards))
+    };
+
+    // The width of every line, in the order they are written
+    let mut lines = Vec::new();
+    // The line every station with an ID is printed on, by row
+    let mut printed = HashMap::new();
+    if let Some(header) = rdr.swimlane_header(&rows, &widths) {
+        lines.push(gutters + header.chars().count());
+    }
+    if let Some(row) = rows.first().filter(|_| rdr.ruler) {
+        lines.push(labels(row, true));
+    }
+    for (i, (row, brackets)) in rows.iter().zip(brackets.iter()).enumerate() {
+        if i > 0 && rdr.starts_page(i) {
+            // The separator, followed by the rails above the page
+            let mut text = rdr.page_break.as_ref().map_or("", |(_, s)| s).to_owned();
+            text.push_str(&" ".repeat(gutters));
+            for ((_, track_id), rail) in row.rails.iter().zip(rdr.rails_to_str(&row.rails, &widths))
+            {
+                text.push_str(&rdr.rail_continued(&rail, *track_id, true));
+            }
+            lines.extend(text.split('\n').map(visible_width));
+        }
+        if i > 0 && rdr.repeats_header(i) {
+            lines.push(labels(row, true));
+        }
+        if let Some(brackets) = brackets {
+            lines.push(gutters + brackets.chars().count());
+        }
+        if row.starts_station && row.station.and_then(|s| s.id()).is_some() {
+            printed.insert(i, lines.len() + 1);
+        }
+        let mut width = gutters
+            + rdr
+                .rails_to_str(&row.rails, &widths)
+                .iter()
+                .map(|rail| rail.chars().count())
+                .sum::<usize>();
+        if let Some(text) = row_text(row, &printed, number_width) {
+            width += widest_track + 3 - row.rails.len() + visible_width(&text);
+            if let Some(marker) = rdr.marker_for(row) {
+                width += marker.chars().count() + 1;
+            }
+        }
+        lines.push(width);
+    }
+    if let Some(row) = rows.last().filter(|_| rdr.ruler) {
+        lines.push(labels(row, false));
+    }
+    if rdr.summary {
+        let summary = summary_line(&rows, events, rdr);
+        lines.push(visible_width(&rdr.prefix) + summary.chars().count());
+    }
+
+    (lines.iter().copied().max().unwrap_or(0), lines.len())
+}
+
+/// Wrap the `plain` rendering in a fenced code block, followed by
+/// the `colored` rendering as HTML in a `<details>` block, if any.
+pub(crate) fn to_markdown(plain: &str, colored: Option<&str>) -> String {
+    // Longer than any run of backticks in station text
+    let mut fence = "```".to_owned();
+    while plain.contains(&fence) {
+        fence.push('`');
+    }
+
+    let mut markdown = format!("{}text\n{}{}\n", fence, plain, fence);
+    if let Some(colored) = colored {
+        markdown.push_str("\n<details>\n<summary>Colored</summary>\n\n<pre>");
+        markdown.push_str(&html::ansi_to_html(colored));
+        markdown.push_str("</pre>\n</details>\n");
+    }
+    markdown
+}
+
+/// Write `s` escaped as the contents of a JSON string.
+pub(crate) fn write_json_escaped<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            '\n' => write!(w, "\\n")?,
+            '\r' => write!(w, "\\r")?,
+            '\t' => write!(w, "\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+/// The number of characters in `line`, excluding ANSI escape sequences.
+pub(crate) fn visible_width(line: &str) -> usize {
+    visible_chars(line).count()
+}
+
+/// Remove every ANSI escape sequence from `rendered`, e.g. to write
+/// a colored rendering to a plain log file too.
+///
+/// ```
+/// use metro::Event;
+///
+/// let colored = metro::to_string(&[Event::station(0, "Station 1")]).unwrap();
+/// let plain = metro::strip_ansi(&colored);
+///
+/// assert!(!plain.contains('\x1b'));
+/// assert!(plain.contains("Station 1"));
+/// ```
+pub fn strip_ansi(rendered: &str) -> String {
+    visible_chars(rendered).collect()
+}
+
+/// The characters of `s`, skipping ANSI escape sequences.
+fn visible_chars(s: &str) -> impl Iterator<Item = char> + '_ {
+    visible_char_indices(s).map(|(_, c)| c)
+}
+
+/// The characters of `s` and their byte index, skipping ANSI escape sequences.
+fn visible_char_indices(s: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut chars = s.char_indices().peekable();
+    std::iter::from_fn(move || loop {
+        match chars.next()? {
+            // Operating system commands, e.g. hyperlinks, end with BEL or `ESC \`
+            (_, '\x1b') if chars.peek().map(|&(_, c)| c) == Some(']') => {
+                while let Some((_, c)) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if(|&(_, c)| c == '\\').is_some())
+                    {
+                        break;
                     }
                 }
-                Event::StopTrack(stopped) => {
-                    assert!(tracks.contains(stopped));
-                    for track_id in tracks.iter() {
-                        write!(
-                            w,
-                            "{}",
-                            if track_id == stopped {
-                                Rail::Ground
-                            } else {
-                                Rail::Ground
-                                // Rail::Straight
-                            }
-                            .render(&self.rdr, track_id)
-                        )?;
+            }
+            (_, '\x1b') => {
+                for (_, c) in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
                     }
-                    writeln!(w)?;
-                    tracks.retain(|t| t != stopped);
                 }
-                Event::Station(target_id, cow) => {
-                    for (i, line) in cow.lines().enumerate() {
-                        for track_id in tracks.iter() {
-                            write!(
-                                w,
-                                "{}",
-                                if i == 0 && track_id == target_id {
-                                    Rail::Station
-                                } else {
-                                    Rail::Straight
-                                }
-                                .render(&self.rdr, track_id)
-                            )?;
-                        }
-                        write!(
-                            w,
-                            "{line:>pad$}",
-                            pad = line.len() + widest_track - tracks.len() + 3
-                        )?;
-                        writeln!(w)?;
-                    }
-                    for track_id in tracks.iter() {
-                        write!(w, "{}", Rail::Straight.render(&self.rdr, track_id))?;
-                    }
-                    writeln!(w)?;
+            }
+            visible => return Some(visible),
+        }
+    })
+}
+
+/// Highlight every occurrence of `pattern` in the visible text of `text`
+/// in reverse video, keeping the escape sequences of `text`.
+fn highlight_visible(text: &str, pattern: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    // The byte range in `text` of the character of every byte of `plain`
+    let mut spans = Vec::with_capacity(text.len());
+    for (i, c) in visible_char_indices(text) {
+        plain.push(c);
+        spans.extend(std::iter::repeat_n((i, i + c.len_utf8()), c.len_utf8()));
+    }
+
+    let mut highlighted = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, m) in plain.match_indices(pattern) {
+        let (from, _) = spans[start];
+        let (_, to) = spans[start + m.len() - 1];
+        highlighted.push_str(&text[last..from]);
+        highlighted.push_str("\x1b[7m");
+        highlighted.push_str(&text[from..to]);
+        highlighted.push_str("\x1b[27m");
+        last = to;
+    }
+    highlighted.push_str(&text[last..]);
+    highlighted
+}
+
+fn widest_track(rows: &[Row]) -> usize {
+    rows.iter().map(|row| row.rails.len()).max().unwrap_or(1)
+}
+
+fn time_width(times: &[Option<String>]) -> usize {
+    times
+        .iter()
+        .map(|t| t.as_ref().map_or(0, |t| t.chars().count()))
+        .max()
+        .unwrap_or(0)
+}
+
+pub(crate) fn render<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    render_range(w, events, rdr, 0..usize::MAX)
+}
+
+/// Render `events` as one JSON object per row, with the rails of the
+/// row as `cells`, along with their `track` and `kind`.
+pub(crate) fn render_structured<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    // Consumers style rows themselves
+    let rdr = rdr.clone().color(false);
+    let rows = layout(events, &rdr);
+    let times = time_column(&rows, &mut None, &rdr);
+    let widths = rdr.column_widths(&rows);
+
+    for (i, (row, time)) in rows.iter().zip(times.iter()).enumerate() {
+        write!(w, r#"{{"row": {}, "cells": ["#, i)?;
+        let glyphs = rdr.rails_to_str(&row.rails, &widths);
+        for (j, ((rail, track_id), glyph)) in row.rails.iter().zip(glyphs).enumerate() {
+            if j > 0 {
+                write!(w, ", ")?;
+            }
+            write!(
+                w,
+                r#"{{"track": {}, "kind": "{}", "glyph": ""#,
+                track_id.0,
+                rail.kind()
+            )?;
+            write_json_escaped(w, &glyph)?;
+            write!(w, r#""}}"#)?;
+        }
+        write!(
+            w,
+            r#"], "station": {}, "detached": {}"#,
+            row.starts_station, row.detached
+        )?;
+        for (key, value) in [("time", time.as_deref()), ("text", row.text.as_deref())] {
+            match value {
+                Some(value) => {
+                    write!(w, r#", "{}": ""#, key)?;
+                    write_json_escaped(w, value)?;
+                    write!(w, "\"")?;
                 }
-                Event::SplitTrack(parent, child) => {
-                    let parent_position = tracks
-                        .iter()
-                        .position(|t| t == parent)
-                        .expect(&format!("no parent {parent:?} found in {tracks:?}"));
-                    if tracks.len() > 1 {
-                        for l_i in 0..(tracks.len() - parent_position) {
-                            for (i, track_id) in tracks.iter().enumerate() {
-                                let ii = tracks.len() - i;
-                                if ii == l_i {
-                                    write!(w, "{}", Rail::ShiftRight.render(&self.rdr, track_id))?;
-                                } else {
-                                    write!(w, "{}", Rail::Straight.render(&self.rdr, track_id))?;
-                                }
-                            }
-                            writeln!(w)?;
-                        }
-                    }
-                    tracks.insert(parent_position + 1, *child);
-                    for track_id in tracks.iter() {
-                        write!(
-                            w,
-                            "{}",
-                            if track_id == child {
-                                Rail::TopRight
-                            } else if track_id == parent {
-                                Rail::SplitRight
-                            } else {
-                                Rail::Straight
-                            }
-                            .render(&self.rdr, track_id)
-                        )?;
-                    }
-                    writeln!(w)?;
+                None => write!(w, r#", "{}": null"#, key)?,
+            }
+        }
+        writeln!(w, "}}")?;
+    }
+
+    Ok(())
+}
+
+/// Render only the rows of `events` within `range`. Everything is laid
+/// out as in a full rendering, so the window matches it exactly.
+pub(crate) fn render_range<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+    range: Range<usize>,
+) -> io::Result<()> {
+    render_rows(w, events, rdr, range, None).map(drop)
+}
+
+/// Render `events`, highlighting every occurrence of `pattern` in
+/// the text of rows, and return the index of every such row.
+pub(crate) fn render_highlighting<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+    pattern: &str,
+) -> io::Result<Vec<usize>> {
+    render_rows(w, events, rdr, 0..usize::MAX, Some(pattern))
+}
+
+/// Render the rows of `events` within `range`, highlighting every
+/// occurrence of `highlight`, if any, in the text of rows. Returns
+/// the index of every row containing `highlight`.
+fn render_rows<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+    range: Range<usize>,
+    highlight: Option<&str>,
+) -> io::Result<Vec<usize>> {
+    let rows = layout(events, rdr);
+    write_rows(w, &rows, events, rdr, range, highlight)
+}
+
+/// Render `events` once to every sink of `sinks`, each styled with its
+/// own settings, e.g. colored for a terminal and plain for a file.
+/// The rows are laid out once, with `rdr`.
+pub(crate) fn render_to_sinks(
+    sinks: &mut [(&mut dyn Write, &RenderingSettings)],
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let rows = layout(events, rdr);
+    // Field colors are part of the text of rows
+    let plain = if rdr.color && sinks.iter().any(|(_, sink)| !sink.color) {
+        rows.iter()
+            .map(|row| Row {
+                rails: row.rails.clone(),
+                text: row.text.as_deref().map(|text| strip_ansi(text).into()),
+                station: row.station,
+                starts_station: row.starts_station,
+                detached: row.detached,
+                back_reference: row.back_reference.clone(),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    for (w, sink) in sinks.iter_mut() {
+        let rows = if sink.color || plain.is_empty() {
+            &rows
+        } else {
+            &plain
+        };
+        write_rows(w, rows, events, sink, 0..usize::MAX, None)?;
+    }
+
+    Ok(())
+}
+
+/// Write the `rows` within `range`, laid out from `events`.
+fn write_rows<W: Write + ?Sized>(
+    w: &mut W,
+    rows: &[Row],
+    events: &[Event],
+    rdr: &RenderingSettings,
+    range: Range<usize>,
+    highlight: Option<&str>,
+) -> io::Result<Vec<usize>> {
+    let highlight = highlight.filter(|pattern| !pattern.is_empty());
+    let matches = match highlight {
+        Some(pattern) => rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| {
+                let text = row.text.as_deref().unwrap_or("");
+                visible_chars(text).collect::<String>().contains(pattern)
+            })
+            .map(|(i, _)| i)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut stations = Stations::default();
+    stations.time(rows, rdr);
+    let mut context = RowContext::new(rdr);
+    let times = context.gather(rows, events, rdr);
+
+    let end = range.end.min(rows.len());
+    let start = range.start.min(end);
+    if start == 0 {
+        context.write_header(w, rows, rows[..end].first(), rdr)?;
+    }
+    let written = &rows[start..end];
+    context.next = start;
+    context.write_rows(
+        w,
+        written,
+        &times[start..end],
+        &mut stations,
+        rdr,
+        highlight,
+    )?;
+    if end == rows.len() {
+        context.write_footer(w, rdr)?;
+        if rdr.summary {
+            writeln!(w, "{}{}", rdr.prefix, summary_line(rows, events, rdr))?;
+        }
+    }
+
+    Ok(matches)
+}
+
+/// What writing rows depends on besides the rows themselves, e.g. the
+/// widest track, gathered from every row to write them all at once,
+/// or from the rows so far to write them as they are laid out.
+#[derive(Clone, Debug)]
+struct RowContext {
+    /// The number of rows gathered.
+    rows: usize,
+    widest_track: Option<usize>,
+    time_width: usize,
+    /// The last station with a time, for time deltas.
+    last_time: Option<SystemTime>,
+    colors: ColorAliases,
+    /// The number of stations of every track, for elastic columns.
+    stations: HashMap<TrackId, usize>,
+    widths: Vec<usize>,
+    /// The group brackets drawn last.
+    drawn: Vec<(usize, usize, String)>,
+    /// The number of lines written so far.
+    lines: usize,
+    /// The index of the next row to write.
+    next: usize,
+    /// The rails of the last row written, if any, for the ruler.
+    last_rails: Option<Vec<(Rail, TrackId)>>,
+}
+
+impl RowContext {
+    fn new(rdr: &RenderingSettings) -> Self {
+        Self {
+            rows: 0,
+            widest_track: None,
+            time_width: 0,
+            last_time: None,
+            colors: ColorAliases::new(&[], rdr),
+            stations: HashMap::new(),
+            widths: Vec::new(),
+            drawn: Vec::new(),
+            lines: 0,
+            next: 0,
+            last_rails: None,
+        }
+    }
+
+    /// Take `rows`, laid out from `events`, into account, and return
+    /// the time column label of every row of them, if any.
+    fn gather(
+        &mut self,
+        rows: &[Row],
+        events: &[Event],
+        rdr: &RenderingSettings,
+    ) -> Vec<Option<String>> {
+        self.rows += rows.len();
+        self.widest_track = self
+            .widest_track
+            .max(rows.iter().map(|row| row.rails.len()).max());
+        let times = time_column(rows, &mut self.last_time, rdr);
+        self.time_width = self.time_width.max(time_width(&times));
+        self.colors.note(events, rdr);
+        rdr.widen_columns(rows, &mut self.stations, &mut self.widths);
+        times
+    }
+
+    fn number_width(&self, rdr: &RenderingSettings) -> usize {
+        number_width(self.rows, rdr)
+    }
+
+    /// The start of the lines without row numbers or times.
+    fn blank_gutters<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        rdr: &RenderingSettings,
+    ) -> io::Result<()> {
+        let pad = gutters_width(self.number_width(rdr), self.time_width);
+        write!(w, "{}{:pad$}", rdr.prefix, "", pad = pad)
+    }
+
+    /// Write the [swimlane] header of `rows`, and the [ruler] above
+    /// `first`, the first row written, if any.
+    ///
+    /// [swimlane]: struct.RenderingSettings.html#method.swimlanes
+    /// [ruler]: struct.RenderingSettings.html#method.ruler
+    fn write_header<W: Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        rows: &[Row],
+        first: Option<&Row>,
+        rdr: &RenderingSettings,
+    ) -> io::Result<()> {
+        let w = &mut LineCount {
+            w,
+            lines: self.lines,
+        };
+        if let Some(header) = rdr.swimlane_header(rows, &self.widths) {
+            self.blank_gutters(w, rdr)?;
+            writeln!(w, "{}", header)?;
+        }
+        if let Some(row) = first.filter(|_| rdr.ruler) {
+            self.blank_gutters(w, rdr)?;
+            writeln!(
+                w,
+                "{}",
+                rdr.labels(&row.rails, &self.widths, &self.colors, true)
+            )?;
+        }
+        self.lines = w.lines;
+        Ok(())
+    }
+
+    /// Write `rows`, the first of which is the `next` row, along with
+    /// their `times`, highlighting every occurrence of `highlight`, if
+    /// any, in their text.
+    fn write_rows<W: Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        rows: &[Row],
+        times: &[Option<String>],
+        stations: &mut Stations,
+        rdr: &RenderingSettings,
+        highlight: Option<&str>,
+    ) -> io::Result<()> {
+        let first = self.next;
+        let widest_track = self.widest_track.unwrap_or(1);
+        let number_width = self.number_width(rdr);
+        let heat = heat_colors(rows, &stations.durations, rdr);
+        let brackets = rdr.group_brackets(rows, &self.widths, &mut self.drawn);
+        let (widths, colors) = (&self.widths, &self.colors);
+        let w = &mut LineCount {
+            w,
+            lines: self.lines,
+        };
+
+        let lines = rows.iter().zip(times.iter()).zip(heat.iter());
+        for (i, (((row, time), heat), brackets)) in lines.zip(brackets.iter()).enumerate() {
+            // Following a row written before
+            let follows = i > 0 || self.last_rails.is_some();
+            if follows && rdr.starts_page(first + i) {
+                if let Some((_, separator)) = &rdr.page_break {
+                    write!(w, "{}", separator)?;
                 }
-                Event::JoinTrack(child, target) => {
-                    let target_position = tracks.iter().position(|t| t == target).unwrap();
-                    let child_position = tracks
-                        .iter()
-                        .position(|t| t == child)
-                        .expect(&format!("child {child:?} not found in {tracks:?}"));
-                    let min_position = target_position.min(child_position);
-                    let max_position = target_position.max(child_position);
-                    for (i, track_id) in tracks.iter().enumerate() {
-                        if i == target_position {
-                            write!(
-                                w,
-                                "{}",
-                                if child_position > target_position {
-                                    Rail::SplitRight
-                                } else {
-                                    Rail::SplitLeft
-                                }
-                                .render(&self.rdr, track_id)
-                            )?;
-                        } else if i == child_position {
-                            write!(
-                                w,
-                                "{}",
-                                if child_position > target_position {
-                                    Rail::BottomRight
-                                } else {
-                                    Rail::BottomtLeft
-                                }
-                                .render(&self.rdr, child)
-                            )?;
-                        } else if i > min_position && i < max_position {
-                            write!(w, "{}", Rail::Horizontal.render(&self.rdr, child))?;
-                        } else {
-                            write!(w, "{}", Rail::Straight.render(&self.rdr, track_id))?;
-                        }
-                    }
-                    writeln!(w)?;
-                    tracks.retain(|t| t != child);
-                    for i in if child_position > target_position {
-                        max_position
-                    } else {
-                        min_position + 1
-                    }..tracks.len()
+                self.blank_gutters(w, rdr)?;
+                let rendered = rdr.rails_to_str(&row.rails, widths);
+                for ((_, track_id), rail) in row.rails.iter().zip(rendered.iter()) {
+                    let above = rdr.rail_continued(rail, *track_id, true);
+                    write!(
+                        w,
+                        "{}",
+                        rdr.paint_rail(above, *track_id, &colors.resolve(*track_id))
+                    )?;
+                }
+                writeln!(w)?;
+            }
+            if follows && rdr.repeats_header(first + i) {
+                self.blank_gutters(w, rdr)?;
+                writeln!(w, "{}", rdr.labels(&row.rails, widths, colors, true))?;
+            }
+            if let Some(brackets) = brackets {
+                self.blank_gutters(w, rdr)?;
+                writeln!(w, "{}", brackets)?;
+            }
+            if row.starts_station && row.station.and_then(|s| s.id()).is_some() {
+                stations.lines.insert(first + i, w.lines + 1);
+            }
+            write!(w, "{}", rdr.prefix)?;
+            if number_width > 0 {
+                let number = format!("{:>width$}", first + i + 1, width = number_width);
+                if cfg!(feature = "color") && rdr.color {
+                    write!(w, "\x1b[2m{}\x1b[22m ", number)?;
+                } else {
+                    write!(w, "{} ", number)?;
+                }
+            }
+            if self.time_width > 0 {
+                write!(
+                    w,
+                    "{:>width$} ",
+                    time.as_deref().unwrap_or(""),
+                    width = self.time_width
+                )?;
+            }
+            let rendered = rdr.rails_to_str(&row.rails, widths);
+            for ((rail, track_id), rendered) in row.rails.iter().zip(rendered) {
+                match (rail, heat) {
+                    (Rail::Station | Rail::SharedStation, Some(heat))
+                        if rdr.heat == Heat::Marker =>
                     {
-                        for (j, track_id) in tracks.iter().enumerate() {
-                            if j == i && j != 0 {
-                                write!(w, "{}", Rail::ShiftLeft.render(&self.rdr, track_id))?;
-                            } else {
-                                write!(w, "{}", Rail::Straight.render(&self.rdr, track_id))?;
-                            }
-                        }
-                        writeln!(w)?;
+                        write!(w, "{}", paint(rendered, *heat))?
                     }
+                    (Rail::Aborted, _) if cfg!(feature = "color") && rdr.color => {
+                        let [r, g, b] = rdr.get_severity_color(Severity::Error);
+                        write!(w, "{}", paint(rendered, Rgb(r, g, b)))?
+                    }
+                    _ => write!(
+                        w,
+                        "{}",
+                        rdr.paint_rail(rendered, *track_id, &colors.resolve(*track_id))
+                    )?,
+                }
+            }
+            if let Some(text) = row_text(row, &stations.lines, number_width) {
+                write!(w, "{:pad$}", "", pad = widest_track + 3 - row.rails.len())?;
+                if let Some(marker) = rdr.marker_for(row) {
+                    write!(w, "{} ", marker)?;
                 }
-                Event::NoEvent => {
-                    for track_id in tracks.iter() {
-                        write!(w, "{}", Rail::Straight.render(&self.rdr, track_id))?;
+                let text = match highlight {
+                    Some(pattern) if cfg!(feature = "color") && rdr.color => {
+                        // Reverse video, which stands out whatever the colors
+                        Cow::Owned(highlight_visible(&text, pattern))
                     }
-
-                    writeln!(w)?;
+                    _ => text,
+                };
+                match heat {
+                    Some(heat) if rdr.heat == Heat::Text => write!(w, "{}", paint(text, *heat))?,
+                    _ => write!(w, "{}", text)?,
                 }
             }
+            writeln!(w)?;
         }
-
+        if let Some(row) = rows.last() {
+            self.last_rails = Some(row.rails.clone());
+        }
+        self.next += rows.len();
+        self.lines = w.lines;
         Ok(())
     }
 
-    /// Write `&[`[`Event`]`]` to [`Vec<u8>`].
-    /// Defines a default track with `track_id` of `0`.
-    ///
-    /// *[See also `Metro::to_vec`.][`Metro::to_vec`]*
-    ///
-    /// *See also [`to_string`] and [`to_writer`].*
-    ///
-    /// [`to_writer`]: fn.to_writer.html
-    /// [`to_string`]: fn.to_string.html
+    /// Write the [ruler] below the last row written, if any.
     ///
-    /// [`Event`]: enum.Event.html
-    ///
-    /// [`Metro::to_vec`]: struct.Metro.html#method.to_vec
-    ///
-    /// [`Vec<u8>`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
-    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
-        let mut vec = Vec::new();
-        self.to_writer(&mut vec)?;
-        Ok(vec)
+    /// [ruler]: struct.RenderingSettings.html#method.ruler
+    fn write_footer<W: Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        rdr: &RenderingSettings,
+    ) -> io::Result<()> {
+        if let Some(rails) = self.last_rails.as_ref().filter(|_| rdr.ruler) {
+            let w = &mut LineCount {
+                w,
+                lines: self.lines,
+            };
+            self.blank_gutters(w, rdr)?;
+            writeln!(
+                w,
+                "{}",
+                rdr.labels(rails, &self.widths, &self.colors, false)
+            )?;
+            self.lines = w.lines;
+        }
+        Ok(())
     }
+}
 
-    /// Write `&[`[`Event`]`]` to [`String`].
-    /// Defines a default track with `track_id` of `0`.
-    ///
-    /// *[See also `Metro::to_string`.][`Metro::to_string`]*
-    ///
-    /// *See also [`to_vec`] and [`to_writer`].*
-    ///
-    /// [`to_writer`]: fn.to_writer.html
-    /// [`to_vec`]: fn.to_vec.html
-    ///
-    /// [`Event`]: enum.Event.html
-    ///
-    /// [`Metro::to_string`]: struct.Metro.html#method.to_string
+/// The state of rendering events one at a time, e.g. as they are
+/// emitted, without laying out or writing the rows so far again.
+#[derive(Clone, Debug)]
+pub(crate) struct Incremental {
+    layout: Layout,
+    context: RowContext,
+}
+
+impl Incremental {
+    pub(crate) fn new(rdr: &RenderingSettings) -> Self {
+        Self {
+            layout: Layout::new(rdr),
+            context: RowContext::new(rdr),
+        }
+    }
+
+    /// Lay out and write the rows of `event`, following the rows
+    /// written so far, with the updates of `patches` applied.
+    pub(crate) fn write<W: Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        event: &Event,
+        patches: &Patches,
+        stations: &mut Stations,
+        rdr: &RenderingSettings,
+    ) -> io::Result<()> {
+        let index = self.layout.events;
+        let mut rows = Vec::new();
+        self.layout.note(event, stations);
+        self.layout.push(event, rdr, patches, stations, &mut rows);
+        // Written again, its stations were timed already
+        if index == stations.timed {
+            stations.time(&rows, rdr);
+            stations.timed += 1;
+        }
+
+        let times = self.context.gather(&rows, std::slice::from_ref(event), rdr);
+        if self.context.next == 0 && !rows.is_empty() {
+            self.context.write_header(w, &rows, rows.first(), rdr)?;
+        }
+        self.context
+            .write_rows(w, &rows, &times, stations, rdr, None)
+    }
+
+    /// Write what follows the last row, e.g. the [ruler] below it.
     ///
-    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
-    pub fn to_string(&self) -> io::Result<String> {
-        let vec = self.to_vec()?;
-        // Metro only writes `str`s and `String`s to the `vec`
-        // which are always valid UTF-8, so this is safe.
-        #[allow(unsafe_code)]
-        unsafe {
-            Ok(String::from_utf8_unchecked(vec))
+    /// [ruler]: struct.RenderingSettings.html#method.ruler
+    pub(crate) fn finish<W: Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        rdr: &RenderingSettings,
+    ) -> io::Result<()> {
+        self.context.write_footer(w, rdr)
+    }
+}
+
+/// The text of `row`, if any, with its reference back to a station,
+/// if any, renumbered by the line the station was `printed` on, by
+/// row, unless rows are numbered.
+fn row_text<'r>(
+    row: &'r Row,
+    printed: &HashMap<usize, usize>,
+    number_width: usize,
+) -> Option<Cow<'r, str>> {
+    let text = row.text.as_deref()?;
+    Some(match &row.back_reference {
+        // With row numbers, references match the numbers of rows
+        Some((target, from, to)) if number_width == 0 => match printed.get(target) {
+            Some(&line) => Cow::Owned(back_reference_text(from, to, line)),
+            None => Cow::Borrowed(text),
+        },
+        _ => Cow::Borrowed(text),
+    })
+}
+
+/// A writer counting the lines written through it.
+struct LineCount<'w, W: ?Sized> {
+    w: &'w mut W,
+    lines: usize,
+}
+
+impl<W: Write + ?Sized> Write for LineCount<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.w.write(buf)?;
+        self.lines += buf[..written].iter().filter(|&&b| b == b'\n').count();
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Write the [summary] of `events`.
+///
+/// [summary]: struct.RenderingSettings.html#method.summary
+pub(crate) fn write_summary<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let rows = layout(events, rdr);
+    writeln!(w, "{}{}", rdr.prefix, summary_line(&rows, events, rdr))
+}
+
+/// Count the tracks of `events` which completed, were aborted, or
+/// are still open, and the stations of `rows`, laid out from them.
+fn summary_line(rows: &[Row], events: &[Event], rdr: &RenderingSettings) -> String {
+    let mut open = rdr.initial_tracks();
+    let mut started: HashSet<TrackId> = open.iter().copied().collect();
+    for_each_tracks(events, rdr, |_, tracks| {
+        started.extend(tracks.iter().copied());
+        open = tracks.to_vec();
+    });
+    let aborted = started
+        .iter()
+        .filter(|&&t| !open.contains(&t) && rdr.get_abort_reason(t).is_some())
+        .count();
+    let completed = started.len() - open.len() - aborted;
+    let stations = rows.iter().filter(|row| row.starts_station).count();
+    format!(
+        "Tracks: {} completed, {} aborted, {} open; stations: {}",
+        completed,
+        aborted,
+        open.len(),
+        stations
+    )
+}
+
+/// Draw the `(first, last, name)` spans of groups, from left to right,
+/// each as a bracket from its first to its last column, around its
+/// name if it fits, and followed by it otherwise.
+fn bracket_line(spans: &[(usize, usize, &str)]) -> String {
+    let mut line = String::new();
+    let mut width = 0;
+    for (i, &(first, last, name)) in spans.iter().enumerate() {
+        if first < width {
+            continue;
+        }
+        line.push_str(&" ".repeat(first - width));
+        let inner = last.saturating_sub(first + 1);
+        let length = name.chars().count();
+        let bracket = if last > first && length <= inner {
+            let fill = inner - length;
+            format!(
+                "╭{}{}{}╮",
+                "─".repeat(fill / 2),
+                name,
+                "─".repeat(fill - fill / 2)
+            )
+        } else {
+            let bracket = match last > first {
+                true => format!("╭{}╮ ", "─".repeat(inner)),
+                false => String::new(),
+            };
+            // Up to the next group
+            let room = match spans.get(i + 1) {
+                Some(&(next, _, _)) => next.saturating_sub(first + bracket.chars().count() + 1),
+                None => usize::MAX,
+            };
+            format!(
+                "{}{}",
+                bracket,
+                name.chars().take(room.max(1)).collect::<String>()
+            )
+        };
+        width = first + bracket.chars().count();
+        line.push_str(&bracket);
+    }
+    line
+}
+
+/// The width of the row number and time gutters, with their spacing.
+fn gutters_width(number_width: usize, time_width: usize) -> usize {
+    let gutter = |width| if width > 0 { width + 1 } else { 0 };
+    gutter(number_width) + gutter(time_width)
+}
+
+/// The width of the row number gutter, if any, for `row_count` rows.
+fn number_width(row_count: usize, rdr: &RenderingSettings) -> usize {
+    if rdr.row_numbers {
+        row_count.max(1).to_string().len()
+    } else {
+        0
+    }
+}
+
+/// The xterm 256 color of the rails of `track_id`.
+fn track_xterm_color(track_id: TrackId) -> u8 {
+    (((track_id.0 + 1) ^ 93) % 255) as u8
+}
+
+/// The track each track is colored like, if any.
+#[derive(Clone, Debug)]
+pub(crate) struct ColorAliases(HashMap<TrackId, TrackId>);
+
+impl ColorAliases {
+    pub(crate) fn new(events: &[Event], rdr: &RenderingSettings) -> Self {
+        let mut aliases = HashMap::new();
+        for (existing, new) in rdr.color_aliases.iter() {
+            aliases.insert(*new, *existing);
+        }
+        let mut aliases = Self(aliases);
+        aliases.note(events, rdr);
+        aliases
+    }
+
+    /// Take the colors inherited in `events`, if any, into account.
+    fn note(&mut self, events: &[Event], rdr: &RenderingSettings) {
+        if !rdr.inherit_colors {
+            return;
+        }
+        for event in events {
+            // Explicit aliases take precedence over inherited colors
+            if let Event::SplitTrack(parent, child) = event {
+                self.0.entry(*child).or_insert(*parent);
+            }
+        }
+    }
+
+    pub(crate) fn resolve(&self, mut track_id: TrackId) -> TrackId {
+        // Bounded, in case aliases form a cycle
+        for _ in 0..self.0.len() {
+            match self.0.get(&track_id) {
+                Some(existing) if *existing != track_id => track_id = *existing,
+                _ => break,
+            }
+        }
+        track_id
+    }
+}
+
+/// Format the time column label of every row, if any, following
+/// the `previous` station with a time, for deltas, then updated.
+fn time_column(
+    rows: &[Row],
+    previous: &mut Option<SystemTime>,
+    rdr: &RenderingSettings,
+) -> Vec<Option<String>> {
+    let now = SystemTime::now();
+    rows.iter()
+        .map(|row| {
+            let time = row.station?.time()?;
+            match rdr.time_column {
+                TimeColumn::Hidden => None,
+                TimeColumn::Absolute => format_time(time, &rdr.time_format),
+                TimeColumn::Relative => Some(match now.duration_since(time) {
+                    Ok(elapsed) => format!("{} ago", format_relative(elapsed)),
+                    Err(error) => format!("in {}", format_relative(error.duration())),
+                }),
+                TimeColumn::Delta => {
+                    let delta = previous
+                        .and_then(|previous| time.duration_since(previous).ok())
+                        .unwrap_or_default();
+                    *previous = Some(time);
+                    Some(format_delta(delta))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Format `time` with the `strftime`-like `format`, in UTC.
+#[cfg(feature = "chrono")]
+fn format_time(time: SystemTime, format: &str) -> Option<String> {
+    use std::fmt::Write;
+
+    let time = chrono::DateTime::<chrono::Utc>::from(time);
+    let mut formatted = String::new();
+    // Invalid patterns fail to format
+    write!(formatted, "{}", time.format(format)).ok()?;
+    Some(formatted)
+}
+
+/// Format `time` with the `strftime`-like `format`, in UTC.
+#[cfg(not(feature = "chrono"))]
+fn format_time(time: SystemTime, format: &str) -> Option<String> {
+    let since_epoch = time.duration_since(std::time::UNIX_EPOCH).ok()?;
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days(secs / 86400);
+    // Days since January 1st, for `%j`
+    let year_day = secs / 86400 - days_from_civil(year, 1, 1) + 1;
+
+    let mut formatted = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(&year.to_string()),
+            Some('y') => formatted.push_str(&format!("{:02}", year % 100)),
+            Some('m') => formatted.push_str(&format!("{:02}", month)),
+            Some('d') => formatted.push_str(&format!("{:02}", day)),
+            Some('j') => formatted.push_str(&format!("{:03}", year_day)),
+            Some('H') => formatted.push_str(&format!("{:02}", secs / 3600 % 24)),
+            Some('M') => formatted.push_str(&format!("{:02}", secs / 60 % 60)),
+            Some('S') => formatted.push_str(&format!("{:02}", secs % 60)),
+            Some('s') => formatted.push_str(&secs.to_string()),
+            Some('%') => formatted.push('%'),
+            Some('3') if chars.as_str().starts_with('f') => {
+                chars.next();
+                formatted.push_str(&format!("{:03}", since_epoch.subsec_millis()));
+            }
+            // Unsupported patterns are kept as is
+            Some(c) => {
+                formatted.push('%');
+                formatted.push(c);
+            }
+            None => formatted.push('%'),
         }
     }
+    Some(formatted)
+}
+
+/// The `(year, month, day)` of the given days since the epoch,
+/// following <http://howardhinnant.github.io/date_algorithms.html>.
+#[cfg(not(feature = "chrono"))]
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The days since the epoch of the given date, the
+/// inverse of `civil_from_days`.
+#[cfg(not(feature = "chrono"))]
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Humanize `elapsed` in its largest unit, e.g. `3m`.
+fn format_relative(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+fn format_delta(delta: Duration) -> String {
+    let secs = delta.as_secs();
+    if secs < 60 {
+        format!("+{:.3}s", delta.as_secs_f64())
+    } else if secs < 3600 {
+        format!("+{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("+{}h{:02}m", secs / 3600, secs / 60 % 60)
+    }
+}
+
+/// Whether rows are painted by the [heat] of their station.
+///
+/// [heat]: struct.RenderingSettings.html#method.heat
+fn heats(rdr: &RenderingSettings) -> bool {
+    cfg!(feature = "color") && rdr.color && rdr.heat != Heat::Off
+}
+
+/// The heat color of every row holding a station with a duration,
+/// by the percentile of that duration among the sorted `durations`
+/// of all stations.
+fn heat_colors(rows: &[Row], durations: &[Duration], rdr: &RenderingSettings) -> Vec<Option<Rgb>> {
+    if !heats(rdr) {
+        return vec![None; rows.len()];
+    }
+
+    let slowest = durations.len().saturating_sub(1).max(1) as f64;
+
+    rows.iter()
+        .map(|row| {
+            let duration = row.station?.duration()?;
+            let p = durations.partition_point(|&d| d < duration) as f64 / slowest;
+            // Green, through yellow, to red
+            Some(if p < 0.5 {
+                Rgb((p * 2.0 * 255.0) as u8, 200, 0)
+            } else {
+                Rgb(255, ((1.0 - p) * 2.0 * 200.0) as u8, 0)
+            })
+        })
+        .collect()
 }
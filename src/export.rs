@@ -0,0 +1,298 @@
+use crate::events::{write_json_escaped, ColorAliases, Event, RenderingSettings};
+use crate::graph::{Graph, Node};
+use std::io::{self, Write};
+
+/// Write `events` as a [D2] diagram, one shape per station, connected
+/// along tracks, with each shape outlined in the color of its track.
+///
+/// [D2]: https://d2lang.com
+pub(crate) fn to_d2<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let graph = Graph::new(events, rdr);
+    let colors = ColorAliases::new(events, rdr);
+
+    writeln!(w, "direction: down")?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        write!(w, "s{}: \"", i)?;
+        write_d2_escaped(w, &node.label)?;
+        writeln!(w, "\"")?;
+        if let Some(color) = node_color(node, &colors, rdr) {
+            writeln!(w, "s{}.style.stroke: \"{}\"", i, color)?;
+        }
+        if let Some(link) = &node.link {
+            write!(w, "s{}.link: \"", i)?;
+            write_d2_escaped(w, link)?;
+            writeln!(w, "\"")?;
+        }
+    }
+    for (from, to) in graph.edges.iter() {
+        writeln!(w, "s{} -> s{}", from, to)?;
+    }
+
+    Ok(())
+}
+
+/// The color of the first track of `node` as `#rrggbb`, if it has any.
+fn node_color(node: &Node, colors: &ColorAliases, rdr: &RenderingSettings) -> Option<String> {
+    let track_id = node.tracks.first()?;
+    let (r, g, b) = rdr.track_rgb(colors.resolve(*track_id));
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn write_d2_escaped<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            '\n' => write!(w, "\\n")?,
+            c => write!(w, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+/// The spacing between columns and rows of stations in Excalidraw scenes.
+const COLUMN_WIDTH: i64 = 240;
+const ROW_HEIGHT: i64 = 80;
+const FONT_SIZE: i64 = 20;
+
+/// Write `events` as an [Excalidraw] scene, one text element per station,
+/// laid out in the columns of their tracks, connected by arrows.
+///
+/// [Excalidraw]: https://excalidraw.com
+pub(crate) fn to_excalidraw<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let graph = Graph::new(events, rdr);
+    let colors = ColorAliases::new(events, rdr);
+
+    let position =
+        |i: usize, node: &Node| (node.column as i64 * COLUMN_WIDTH, i as i64 * ROW_HEIGHT);
+    let size = |node: &Node| {
+        let lines = node.label.lines().count().max(1) as i64;
+        let columns = node
+            .label
+            .lines()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0) as i64;
+        // Roughly the size of the default font
+        (columns * FONT_SIZE * 11 / 20, lines * FONT_SIZE * 5 / 4)
+    };
+    let color =
+        |node: &Node| node_color(node, &colors, rdr).unwrap_or_else(|| "#1e1e1e".to_owned());
+
+    write!(
+        w,
+        r#"{{"type": "excalidraw", "version": 2, "source": "metro", "elements": ["#
+    )?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let (x, y) = position(i, node);
+        let (width, height) = size(node);
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        write!(w, "\n  ")?;
+        write_element(
+            w,
+            &format!("station-{}", i),
+            "text",
+            (x, y, width, height),
+            &color(node),
+        )?;
+        write!(w, r#", "text": ""#)?;
+        write_json_escaped(w, &node.label)?;
+        write!(w, r#"", "originalText": ""#)?;
+        write_json_escaped(w, &node.label)?;
+        write!(
+            w,
+            r#"", "fontSize": {}, "fontFamily": 1, "textAlign": "left", "verticalAlign": "top", "containerId": null, "lineHeight": 1.25, "autoResize": true, "link": "#,
+            FONT_SIZE
+        )?;
+        match &node.link {
+            Some(link) => {
+                write!(w, "\"")?;
+                write_json_escaped(w, link)?;
+                write!(w, "\"}}")?;
+            }
+            None => write!(w, "null}}")?,
+        }
+    }
+    for (i, &(from, to)) in graph.edges.iter().enumerate() {
+        let (from_x, from_y) = position(from, &graph.nodes[from]);
+        let (to_x, to_y) = position(to, &graph.nodes[to]);
+        // From the bottom of a station to the top of the next
+        let start = (from_x, from_y + size(&graph.nodes[from]).1);
+        let (dx, dy) = (to_x - start.0, to_y - start.1);
+        // Edges always follow stations
+        write!(w, ",\n  ")?;
+        write_element(
+            w,
+            &format!("edge-{}", i),
+            "arrow",
+            (start.0, start.1, dx.abs(), dy.abs()),
+            &color(&graph.nodes[to]),
+        )?;
+        write!(
+            w,
+            r#", "points": [[0, 0], [{}, {}]], "lastCommittedPoint": null, "startBinding": null, "endBinding": null, "startArrowhead": null, "endArrowhead": "arrow", "link": null}}"#,
+            dx, dy
+        )?;
+    }
+    writeln!(
+        w,
+        "\n], \"appState\": {{\"viewBackgroundColor\": \"#ffffff\"}}, \"files\": {{}}}}"
+    )?;
+
+    Ok(())
+}
+
+/// Write the properties shared by every Excalidraw element,
+/// leaving the object open for the properties of its type.
+fn write_element<W: Write>(
+    w: &mut W,
+    id: &str,
+    kind: &str,
+    (x, y, width, height): (i64, i64, i64, i64),
+    color: &str,
+) -> io::Result<()> {
+    // Seeds only need to be stable, to render the same sketch every time
+    let seed = id
+        .bytes()
+        .fold(1u32, |seed, b| seed.wrapping_mul(31).wrapping_add(b.into()));
+    write!(
+        w,
+        r#"{{"id": "{id}", "type": "{kind}", "x": {x}, "y": {y}, "width": {width}, "height": {height}, "angle": 0, "strokeColor": "{color}", "backgroundColor": "transparent", "fillStyle": "solid", "strokeWidth": 2, "strokeStyle": "solid", "roughness": 1, "opacity": 100, "groupIds": [], "frameId": null, "roundness": null, "seed": {seed}, "version": 1, "versionNonce": {seed}, "isDeleted": false, "boundElements": null, "updated": 1, "locked": false"#,
+    )
+}
+
+/// Write `events` as a [GraphML] document, one node per station, with
+/// its label, link, tracks and color as data, connected along tracks.
+///
+/// [GraphML]: http://graphml.graphdrawing.org
+pub(crate) fn to_graphml<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let graph = Graph::new(events, rdr);
+    let colors = ColorAliases::new(events, rdr);
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for key in ["label", "link", "tracks", "color"] {
+        writeln!(
+            w,
+            r#"  <key id="{0}" for="node" attr.name="{0}" attr.type="string"/>"#,
+            key
+        )?;
+    }
+    writeln!(w, r#"  <graph id="metro" edgedefault="directed">"#)?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        writeln!(w, r#"    <node id="s{}">"#, i)?;
+        write!(w, r#"      <data key="label">"#)?;
+        write_xml_escaped(w, &node.label)?;
+        writeln!(w, "</data>")?;
+        if let Some(link) = &node.link {
+            write!(w, r#"      <data key="link">"#)?;
+            write_xml_escaped(w, link)?;
+            writeln!(w, "</data>")?;
+        }
+        if !node.tracks.is_empty() {
+            let tracks = node
+                .tracks
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>();
+            writeln!(w, r#"      <data key="tracks">{}</data>"#, tracks.join(" "))?;
+        }
+        if let Some(color) = node_color(node, &colors, rdr) {
+            writeln!(w, r#"      <data key="color">{}</data>"#, color)?;
+        }
+        writeln!(w, "    </node>")?;
+    }
+    for (from, to) in graph.edges.iter() {
+        writeln!(w, r#"    <edge source="s{}" target="s{}"/>"#, from, to)?;
+    }
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")?;
+
+    Ok(())
+}
+
+/// Escape `s` for XML text, dropping the control characters XML forbids.
+fn write_xml_escaped<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    for c in s.chars() {
+        match c {
+            '&' => write!(w, "&amp;")?,
+            '<' => write!(w, "&lt;")?,
+            '>' => write!(w, "&gt;")?,
+            '"' => write!(w, "&quot;")?,
+            '\t' | '\n' | '\r' => write!(w, "{}", c)?,
+            c if (c as u32) < 0x20 => {}
+            c => write!(w, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Write `events` in the [JSON Graph Format], one node per station, with
+/// its link, tracks and color as metadata, connected along tracks.
+///
+/// [JSON Graph Format]: https://jsongraphformat.info
+pub(crate) fn to_jgf<W: Write>(
+    w: &mut W,
+    events: &[Event],
+    rdr: &RenderingSettings,
+) -> io::Result<()> {
+    let graph = Graph::new(events, rdr);
+    let colors = ColorAliases::new(events, rdr);
+
+    write!(w, r#"{{"graph": {{"directed": true, "nodes": {{"#)?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        write!(w, "\n  \"s{}\": {{\"label\": \"", i)?;
+        write_json_escaped(w, &node.label)?;
+        write!(w, r#"", "metadata": {{"tracks": ["#)?;
+        for (j, track_id) in node.tracks.iter().enumerate() {
+            if j > 0 {
+                write!(w, ", ")?;
+            }
+            write!(w, "{}", track_id)?;
+        }
+        write!(w, "]")?;
+        if let Some(link) = &node.link {
+            write!(w, r#", "link": ""#)?;
+            write_json_escaped(w, link)?;
+            write!(w, "\"")?;
+        }
+        if let Some(color) = node_color(node, &colors, rdr) {
+            write!(w, r#", "color": "{}""#, color)?;
+        }
+        write!(w, "}}}}")?;
+    }
+    write!(w, "\n}}, \"edges\": [")?;
+    for (i, (from, to)) in graph.edges.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        write!(
+            w,
+            "\n  {{\"source\": \"s{}\", \"target\": \"s{}\"}}",
+            from, to
+        )?;
+    }
+    writeln!(w, "\n]}}}}")?;
+
+    Ok(())
+}
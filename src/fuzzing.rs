@@ -0,0 +1,114 @@
+use crate::events::{Event, TrackId};
+use crate::station::{Level, Severity, Station, Status};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::borrow::Cow;
+use std::time::{Duration, UNIX_EPOCH};
+
+impl<'a> Arbitrary<'a> for TrackId {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // Few tracks, so that events mostly involve the same tracks
+        if u.ratio(1, 32)? {
+            Ok(TrackId(usize::MAX))
+        } else {
+            Ok(TrackId(u.int_in_range(0..=7)?))
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for Level {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&[
+            Level::Trace,
+            Level::Debug,
+            Level::Info,
+            Level::Warn,
+            Level::Error,
+        ])
+        .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Severity {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&[Severity::Info, Severity::Warn, Severity::Error])
+            .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Status {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&[
+            Status::Pending,
+            Status::Running,
+            Status::Done,
+            Status::Failed,
+        ])
+        .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Station<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut station = Station::new(<&str>::arbitrary(u)?);
+        if u.arbitrary()? {
+            station =
+                station.with_time(UNIX_EPOCH + Duration::from_secs(u32::arbitrary(u)?.into()));
+        }
+        if u.arbitrary()? {
+            station = station.with_duration(Duration::from_millis(u32::arbitrary(u)?.into()));
+        }
+        if u.arbitrary()? {
+            station = station.with_id(<&str>::arbitrary(u)?);
+        }
+        if u.arbitrary()? {
+            station = station.with_level(u.arbitrary()?);
+        }
+        if u.arbitrary()? {
+            station = station.with_severity(u.arbitrary()?);
+        }
+        if u.arbitrary()? {
+            station = station.with_status(u.arbitrary()?);
+        }
+        for _ in 0..u.int_in_range(0..=2)? {
+            station = station.with_tag(<&str>::arbitrary(u)?);
+        }
+        for _ in 0..u.int_in_range(0..=2)? {
+            station = station.with_field(<&str>::arbitrary(u)?, <&str>::arbitrary(u)?);
+        }
+        Ok(station)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Event<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let track_ids = |u: &mut Unstructured<'a>| -> Result<Cow<'a, [TrackId]>> {
+            let len = u.int_in_range(0..=3)?;
+            (0..len)
+                .map(|_| TrackId::arbitrary(u))
+                .collect::<Result<Vec<_>>>()
+                .map(Cow::Owned)
+        };
+
+        Ok(match u.int_in_range(0..=12)? {
+            0 => Event::StartTrack(u.arbitrary()?),
+            1 => Event::StartTracks(track_ids(u)?),
+            2 => Event::StopTrack(u.arbitrary()?),
+            3 => Event::Station(u.arbitrary()?, u.arbitrary()?),
+            4 => Event::SplitTrack(u.arbitrary()?, u.arbitrary()?),
+            5 => Event::JoinTrack(u.arbitrary()?, u.arbitrary()?),
+            6 => Event::NoEvent,
+            7 => Event::Reference(<&str>::arbitrary(u)?.into(), <&str>::arbitrary(u)?.into()),
+            8 => Event::Link(u.arbitrary()?, u.arbitrary()?),
+            9 => Event::SharedStation(track_ids(u)?, u.arbitrary()?),
+            10 => Event::UpdateStation(<&str>::arbitrary(u)?.into(), <&str>::arbitrary(u)?.into()),
+            11 => Event::SetStatus(<&str>::arbitrary(u)?.into(), u.arbitrary()?),
+            _ => {
+                let len = u.int_in_range(0..=3)?;
+                let fields = (0..len)
+                    .map(|_| Ok((<&str>::arbitrary(u)?.into(), <&str>::arbitrary(u)?.into())))
+                    .collect::<Result<_>>()?;
+                Event::StationKv(u.arbitrary()?, fields)
+            }
+        })
+    }
+}
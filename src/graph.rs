@@ -0,0 +1,111 @@
+use crate::events::{for_each_tracks, station_patches, Event, RenderingSettings, TrackId};
+use crate::station::Station;
+use std::collections::{HashMap, HashSet};
+
+/// The topology of a graph: its stations, and the connections
+/// between them along tracks, e.g. for exporting to other formats.
+pub(crate) struct Graph {
+    pub(crate) nodes: Vec<Node>,
+    /// Each connection as `(from, to)` indices into `nodes`.
+    pub(crate) edges: Vec<(usize, usize)>,
+}
+
+pub(crate) struct Node {
+    /// The plain text of the station.
+    pub(crate) label: String,
+    pub(crate) link: Option<String>,
+    /// The tracks of the station, empty if it is not tied to any track.
+    pub(crate) tracks: Vec<TrackId>,
+    /// The column of the station in the rendering.
+    pub(crate) column: usize,
+}
+
+impl Graph {
+    pub(crate) fn new(events: &[Event], rdr: &RenderingSettings) -> Self {
+        let plain = rdr.clone().color(false);
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut connected = HashSet::new();
+        // Stations are exported as last updated
+        let patches = station_patches(events);
+        let patched = |station: &Station| match station.id().and_then(|id| patches.get(id)) {
+            Some(patch) => plain.station_lines(&patch.apply(station)).join("\n"),
+            None => plain.station_lines(station).join("\n"),
+        };
+        // The stations leading into the next station of each track
+        let mut heads: HashMap<TrackId, Vec<usize>> = HashMap::new();
+
+        for_each_tracks(events, rdr, |i, alive| {
+            let (targets, label, link): (Vec<TrackId>, String, Option<&str>) = match &events[i] {
+                Event::Station(_, station) | Event::SharedStation(_, station)
+                    if plain.is_hidden(station) =>
+                {
+                    return;
+                }
+                Event::Station(track_id, station) => {
+                    (vec![*track_id], patched(station), station.link())
+                }
+                Event::SharedStation(track_ids, station) => {
+                    (track_ids.to_vec(), patched(station), station.link())
+                }
+                Event::StationKv(track_id, pairs) => (
+                    vec![*track_id],
+                    pairs
+                        .iter()
+                        .map(|(key, value)| format!("{}: {}", key, value))
+                        .collect::<Vec<_>>()
+                        .join("  "),
+                    None,
+                ),
+                Event::SplitTrack(parent, child) => {
+                    let parent = heads.get(parent).cloned().unwrap_or_default();
+                    heads.insert(*child, parent);
+                    return;
+                }
+                Event::JoinTrack(child, target) => {
+                    if child != target {
+                        let child = heads.remove(child).unwrap_or_default();
+                        heads.entry(*target).or_default().extend(child);
+                    }
+                    return;
+                }
+                Event::Link(from, to) => {
+                    let from = heads.get(from).cloned().unwrap_or_default();
+                    heads.entry(*to).or_default().extend(from);
+                    return;
+                }
+                Event::StopTrack(track_id) => {
+                    heads.remove(track_id);
+                    return;
+                }
+                _ => return,
+            };
+
+            let tracks = targets
+                .into_iter()
+                .filter(|track_id| alive.contains(track_id))
+                .collect::<Vec<_>>();
+            let node = nodes.len();
+            for track_id in tracks.iter() {
+                for head in heads.insert(*track_id, vec![node]).unwrap_or_default() {
+                    if connected.insert((head, node)) {
+                        edges.push((head, node));
+                    }
+                }
+            }
+            let column = tracks
+                .iter()
+                .filter_map(|track_id| alive.iter().position(|t| t == track_id))
+                .min()
+                .unwrap_or(0);
+            nodes.push(Node {
+                label,
+                link: link.map(str::to_owned),
+                tracks,
+                column,
+            });
+        });
+
+        Self { nodes, edges }
+    }
+}
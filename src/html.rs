@@ -0,0 +1,188 @@
+use std::fmt::Write;
+
+/// The style set by the SGR escape sequences seen so far.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Style {
+    color: Option<(u8, u8, u8)>,
+    bold: bool,
+    dim: bool,
+    reversed: bool,
+    italic: bool,
+    underlined: bool,
+}
+
+impl Style {
+    /// Apply the parameters of an SGR escape sequence, e.g. `38;5;92`.
+    fn apply(&mut self, params: &str) {
+        let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                3 => self.italic = true,
+                23 => self.italic = false,
+                4 => self.underlined = true,
+                24 => self.underlined = false,
+                7 => self.reversed = true,
+                27 => self.reversed = false,
+                39 => self.color = None,
+                38 => match params.next() {
+                    Some(5) => self.color = params.next().map(xterm_to_rgb),
+                    Some(2) => {
+                        let mut next = || params.next().unwrap_or(0);
+                        self.color = Some((next(), next(), next()));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn css(&self) -> String {
+        let mut css = String::new();
+        if let Some((r, g, b)) = self.color {
+            write!(css, "color:#{:02x}{:02x}{:02x};", r, g, b).unwrap();
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.dim {
+            css.push_str("opacity:0.6;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        if self.underlined {
+            css.push_str("text-decoration:underline;");
+        }
+        if self.reversed {
+            css.push_str("filter:invert(100%);");
+        }
+        css
+    }
+}
+
+/// Convert the ANSI colors and styles of `rendered` into
+/// `<span>`s, escaping everything else for HTML.
+pub(crate) fn ansi_to_html(rendered: &str) -> String {
+    let mut html = String::with_capacity(rendered.len());
+    let mut style = Style::default();
+    let mut chars = rendered.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' if chars.peek() == Some(&']') => {
+                chars.next();
+                let mut command = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                    command.push(c);
+                }
+                // Hyperlinks are `8;params;url`, closed by an empty url
+                if let Some(url) = command.strip_prefix("8;").and_then(|c| c.split_once(';')) {
+                    // Keep spans nested within links
+                    if style != Style::default() {
+                        html.push_str("</span>");
+                    }
+                    match url.1 {
+                        "" => html.push_str("</a>"),
+                        url => write!(html, r#"<a href="{}">"#, escape(url)).unwrap(),
+                    }
+                    if style != Style::default() {
+                        write!(html, r#"<span style="{}">"#, style.css()).unwrap();
+                    }
+                }
+            }
+            '\x1b' if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        if c == 'm' {
+                            let previous = style;
+                            style.apply(&params);
+                            if style != previous {
+                                if previous != Style::default() {
+                                    html.push_str("</span>");
+                                }
+                                if style != Style::default() {
+                                    write!(html, r#"<span style="{}">"#, style.css()).unwrap();
+                                }
+                            }
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            c => push_escaped(&mut html, c),
+        }
+    }
+    if style != Style::default() {
+        html.push_str("</span>");
+    }
+
+    html
+}
+
+/// Escape `s` for HTML text and attribute values.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        push_escaped(&mut escaped, c);
+    }
+    escaped
+}
+
+fn push_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        c => html.push(c),
+    }
+}
+
+/// The RGB color of the xterm 256 color `index`.
+pub(crate) fn xterm_to_rgb(index: u8) -> (u8, u8, u8) {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => BASIC[usize::from(index)],
+        16..=231 => {
+            let i = usize::from(index - 16);
+            (LEVELS[i / 36], LEVELS[i / 6 % 6], LEVELS[i % 6])
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
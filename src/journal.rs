@@ -0,0 +1,365 @@
+//! Journals: files events are appended to as they happen, e.g. so
+//! that a pipeline run can still be visualized after a crash.
+//!
+//! ```
+//! use metro::journal::{self, Journal};
+//! use metro::Event;
+//!
+//! let path = std::env::temp_dir().join("metro-journal-example.journal");
+//!
+//! let mut journal = Journal::create(&path).unwrap();
+//! journal.append(&Event::station(0, "Checkout")).unwrap();
+//! drop(journal);
+//!
+//! // e.g. after a restart
+//! let mut journal = Journal::open(&path).unwrap();
+//! journal.append(&Event::station(0, "Build")).unwrap();
+//!
+//! let events = journal::read(&path).unwrap();
+//! assert_eq!(events, [Event::station(0, "Checkout"), Event::station(0, "Build")]);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use crate::binary::{self, VERSION};
+use crate::error::invalid_data;
+use crate::events::{self, Event, RenderingSettings, TrackId};
+use crate::sink::{EventSink, LiveRenderer};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Every journal starts with these bytes, followed by
+/// the version of the binary format of its events.
+const MAGIC: &[u8; 4] = b"MTRJ";
+
+/// When a [`Journal`] waits for its events to be written to disk,
+/// rather than only handed to the operating system.
+///
+/// Events are handed to the operating system as soon as they are
+/// appended, so they survive a crash of the process whatever the
+/// policy, but only synced events survive a crash of the system.
+///
+/// [`Journal`]: struct.Journal.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fsync {
+    /// Only when [synced] explicitly.
+    ///
+    /// [synced]: struct.Journal.html#method.sync
+    Never,
+    /// After every event.
+    Always,
+    /// After every given number of events.
+    Every(usize),
+}
+
+/// A file [`Event`]s are appended to, one at a time, as they happen.
+///
+/// *See the [module documentation](index.html) for an example.*
+///
+/// [`Event`]: ../enum.Event.html
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+    fsync: Fsync,
+    /// The number of events appended since the last sync.
+    unsynced: usize,
+}
+
+impl Journal {
+    /// Create a new, empty journal at `path`, truncating any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION])?;
+        Ok(Self::new(file))
+    }
+
+    /// Open the journal at `path` to append to it, or create
+    /// it if it doesn't exist.
+    ///
+    /// An incomplete last event, e.g. cut short by a crash,
+    /// is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`] if the file is not a
+    /// journal, or was written by another version of the format.
+    ///
+    /// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            file.write_all(MAGIC)?;
+            file.write_all(&[VERSION])?;
+            return Ok(Self::new(file));
+        }
+
+        let version = header(&bytes)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "cannot append to a journal of version {}",
+                version
+            )));
+        }
+        let (_, len) = binary::read_records(&bytes[MAGIC.len() + 1..], version)?;
+        let end = (MAGIC.len() + 1 + len) as u64;
+        file.set_len(end)?;
+        file.seek(SeekFrom::Start(end))?;
+        Ok(Self::new(file))
+    }
+
+    fn new(file: File) -> Self {
+        Self {
+            file,
+            fsync: Fsync::Never,
+            unsynced: 0,
+        }
+    }
+
+    /// Sync events to disk according to `fsync`.
+    ///
+    /// Defaults to [`Fsync::Never`].
+    ///
+    /// [`Fsync::Never`]: enum.Fsync.html#variant.Never
+    pub fn fsync(mut self, fsync: Fsync) -> Self {
+        self.fsync = fsync;
+        self
+    }
+
+    /// Append `event` to the journal.
+    pub fn append(&mut self, event: &Event) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        binary::write_record(&mut bytes, event);
+        // A single write, so that an interrupted
+        // append leaves at most one incomplete event
+        self.file.write_all(&bytes)?;
+
+        self.unsynced += 1;
+        match self.fsync {
+            Fsync::Always => self.sync(),
+            Fsync::Every(n) if self.unsynced >= n => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Append every event of `events` to the journal.
+    pub fn extend<'b, 'a: 'b, I>(&mut self, events: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'b Event<'a>>,
+    {
+        events.into_iter().try_for_each(|event| self.append(event))
+    }
+
+    /// Read and interleave the events of the journals at `paths`, e.g.
+    /// the journals of every process of a distributed job, to render
+    /// them as one graph.
+    ///
+    /// Events are ordered by the [time] of stations, the events
+    /// without one keeping their place in their journal, then by the
+    /// order of `paths`. Track IDs are renumbered so that the tracks
+    /// of each journal are distinct, and the default track `0` of
+    /// every journal but the first is started if it isn't explicitly.
+    ///
+    /// ```
+    /// use metro::journal::Journal;
+    /// use metro::{Event, Station};
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let at = |secs| Station::new(format!("At {}s", secs)).with_time(UNIX_EPOCH + Duration::from_secs(secs));
+    /// let dir = std::env::temp_dir();
+    /// let paths = [dir.join("metro-merge-a.journal"), dir.join("metro-merge-b.journal")];
+    /// Journal::create(&paths[0]).unwrap().extend(&[Event::station(0, at(1)), Event::station(0, at(3))]).unwrap();
+    /// Journal::create(&paths[1]).unwrap().extend(&[Event::station(0, at(2))]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     Journal::merge(&paths).unwrap(),
+    ///     [
+    ///         Event::StartTrack(1.into()),
+    ///         Event::station(0, at(1)),
+    ///         Event::station(1, at(2)),
+    ///         Event::station(0, at(3)),
+    ///     ]
+    /// );
+    /// # paths.iter().for_each(|path| std::fs::remove_file(path).unwrap());
+    /// ```
+    ///
+    /// [time]: ../struct.Station.html#method.with_time
+    pub fn merge<P: AsRef<Path>>(paths: &[P]) -> io::Result<Vec<Event<'static>>> {
+        // Every event, along with the time, journal, and
+        // sequence to order it by
+        let mut merged = Vec::new();
+        let mut offset = 0;
+        for (journal, path) in paths.iter().enumerate() {
+            let mut events = read(path)?;
+            let next_offset = events
+                .iter()
+                .flat_map(|event| {
+                    let mut track_ids = Vec::new();
+                    event.for_each_track_id(|track_id| track_ids.push(track_id));
+                    track_ids
+                })
+                .filter(|track_id| track_id.0 != usize::MAX)
+                .map(|track_id| offset + track_id.0 + 1)
+                .max()
+                .unwrap_or(offset);
+            // The default track of the first journal is still the default track
+            if offset > 0 && starts_implicitly(&events) {
+                events.insert(0, Event::StartTrack(TrackId(0)));
+            }
+
+            let mut time: Option<SystemTime> = None;
+            for (sequence, event) in events.iter().enumerate() {
+                let event = event.map_track_ids(|track_id| match track_id.0 {
+                    usize::MAX => track_id,
+                    id => TrackId(offset + id),
+                });
+                if let Event::Station(_, station) | Event::SharedStation(_, station) = &event {
+                    time = time.max(station.time());
+                }
+                merged.push(((time, journal, sequence), event));
+            }
+            offset = next_offset;
+        }
+
+        merged.sort_by_key(|(key, _)| *key);
+        Ok(merged.into_iter().map(|(_, event)| event).collect())
+    }
+
+    /// Wait for every event appended so far to be written to disk.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+}
+
+/// Read every event of the journal at `path`, e.g. to render
+/// them, ignoring an incomplete last event, e.g. one being
+/// appended, or cut short by a crash.
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if the file is not a
+/// journal, or was written by an unsupported version of the format.
+/// Journals written by older versions are supported.
+///
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<Event<'static>>> {
+    let bytes = fs::read(path)?;
+    let version = header(&bytes)?;
+    let (events, _) = binary::read_records(&bytes[MAGIC.len() + 1..], version)?;
+    Ok(events)
+}
+
+/// How long [`follow`] waits before checking for new events.
+///
+/// [`follow`]: fn.follow.html
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Render the journal at `path` to [`<W: io::Write>`] like `tail -f`,
+/// writing new rows as events are appended to it, e.g. to monitor a
+/// pipeline from another terminal.
+///
+/// Returns once every track is stopped, e.g. when the pipeline is
+/// over, and runs until interrupted otherwise.
+///
+/// ```
+/// use metro::journal::{self, Journal};
+/// use metro::Event;
+/// use std::thread;
+///
+/// let path = std::env::temp_dir().join("metro-follow-example.journal");
+/// let mut journal = Journal::create(&path).unwrap();
+///
+/// let pipeline = thread::spawn(move || {
+///     journal.append(&Event::station(0, "Build")).unwrap();
+///     journal.append(&Event::station(0, "Deploy")).unwrap();
+///     journal.append(&Event::StopTrack(0.into())).unwrap();
+/// });
+///
+/// let mut rendered = Vec::new();
+/// journal::follow(&path, &mut rendered).unwrap();
+/// pipeline.join().unwrap();
+///
+/// assert!(String::from_utf8(rendered).unwrap().contains("Deploy"));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+pub fn follow<P: AsRef<Path>, W: Write>(path: P, w: W) -> io::Result<()> {
+    let rdr = RenderingSettings::default();
+    let mut file = File::open(path)?;
+    // The header, followed by the bytes of the events not read yet
+    let mut bytes = Vec::new();
+    let mut live = LiveRenderer::with_settings(w, rdr.clone());
+    loop {
+        file.read_to_end(&mut bytes)?;
+        if bytes.len() > MAGIC.len() {
+            let version = header(&bytes)?;
+            let start = MAGIC.len() + 1;
+            let (events, len) = binary::read_records(&bytes[start..], version)?;
+            bytes.drain(start..start + len);
+            for event in events {
+                live.emit(event);
+            }
+            if let Some(error) = live.take_error() {
+                return Err(error);
+            }
+
+            let events = live.events();
+            if let Some(last) = events.len().checked_sub(1) {
+                if events::active_tracks_at(events, &rdr, last).is_empty() {
+                    return live.finish().map(drop);
+                }
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Whether `events` use the default track `0` without starting it.
+fn starts_implicitly(events: &[Event]) -> bool {
+    let default = TrackId(0);
+    for event in events {
+        match event {
+            Event::StartTrack(track_id) | Event::SplitTrack(_, track_id)
+                if *track_id == default =>
+            {
+                return false
+            }
+            Event::StartTracks(track_ids) if track_ids.contains(&default) => return false,
+            _ => {
+                let mut uses_default = false;
+                event.for_each_track_id(|track_id| uses_default |= track_id == default);
+                if uses_default {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns the format version of the journal starting with `bytes`.
+fn header(bytes: &[u8]) -> io::Result<u8> {
+    match bytes.get(..MAGIC.len() + 1) {
+        Some([magic @ .., version]) if magic == MAGIC => match *version {
+            0 => Err(invalid_data("unsupported journal version 0")),
+            version if version > VERSION => Err(invalid_data(format!(
+                "unsupported journal version {}",
+                version
+            ))),
+            version => Ok(version),
+        },
+        _ => Err(invalid_data("not a metro journal")),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::events::TrackId;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+/// A key identifying a track, e.g. a branch name or a thread ID.
+///
+/// `TrackKey` is implemented for every `Hash + Eq + Debug + 'static`
+/// type, and keys of different types never compare equal.
+///
+/// *See [`events::Metro::track_id`].*
+///
+/// [`events::Metro::track_id`]: events/struct.Metro.html#method.track_id
+pub trait TrackKey: Any + Debug {
+    fn key_eq(&self, other: &dyn TrackKey) -> bool;
+    fn key_hash(&self, state: &mut dyn Hasher);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<K: Hash + Eq + Debug + 'static> TrackKey for K {
+    fn key_eq(&self, other: &dyn TrackKey) -> bool {
+        other.as_any().downcast_ref::<K>() == Some(self)
+    }
+
+    fn key_hash(&self, mut state: &mut dyn Hasher) {
+        TypeId::of::<K>().hash(&mut state);
+        self.hash(&mut state);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl PartialEq for dyn TrackKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key_eq(other)
+    }
+}
+
+impl Eq for dyn TrackKey {}
+
+impl Hash for dyn TrackKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key_hash(state);
+    }
+}
+
+/// Interns [`TrackKey`]s into [`TrackId`]s.
+#[derive(Debug, Default)]
+pub(crate) struct TrackKeys {
+    ids: HashMap<Box<dyn TrackKey>, TrackId>,
+    /// The lowest track ID not used by any event so far.
+    next_free: usize,
+}
+
+impl TrackKeys {
+    pub(crate) fn id<K: TrackKey>(&mut self, key: K) -> TrackId {
+        let key: Box<dyn TrackKey> = Box::new(key);
+        if let Some(id) = self.ids.get(&key) {
+            return *id;
+        }
+        let id = TrackId(self.next_free);
+        self.next_free += 1;
+        self.ids.insert(key, id);
+        id
+    }
+
+    pub(crate) fn key(&self, id: TrackId) -> Option<&dyn TrackKey> {
+        self.ids
+            .iter()
+            .find(|(_, &i)| i == id)
+            .map(|(key, _)| key.as_ref())
+    }
+
+    pub(crate) fn reserve_ids(&mut self, n: usize) -> Range<TrackId> {
+        let start = self.next_free;
+        self.next_free += n;
+        TrackId(start)..TrackId(self.next_free)
+    }
+
+    /// Make sure `id` is never handed out for a new key.
+    pub(crate) fn reserve(&mut self, id: TrackId) {
+        // Don't let a detached station exhaust all IDs
+        if id.0 != usize::MAX {
+            self.next_free = self.next_free.max(id.0 + 1);
+        }
+    }
+}
@@ -100,26 +100,26 @@
 //!     Event::station(0, "Station 1"),
 //!     Event::station(0, "Station 2"),
 //!     Event::station(0, "Station 3"),
-//!     Event::SplitTrack(0, 1),
+//!     Event::SplitTrack(0.into(), 1.into()),
 //!     Event::station(1, "Station 4"),
-//!     Event::SplitTrack(1, 2),
+//!     Event::SplitTrack(1.into(), 2.into()),
 //!     Event::station(1, "Station 5"),
 //!     Event::station(2, "Station 6"),
 //!     Event::station(0, "Station 7"),
 //!     Event::station(1, "Station 8"),
 //!     Event::station(2, "Station 9"),
-//!     Event::SplitTrack(2, 3),
-//!     Event::SplitTrack(3, 4),
+//!     Event::SplitTrack(2.into(), 3.into()),
+//!     Event::SplitTrack(3.into(), 4.into()),
 //!     Event::station(5, "Station 10 (Detached)"),
-//!     Event::JoinTrack(4, 0),
+//!     Event::JoinTrack(4.into(), 0.into()),
 //!     Event::station(3, "Station 11"),
-//!     Event::StopTrack(1),
+//!     Event::StopTrack(1.into()),
 //!     Event::station(0, "Station 12"),
 //!     Event::station(2, "Station 13"),
 //!     Event::station(3, "Station 14"),
-//!     Event::JoinTrack(3, 0),
+//!     Event::JoinTrack(3.into(), 0.into()),
 //!     Event::station(2, "Station 15"),
-//!     Event::StopTrack(2),
+//!     Event::StopTrack(2.into()),
 //!     Event::station(0, "Station 16"),
 //! ];
 //!
@@ -127,11 +127,81 @@
 //!
 //! println!("{}", string);
 //! ```
-
+//!
+//! # Features
+//!
+//! - `color` *(default)*: color the rendered graph, using [`owo-colors`].
+//!   Without it, rendering is always uncolored.
+//! - `compact_str` *(default)*: avoid allocating while rendering,
+//!   using [`compact_str`].
+//! - `chrono`: every `strftime` pattern of [`chrono`] in
+//!   [`RenderingSettings::time_format`].
+//! - `json`: the adapters of JSON formats, e.g. [`adapters::plan`].
+//! - `toml`: [`RenderingSettings::from_toml`].
+//! - `yaml`: the adapters of YAML formats, e.g. [`adapters::gha`].
+//! - `python`: Python bindings.
+//! - `arbitrary`: generate random [`Event`]s, using [`arbitrary`],
+//!   e.g. for the fuzz target in `fuzz/`.
+//!
+//! [`owo-colors`]: https://docs.rs/owo-colors
+//! [`compact_str`]: https://docs.rs/compact_str
+//! [`arbitrary`]: https://docs.rs/arbitrary
+//! [`Event`]: enum.Event.html
+//! [`RenderingSettings::from_toml`]: struct.RenderingSettings.html#method.from_toml
+//! [`RenderingSettings::time_format`]: struct.RenderingSettings.html#method.time_format
+//! [`chrono`]: https://docs.rs/chrono
+//! [`adapters::gha`]: adapters/gha/index.html
+//! [`adapters::plan`]: adapters/plan/index.html
 #![deny(unsafe_code)]
 #![warn(clippy::all)]
-mod events;
-pub use events::Event;
-pub use events::Metro;
-pub use events::RenderingSettings;
-pub use events::TrackId;
+#![warn(missing_debug_implementations)]
+mod accessible;
+pub mod adapters;
+pub mod analysis;
+mod binary;
+mod channel;
+mod config;
+mod dag;
+mod dot;
+mod error;
+pub mod events;
+mod export;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+mod graph;
+mod html;
+pub mod journal;
+mod keys;
+mod markup;
+mod mermaid;
+mod metro;
+mod minimap;
+#[cfg(feature = "python")]
+mod python;
+pub mod release;
+mod replay;
+mod simplify;
+mod sink;
+mod station;
+mod stats;
+mod styled;
+mod template;
+mod threaded;
+mod threads;
+
+pub use crate::binary::{from_bytes, to_bytes};
+pub use crate::channel::{channel, MetroCollector, MetroSender};
+pub use crate::dot::from_dot;
+pub use crate::events::{strip_ansi, to_string, to_vec, to_writer};
+pub use crate::events::{Event, Heat, RenderingSettings, TimeColumn, TrackId};
+pub use crate::keys::TrackKey;
+pub use crate::mermaid::from_mermaid;
+pub use crate::metro::{Metro, ScopeGuard, StationHandle, Track};
+pub use crate::replay::{replay, to_asciicast};
+pub use crate::simplify::{canonical, dedup, first_parent, graphs_equal, slice_between, summarize};
+pub use crate::sink::{EventSink, LiveRenderer, Throttle};
+pub use crate::station::{Level, Severity, Station, Status};
+pub use crate::stats::Stats;
+pub use crate::styled::StyledText;
+pub use crate::threaded::from_threaded;
+pub use crate::threads::{collect_threads, thread_track, ThreadTrack};
@@ -0,0 +1,98 @@
+/// Translate the inline markup of `line` into ANSI styles, or remove
+/// it if `styled` is `false`: `**bold**`, `_dim_`, and `` `code` ``,
+/// rendered underlined.
+///
+/// Delimiters without a matching closing delimiter are kept as is, and
+/// so are underscores within words or runs of underscores, e.g. in
+/// `snake_case` or `__init__`. Markup is not interpreted within code.
+pub(crate) fn apply(line: &str, styled: bool) -> String {
+    let mut out = String::with_capacity(line.len());
+    let (mut bold, mut dim, mut code) = (false, false, false);
+    let mut previous = None;
+    let mut i = 0;
+
+    while let Some(c) = line[i..].chars().next() {
+        let rest = &line[i + c.len_utf8()..];
+        let next = rest.chars().next();
+        let mut toggled = true;
+
+        if c == '`' && (code || rest.contains('`')) {
+            code = !code;
+            if styled {
+                out.push_str(if code { "\x1b[4m" } else { "\x1b[24m" });
+            }
+        } else if code {
+            toggled = false;
+        } else if line[i..].starts_with("**") && (bold || rest[1..].contains("**")) {
+            bold = !bold;
+            // Skip the second `*`
+            i += 1;
+            if styled {
+                push_intensity(&mut out, bold, dim);
+            }
+        } else if c == '_'
+            && previous != Some('_')
+            && next != Some('_')
+            && if dim {
+                !next.is_some_and(char::is_alphanumeric)
+            } else {
+                !previous.is_some_and(char::is_alphanumeric)
+                    && next.is_some_and(|n| !n.is_whitespace())
+                    && closes_dim(rest)
+            }
+        {
+            dim = !dim;
+            if styled {
+                push_intensity(&mut out, bold, dim);
+            }
+        } else {
+            toggled = false;
+        }
+
+        if !toggled {
+            out.push(c);
+        }
+        previous = Some(c);
+        i += c.len_utf8();
+    }
+
+    if styled {
+        if bold || dim {
+            out.push_str("\x1b[22m");
+        }
+        if code {
+            out.push_str("\x1b[24m");
+        }
+    }
+    out
+}
+
+/// Whether `rest` holds an underscore closing dim text.
+fn closes_dim(rest: &str) -> bool {
+    let mut previous = None;
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        if c == '_'
+            && previous != Some('_')
+            && next != Some('_')
+            && !next.is_some_and(char::is_alphanumeric)
+        {
+            return true;
+        }
+        previous = Some(c);
+    }
+    false
+}
+
+/// Bold and dim are both reset by the same sequence, so
+/// reset them both, then set the remaining one again.
+fn push_intensity(out: &mut String, bold: bool, dim: bool) {
+    out.push_str("\x1b[22m");
+    if bold {
+        out.push_str("\x1b[1m");
+    }
+    if dim {
+        out.push_str("\x1b[2m");
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::dag;
+use crate::error::invalid_line;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Parse a [Mermaid flowchart], e.g. `graph TD`, and lay it out as
+/// events, one station per node, connected by tracks along the links.
+///
+/// Nodes are rendered in topological order, and otherwise in the
+/// order they first appear. A node is rendered as its text, or its
+/// ID, whatever its shape, and gets its ID as [station ID], and the
+/// URL of its `click` statement as [link]. Every kind of link
+/// connects its nodes from left to right, except invisible links,
+/// and link texts are ignored. Links closing a cycle are rendered as
+/// a [reference] back to the earlier node.
+///
+/// Styling statements, e.g. `classDef`, are ignored, and so are
+/// subgraphs and flowchart directions.
+///
+/// ```
+/// let events = metro::from_mermaid("
+///     graph TD
+///         A[Checkout] --> B(Build)
+///         B --> C{Test} & D[[Lint]]
+///         C -->|ok| E[Deploy to prod]
+///         D --> E
+/// ").unwrap();
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// assert!(string.contains("Deploy to prod"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`] if `source` is not a
+/// flowchart, or uses unsupported syntax.
+///
+/// [Mermaid flowchart]: https://mermaid.js.org/syntax/flowchart.html
+/// [station ID]: struct.Station.html#method.with_id
+/// [link]: struct.Station.html#method.with_link
+/// [reference]: enum.Event.html#variant.Reference
+/// [`InvalidData`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html#variant.InvalidData
+pub fn from_mermaid(source: &str) -> io::Result<Vec<Event<'static>>> {
+    let mut flowchart = Flowchart::default();
+    let mut header = false;
+
+    for (i, line) in source.lines().enumerate() {
+        for statement in line.split(';') {
+            let statement = statement.trim();
+            if statement.is_empty() || statement.starts_with("%%") {
+                continue;
+            }
+            let keyword = statement.split_whitespace().next().unwrap_or_default();
+            if !header {
+                if keyword != "graph" && keyword != "flowchart" {
+                    return Err(invalid_line(i + 1, "expected `graph` or `flowchart`"));
+                }
+                header = true;
+                continue;
+            }
+            match keyword {
+                // Styles, subgraphs and directions are ignored
+                "style" | "classDef" | "class" | "linkStyle" | "direction" => continue,
+                "subgraph" | "end" => continue,
+                "click" => flowchart.click(statement),
+                _ => flowchart
+                    .chain(statement)
+                    .map_err(|msg| invalid_line(i + 1, msg))?,
+            }
+        }
+    }
+    if !header {
+        return Err(invalid_line(1, "expected `graph` or `flowchart`"));
+    }
+
+    let stations = flowchart
+        .nodes
+        .into_iter()
+        .map(|Node { id, text, link }| {
+            let text = text.unwrap_or_else(|| id.clone());
+            let mut station = Station::new(text).with_id(id);
+            if let Some(link) = link {
+                station = station.with_link(link);
+            }
+            station
+        })
+        .collect();
+
+    Ok(dag::layout(stations, &flowchart.edges))
+}
+
+#[derive(Default)]
+struct Flowchart {
+    /// The index of every node into `nodes`, by ID.
+    ids: HashMap<String, usize>,
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize)>,
+}
+
+struct Node {
+    id: String,
+    text: Option<String>,
+    link: Option<String>,
+}
+
+impl Flowchart {
+    /// Parse nodes connected by links, e.g. `A --> B & C -.-> D`.
+    fn chain(&mut self, statement: &str) -> Result<(), String> {
+        let mut cursor = Cursor(statement);
+        let mut from = self.nodes_group(&mut cursor)?;
+
+        while !cursor.is_empty() {
+            let visible = cursor.link()?;
+            let to = self.nodes_group(&mut cursor)?;
+            if visible {
+                for &f in from.iter() {
+                    for &t in to.iter() {
+                        self.edges.push((f, t));
+                    }
+                }
+            }
+            from = to;
+        }
+        Ok(())
+    }
+
+    /// Parse nodes joined by `&`, e.g. `A & B[Text]`.
+    fn nodes_group(&mut self, cursor: &mut Cursor) -> Result<Vec<usize>, String> {
+        let mut nodes = vec![self.node(cursor)?];
+        while cursor.eat("&") {
+            nodes.push(self.node(cursor)?);
+        }
+        Ok(nodes)
+    }
+
+    /// Parse a node, with its shape and text if any, e.g. `A((Text))`.
+    fn node(&mut self, cursor: &mut Cursor) -> Result<usize, String> {
+        let id = cursor.id().ok_or("expected a node")?;
+        let text = cursor.shape()?;
+        // Classes, e.g. `A:::important`
+        if cursor.0.starts_with(":::") {
+            cursor.0 = &cursor.0[3..];
+            cursor.id();
+        }
+
+        let nodes = &mut self.nodes;
+        let index = *self.ids.entry(id.to_owned()).or_insert_with(|| {
+            nodes.push(Node {
+                id: id.to_owned(),
+                text: None,
+                link: None,
+            });
+            nodes.len() - 1
+        });
+        if text.is_some() {
+            self.nodes[index].text = text;
+        }
+        Ok(index)
+    }
+
+    /// Parse a `click` statement linking a node to a URL, e.g.
+    /// `click A href "https://example.com"`, ignoring callbacks.
+    fn click(&mut self, statement: &str) {
+        let mut words = statement.split_whitespace().skip(1);
+        let (Some(id), Some(mut url)) = (words.next(), words.next()) else {
+            return;
+        };
+        if url == "href" {
+            url = words.next().unwrap_or_default();
+        }
+        let url = match url.strip_prefix('"') {
+            Some(url) => url.split('"').next().unwrap_or_default(),
+            None => return,
+        };
+        if let Some(&index) = self.ids.get(id) {
+            self.nodes[index].link = Some(url.to_owned());
+        }
+    }
+}
+
+/// The rest of a statement being parsed.
+struct Cursor<'a>(&'a str);
+
+impl<'a> Cursor<'a> {
+    fn is_empty(&mut self) -> bool {
+        self.0 = self.0.trim_start();
+        self.0.is_empty()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        self.0 = self.0.trim_start();
+        match self.0.strip_prefix(s) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> &'a str {
+        let end = self.0.find(|c| !f(c)).unwrap_or(self.0.len());
+        let (taken, rest) = self.0.split_at(end);
+        self.0 = rest;
+        taken
+    }
+
+    fn id(&mut self) -> Option<&'a str> {
+        self.0 = self.0.trim_start();
+        let id = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        Some(id).filter(|id| !id.is_empty())
+    }
+
+    /// Parse the shape of a node, returning its text, e.g. `Text`
+    /// for `[Text]`, `((Text))`, `[/"Text"/]` or `>Text]`.
+    fn shape(&mut self) -> Result<Option<String>, String> {
+        let opening = self.take_while(|c| "[({>".contains(c));
+        if opening.is_empty() {
+            return Ok(None);
+        }
+        // Second delimiters, e.g. in `[/Text/]` or `[\Text\]`
+        let slanted = self.take_while(|c| c == '/' || c == '\\');
+
+        let text = match self.0.strip_prefix('"') {
+            Some(rest) => {
+                let end = rest.find('"').ok_or("unterminated string")?;
+                self.0 = &rest[end + 1..];
+                &rest[..end]
+            }
+            None => {
+                let text = self.take_while(|c| !"])}".contains(c));
+                match slanted.is_empty() {
+                    true => text,
+                    false => text.trim_end_matches(['/', '\\']),
+                }
+            }
+        };
+        self.take_while(|c| c == '/' || c == '\\');
+        if self.take_while(|c| "])}".contains(c)).is_empty() {
+            return Err(format!("unterminated node shape `{}`", opening));
+        }
+
+        let text = text
+            .trim()
+            .replace("<br>", "\n")
+            .replace("<br/>", "\n")
+            .replace("<br />", "\n");
+        Ok(Some(text))
+    }
+
+    /// Parse a link, with its text if any, e.g. `-->`, `-.->|Text|`,
+    /// `== Text ==>` or `~~~`, returning whether it is visible.
+    fn link(&mut self) -> Result<bool, String> {
+        self.0 = self.0.trim_start();
+        if !self.take_while(|c| c == '~').is_empty() {
+            return Ok(false);
+        }
+
+        let mut op = self.take_while(|c| "<-=.".contains(c)).to_owned();
+        if op.is_empty() {
+            return Err("expected a link".to_owned());
+        }
+        if self.0.starts_with('>')
+            || (self.0.starts_with(['o', 'x']) && self.0[1..].starts_with(char::is_whitespace))
+        {
+            op.push_str(&self.0[..1]);
+            self.0 = &self.0[1..];
+        }
+
+        // Texts within links, e.g. `-- Text -->`, are
+        // opened by two characters and closed by a link
+        if matches!(op.as_str(), "--" | "==" | "-." | "<--" | "<==" | "<-.") {
+            let end = ["-->", "---", "==>", "===", ".->", ".-"]
+                .iter()
+                .filter_map(|close| self.0.find(close))
+                .min();
+            match end {
+                Some(end) => {
+                    self.0 = &self.0[end..];
+                    self.take_while(|c| "-=.".contains(c));
+                    if self.0.starts_with(['>', 'o', 'x']) {
+                        self.0 = &self.0[1..];
+                    }
+                }
+                // Arrowheads right before a node, e.g. `A--oB`
+                None if self.0.starts_with(['o', 'x']) => self.0 = &self.0[1..],
+                None => return Err("unterminated link text".to_owned()),
+            }
+        }
+
+        if self.eat("|") {
+            let end = self.0.find('|').ok_or("unterminated link text")?;
+            self.0 = &self.0[end + 1..];
+        }
+        Ok(true)
+    }
+}
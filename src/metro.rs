@@ -0,0 +1,1302 @@
+use crate::accessible;
+use crate::events::{self, Event, RenderingSettings, TrackId};
+use crate::export;
+use crate::minimap;
+use crate::station::{Level, Severity, Station, Status};
+use crate::stats::{self, Stats};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// The track ID used for stations not tied to any track.
+const DETACHED: TrackId = TrackId(usize::MAX);
+
+#[derive(Clone, Debug)]
+struct MetroState<'a> {
+    events: Vec<Event<'a>>,
+    next_track_id: usize,
+    pinned: Option<TrackId>,
+    weights: Vec<(TrackId, usize)>,
+    lanes: Vec<(TrackId, usize)>,
+    ghosts: Vec<TrackId>,
+    labels: Vec<(TrackId, String)>,
+    spacings: Vec<(TrackId, usize)>,
+    abort_reasons: Vec<(TrackId, String)>,
+    inherit_style: bool,
+    max_events: Option<usize>,
+    /// The number of events standing for the evicted ones.
+    prelude_len: usize,
+    /// The number of events evicted so far.
+    evicted: usize,
+    /// The tracks with a [`Track`] handle.
+    ///
+    /// [`Track`]: struct.Track.html
+    handles: Vec<TrackId>,
+}
+
+impl<'a> MetroState<'a> {
+    fn next_track_id(&mut self) -> TrackId {
+        let id = TrackId(self.next_track_id);
+        self.next_track_id += 1;
+        id
+    }
+
+    fn label_of(&self, track_id: TrackId) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(t, _)| *t == track_id)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// Give `child` the weight, lanes, ghostliness and spacing of `parent`.
+    fn inherit_style(&mut self, parent: TrackId, child: TrackId) {
+        let inherit = |styles: &mut Vec<(TrackId, usize)>| {
+            if let Some(&(_, style)) = styles.iter().find(|(t, _)| *t == parent) {
+                styles.push((child, style));
+            }
+        };
+        inherit(&mut self.weights);
+        inherit(&mut self.lanes);
+        inherit(&mut self.spacings);
+        if self.ghosts.contains(&parent) {
+            self.ghosts.push(child);
+        }
+    }
+
+    fn adapt_settings(&self, rdr: RenderingSettings) -> RenderingSettings {
+        let mut rdr = rdr.implicit_root(false);
+        if self.inherit_style {
+            rdr.set_inherit_colors(true);
+        }
+        if let Some(pinned) = self.pinned {
+            if rdr.get_pin_left().is_none() {
+                rdr.set_pin_left(Some(pinned));
+            }
+        }
+        for &(track_id, weight) in self.weights.iter() {
+            if !rdr.has_track_weight(track_id) {
+                rdr.set_track_weight(track_id, weight);
+            }
+        }
+        for &(track_id, lanes) in self.lanes.iter() {
+            if !rdr.has_track_lanes(track_id) {
+                rdr.set_track_lanes(track_id, lanes);
+            }
+        }
+        for &track_id in self.ghosts.iter() {
+            rdr.set_ghost_track(track_id, true);
+        }
+        for (track_id, label) in self.labels.iter() {
+            if rdr.get_track_label(*track_id).is_none() {
+                rdr.set_track_label(*track_id, label.as_str());
+            }
+        }
+        for &(track_id, spacing) in self.spacings.iter() {
+            if !rdr.has_track_spacing(track_id) {
+                rdr.set_track_spacing(track_id, spacing);
+            }
+        }
+        for (track_id, reason) in self.abort_reasons.iter() {
+            if rdr.get_abort_reason(*track_id).is_none() {
+                rdr.set_abort_reason(*track_id, reason.as_str());
+            }
+        }
+        rdr
+    }
+
+    /// Whether `track_id` is alive after the recorded events.
+    fn is_alive(&self, track_id: TrackId) -> bool {
+        let Some(last) = self.events.len().checked_sub(1) else {
+            return false;
+        };
+        let rdr = self.adapt_settings(RenderingSettings::default());
+        events::active_tracks_at(&self.events, &rdr, last).contains(&track_id)
+    }
+
+    /// Record `event`, evicting the oldest events beyond `max_events`.
+    fn push(&mut self, event: Event<'a>) {
+        self.events.push(event);
+        let Some(max_events) = self.max_events else {
+            return;
+        };
+        let excess = (self.events.len() - self.prelude_len).saturating_sub(max_events);
+        if excess == 0 {
+            return;
+        }
+
+        let cut = self.prelude_len + excess;
+        let rdr = self.adapt_settings(RenderingSettings::default());
+        let prelude = events::state_prelude(&self.events[..cut], &rdr);
+        self.prelude_len = prelude.len();
+        self.evicted += excess;
+        self.events.splice(..cut, prelude);
+    }
+
+    /// The number of events recorded so far, evicted or not.
+    fn recorded(&self) -> usize {
+        self.evicted + self.events.len() - self.prelude_len
+    }
+
+    /// Returns the station recorded as the `number`th event, unless evicted.
+    fn station_mut(&mut self, number: usize) -> Option<&mut Station<'a>> {
+        let index = number.checked_sub(self.evicted)? + self.prelude_len;
+        match self.events.get_mut(index)? {
+            Event::Station(_, station) => Some(station),
+            _ => None,
+        }
+    }
+}
+
+/// `Metro` records the [`Event`]s produced by its [`Track`]s,
+/// and renders them afterwards.
+///
+/// *See the [crate-level example](index.html#example-using-metro).*
+///
+/// [`Event`]: enum.Event.html
+/// [`Track`]: struct.Track.html
+#[derive(Debug)]
+pub struct Metro<'a> {
+    state: Rc<RefCell<MetroState<'a>>>,
+}
+
+/// Cloning a `Metro` copies its events, rather than sharing them:
+/// the clone is independent of the original and of its [`Track`]s.
+/// Use [`Metro::resume_track`] to continue the tracks of the clone.
+///
+/// ```
+/// let mut metro = metro::Metro::new();
+/// let mut track = metro.new_track();
+/// track.add_station("Common");
+///
+/// let mut fork = metro.clone();
+/// track.add_station("Original only");
+/// fork.resume_track(track.id()).unwrap().add_station("Fork only");
+///
+/// assert_ne!(metro.to_events(), fork.to_events());
+/// ```
+///
+/// [`Track`]: struct.Track.html
+/// [`Metro::resume_track`]: struct.Metro.html#method.resume_track
+impl<'a> Clone for Metro<'a> {
+    fn clone(&self) -> Self {
+        let mut state = self.state.borrow().clone();
+        // The handles of the original don't belong to the clone
+        state.handles.clear();
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+}
+
+impl<'a> Default for Metro<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Metro<'a> {
+    /// Create a new, empty `Metro`.
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(MetroState {
+                events: Vec::new(),
+                next_track_id: 0,
+                pinned: None,
+                weights: Vec::new(),
+                lanes: Vec::new(),
+                ghosts: Vec::new(),
+                labels: Vec::new(),
+                spacings: Vec::new(),
+                abort_reasons: Vec::new(),
+                inherit_style: false,
+                max_events: None,
+                prelude_len: 0,
+                evicted: 0,
+                handles: Vec::new(),
+            })),
+        }
+    }
+
+    /// Create a new, empty `Metro` keeping only the most recent
+    /// `max_events` events, e.g. to keep a diagram of a long-running
+    /// service in memory.
+    ///
+    /// The evicted events are replaced by a single `StartTracks` event
+    /// of the tracks still alive, so that rendering still works.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::Metro::with_max_events(2);
+    /// let mut main = metro.new_track();
+    /// for i in 0..100 {
+    ///     main.add_station(format!("Request {}", i));
+    /// }
+    ///
+    /// assert_eq!(
+    ///     metro.to_events(),
+    ///     [
+    ///         Event::StartTracks(vec![main.id()].into()),
+    ///         Event::station(main.id(), "Request 98"),
+    ///         Event::station(main.id(), "Request 99"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn with_max_events(max_events: usize) -> Self {
+        let metro = Self::new();
+        metro.state.borrow_mut().max_events = Some(max_events);
+        metro
+    }
+
+    /// Create a new [`Track`], added rightmost, though left of any
+    /// [ghost track](struct.Track.html#method.set_ghost).
+    ///
+    /// [`Track`]: struct.Track.html
+    pub fn new_track(&mut self) -> Track<'a> {
+        let id = self.state.borrow_mut().next_track_id();
+        self.push(Event::StartTrack(id));
+        Track::new(id, &self.state)
+    }
+
+    /// Returns a new handle to the track `id`, if it is still alive
+    /// and has no handle yet, e.g. to continue the tracks of a [clone]
+    /// of a `Metro`.
+    ///
+    /// The new handle stops the track when dropped, so there
+    /// is never more than one handle per track.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let track = metro.new_track();
+    /// let id = track.id();
+    ///
+    /// let mut fork = metro.clone();
+    /// assert!(metro.resume_track(id).is_none());
+    /// track.stop();
+    ///
+    /// assert!(metro.resume_track(id).is_none());
+    /// let resumed = fork.resume_track(id);
+    /// assert!(resumed.is_some());
+    /// assert!(fork.resume_track(id).is_none());
+    /// ```
+    ///
+    /// [clone]: struct.Metro.html#impl-Clone-for-Metro%3C'a%3E
+    pub fn resume_track(&mut self, id: TrackId) -> Option<Track<'a>> {
+        let resumable = {
+            let state = self.state.borrow();
+            state.is_alive(id) && !state.handles.contains(&id)
+        };
+        resumable.then(|| Track::new(id, &self.state))
+    }
+
+    /// Make tracks split from another one inherit its style: its
+    /// weight, lanes, ghostliness, spacing and color, and, for
+    /// [named splits], its label as a prefix of theirs.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// metro.set_inherit_style(true);
+    /// let mut main = metro.new_track();
+    /// main.set_label("main");
+    /// main.set_weight(2);
+    /// let feature = main.split_named("feature");
+    /// let id = feature.id();
+    /// drop((main, feature));
+    ///
+    /// let events = metro::events::Metro::from(metro);
+    /// assert_eq!(events.settings().get_track_label(id), Some("main/feature"));
+    /// assert_eq!(events.settings().get_track_weight(id), 2);
+    /// ```
+    ///
+    /// *See [`RenderingSettings::inherit_colors`].*
+    ///
+    /// [named splits]: struct.Track.html#method.split_named
+    /// [`RenderingSettings::inherit_colors`]: struct.RenderingSettings.html#method.inherit_colors
+    pub fn set_inherit_style(&mut self, inherit_style: bool) {
+        self.state.borrow_mut().inherit_style = inherit_style;
+    }
+
+    /// Add a station that is not tied to any track.
+    pub fn add_station<S: Into<Station<'a>>>(&mut self, station: S) {
+        self.push(Event::Station(DETACHED, station.into()));
+    }
+
+    /// Add a station that is not tied to any track, at the verbosity `level`.
+    ///
+    /// *See [`Station::with_level`].*
+    ///
+    /// [`Station::with_level`]: struct.Station.html#method.with_level
+    pub fn add_station_at_level<S: Into<Station<'a>>>(&mut self, level: Level, station: S) {
+        self.add_station(station.into().with_level(level));
+    }
+
+    /// Add a station that is not tied to any track, tagged with every tag of `tags`.
+    ///
+    /// *See [`Station::with_tag`].*
+    ///
+    /// [`Station::with_tag`]: struct.Station.html#method.with_tag
+    pub fn add_station_tagged<S: Into<Station<'a>>>(&mut self, station: S, tags: &[&'a str]) {
+        let station = tags
+            .iter()
+            .fold(station.into(), |station, tag| station.with_tag(*tag));
+        self.add_station(station);
+    }
+
+    /// Add a station that is not tied to any track, colored by `severity`.
+    ///
+    /// *See [`Station::with_severity`].*
+    ///
+    /// [`Station::with_severity`]: struct.Station.html#method.with_severity
+    pub fn add_station_with_severity<S: Into<Station<'a>>>(
+        &mut self,
+        station: S,
+        severity: Severity,
+    ) {
+        self.add_station(station.into().with_severity(severity));
+    }
+
+    /// Add a station that is not tied to any track, with the numeric `badge`.
+    ///
+    /// *See [`Station::with_badge`].*
+    ///
+    /// [`Station::with_badge`]: struct.Station.html#method.with_badge
+    pub fn add_station_badged<S: Into<Station<'a>>>(&mut self, station: S, badge: usize) {
+        self.add_station(station.into().with_badge(badge));
+    }
+
+    /// Add a station that is not tied to any track, whose text is
+    /// `template` with its placeholders resolved from `fields`.
+    ///
+    /// *See [`Station::with_field`].*
+    ///
+    /// [`Station::with_field`]: struct.Station.html#method.with_field
+    pub fn add_station_fmt<S, I, K, V>(&mut self, template: S, fields: I)
+    where
+        S: Into<Cow<'a, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.add_station(Station::new(template).with_fields(fields));
+    }
+
+    /// Returns a copy of the [`Event`]s recorded so far.
+    ///
+    /// [`Event`]: enum.Event.html
+    pub fn to_events(&self) -> Vec<Event<'a>> {
+        self.state.borrow().events.clone()
+    }
+
+    /// Returns the minimal events starting every track alive so far,
+    /// from left to right, e.g. to resume rendering a truncated stream
+    /// of the events. Track labels are kept by the settings, e.g. when
+    /// [converted] into an [`events::Metro`].
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// let feature = main.split();
+    /// main.add_station("Release");
+    ///
+    /// assert_eq!(
+    ///     metro.state_prelude(),
+    ///     [Event::StartTracks(vec![main.id(), feature.id()].into())]
+    /// );
+    /// ```
+    ///
+    /// [converted]: struct.Metro.html#impl-From%3CMetro%3C'a%3E%3E-for-Metro%3C'a%3E
+    /// [`events::Metro`]: events/struct.Metro.html
+    pub fn state_prelude(&self) -> Vec<Event<'a>> {
+        events::state_prelude(&self.state.borrow().events, &self.settings())
+    }
+
+    fn push(&self, event: Event<'a>) {
+        self.state.borrow_mut().push(event);
+    }
+
+    fn settings(&self) -> RenderingSettings {
+        self.adapt_settings(RenderingSettings::default())
+    }
+
+    /// Every track is started explicitly, so
+    /// there is no need for a default track.
+    fn adapt_settings(&self, rdr: RenderingSettings) -> RenderingSettings {
+        self.state.borrow().adapt_settings(rdr)
+    }
+
+    /// Returns statistics about the shape of the graph.
+    ///
+    /// *See [`events::Metro::stats`].*
+    ///
+    /// [`events::Metro::stats`]: events/struct.Metro.html#method.stats
+    pub fn stats(&self) -> Stats {
+        stats::stats(&self.state.borrow().events, &self.settings())
+    }
+
+    /// Returns the `(width, height)` in characters that rendering
+    /// with `rdr` would produce, without rendering anything.
+    ///
+    /// *See [`events::Metro::measure`].*
+    ///
+    /// [`events::Metro::measure`]: events/struct.Metro.html#method.measure
+    pub fn measure(&self, rdr: &RenderingSettings) -> (usize, usize) {
+        events::measure(&self.state.borrow().events, rdr)
+    }
+
+    /// Returns a compact overview of the graph, of at most `height`
+    /// lines of `width` characters.
+    ///
+    /// *See [`events::Metro::minimap`].*
+    ///
+    /// [`events::Metro::minimap`]: events/struct.Metro.html#method.minimap
+    pub fn minimap(&self, width: usize, height: usize) -> String {
+        minimap::minimap(&self.state.borrow().events, &self.settings(), width, height)
+    }
+
+    /// Render only the rows within `range` of the full rendering.
+    ///
+    /// *See [`events::Metro::render_range`].*
+    ///
+    /// [`events::Metro::render_range`]: events/struct.Metro.html#method.render_range
+    pub fn render_range(&self, range: Range<usize>) -> io::Result<String> {
+        let mut vec = Vec::new();
+        events::render_range(
+            &mut vec,
+            &self.state.borrow().events,
+            &self.settings(),
+            range,
+        )?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Render the layout of the graph as newline-delimited JSON,
+    /// one object per row.
+    ///
+    /// *See [`events::Metro::to_structured`].*
+    ///
+    /// [`events::Metro::to_structured`]: events/struct.Metro.html#method.to_structured
+    pub fn to_structured(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        events::render_structured(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Render, highlighting every occurrence of `pattern`, and return
+    /// the rendering along with the index of every matching row.
+    ///
+    /// *See [`events::Metro::render_highlighting`].*
+    ///
+    /// [`events::Metro::render_highlighting`]: events/struct.Metro.html#method.render_highlighting
+    pub fn render_highlighting(&self, pattern: &str) -> io::Result<(String, Vec<usize>)> {
+        let mut vec = Vec::new();
+        let rows = events::render_highlighting(
+            &mut vec,
+            &self.state.borrow().events,
+            &self.settings(),
+            pattern,
+        )?;
+        Ok((events::rendered_to_string(vec), rows))
+    }
+
+    /// Returns the span of events `(start, end)` during which
+    /// `track_id` is alive.
+    ///
+    /// *See [`events::Metro::track_span`].*
+    ///
+    /// [`events::Metro::track_span`]: events/struct.Metro.html#method.track_span
+    pub fn track_span(&self, track_id: TrackId) -> Option<(usize, usize)> {
+        events::track_span(&self.state.borrow().events, &self.settings(), track_id)
+    }
+
+    /// Returns the tracks alive after the event at `index`,
+    /// from left to right.
+    ///
+    /// *See [`events::Metro::active_tracks_at`].*
+    ///
+    /// [`events::Metro::active_tracks_at`]: events/struct.Metro.html#method.active_tracks_at
+    pub fn active_tracks_at(&self, index: usize) -> Vec<TrackId> {
+        events::active_tracks_at(&self.state.borrow().events, &self.settings(), index)
+    }
+
+    /// Returns the tracks still alive after the recorded events, from
+    /// left to right, i.e. those neither stopped nor joined yet.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// let worker = main.split();
+    /// assert_eq!(metro.open_tracks(), [main.id(), worker.id()]);
+    ///
+    /// worker.join(&main);
+    /// assert_eq!(metro.open_tracks(), [main.id()]);
+    /// ```
+    pub fn open_tracks(&self) -> Vec<TrackId> {
+        let state = self.state.borrow();
+        let last = state.events.len().saturating_sub(1);
+        let mut open = events::active_tracks_at(&state.events, &self.settings(), last);
+        open.retain(|&track_id| track_id != DETACHED);
+        open
+    }
+
+    /// Panics if any track is still [open], e.g. to check that every
+    /// track was stopped or joined before rendering.
+    ///
+    /// ```should_panic
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// let worker = main.split();
+    /// main.stop();
+    ///
+    /// // Panics: track 1 is still open
+    /// metro.assert_all_closed();
+    /// # drop(worker);
+    /// ```
+    ///
+    /// [open]: #method.open_tracks
+    #[track_caller]
+    pub fn assert_all_closed(&self) {
+        let open = self.open_tracks();
+        if !open.is_empty() {
+            let open = open
+                .iter()
+                .map(|track_id| match self.state.borrow().label_of(*track_id) {
+                    Some(label) => format!("{} ({})", track_id, label),
+                    None => track_id.to_string(),
+                })
+                .collect::<Vec<_>>();
+            panic!("tracks still open: {}", open.join(", "));
+        }
+    }
+
+    /// Render the recorded [`Event`]s to [`<W: io::Write>`].
+    ///
+    /// *See also [`Metro::to_string`] and [`Metro::to_vec`].*
+    ///
+    /// [`Metro::to_vec`]: struct.Metro.html#method.to_vec
+    /// [`Metro::to_string`]: struct.Metro.html#method.to_string
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+    pub fn to_writer<W: Write>(&self, mut w: W) -> io::Result<()> {
+        events::render(&mut w, &self.state.borrow().events, &self.settings())
+    }
+
+    /// Render the recorded [`Event`]s to [`Vec<u8>`].
+    ///
+    /// *See also [`Metro::to_string`] and [`Metro::to_writer`].*
+    ///
+    /// [`Metro::to_writer`]: struct.Metro.html#method.to_writer
+    /// [`Metro::to_string`]: struct.Metro.html#method.to_string
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`Vec<u8>`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.to_writer(&mut vec)?;
+        Ok(vec)
+    }
+
+    /// Render the recorded [`Event`]s to [`String`].
+    ///
+    /// *See also [`Metro::to_vec`] and [`Metro::to_writer`].*
+    ///
+    /// [`Metro::to_writer`]: struct.Metro.html#method.to_writer
+    /// [`Metro::to_vec`]: struct.Metro.html#method.to_vec
+    ///
+    /// [`Event`]: enum.Event.html
+    ///
+    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+    pub fn to_string(&self) -> io::Result<String> {
+        Ok(events::rendered_to_string(self.to_vec()?))
+    }
+
+    /// Describe the recorded [`Event`]s as text, e.g. for screen readers.
+    ///
+    /// *See [`events::Metro::to_accessible_text`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_accessible_text`]: events/struct.Metro.html#method.to_accessible_text
+    pub fn to_accessible_text(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        accessible::to_accessible_text(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Export the recorded [`Event`]s as a D2 diagram.
+    ///
+    /// *See [`events::Metro::to_d2`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_d2`]: events/struct.Metro.html#method.to_d2
+    pub fn to_d2(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_d2(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Export the recorded [`Event`]s as an Excalidraw scene.
+    ///
+    /// *See [`events::Metro::to_excalidraw`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_excalidraw`]: events/struct.Metro.html#method.to_excalidraw
+    pub fn to_excalidraw(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_excalidraw(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Export the recorded [`Event`]s as a GraphML document.
+    ///
+    /// *See [`events::Metro::to_graphml`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_graphml`]: events/struct.Metro.html#method.to_graphml
+    pub fn to_graphml(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_graphml(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Export the recorded [`Event`]s in the JSON Graph Format.
+    ///
+    /// *See [`events::Metro::to_jgf`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_jgf`]: events/struct.Metro.html#method.to_jgf
+    pub fn to_jgf(&self) -> io::Result<String> {
+        let mut vec = Vec::new();
+        export::to_jgf(&mut vec, &self.state.borrow().events, &self.settings())?;
+        Ok(events::rendered_to_string(vec))
+    }
+
+    /// Render the recorded [`Event`]s as a fenced code block, followed
+    /// by the colored rendering as HTML if `colored` is `true`.
+    ///
+    /// *See [`events::Metro::to_markdown`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_markdown`]: events/struct.Metro.html#method.to_markdown
+    pub fn to_markdown(&self, colored: bool) -> io::Result<String> {
+        let colored = if colored {
+            Some(self.to_string()?)
+        } else {
+            None
+        };
+        Ok(events::to_markdown(
+            &self.render_plain()?,
+            colored.as_deref(),
+        ))
+    }
+
+    /// Render the recorded [`Event`]s to every sink of `sinks`,
+    /// each styled with its own settings, laying them out only once.
+    ///
+    /// *See [`events::Metro::to_writers`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`events::Metro::to_writers`]: events/struct.Metro.html#method.to_writers
+    pub fn to_writers(&self, sinks: &mut [(&mut dyn Write, &RenderingSettings)]) -> io::Result<()> {
+        events::render_to_sinks(sinks, &self.state.borrow().events, &self.settings())
+    }
+
+    /// Render the recorded [`Event`]s to [`String`] without
+    /// any ANSI escape sequence.
+    ///
+    /// *See [`events::Metro::render_plain`].*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+    /// [`events::Metro::render_plain`]: events/struct.Metro.html#method.render_plain
+    pub fn render_plain(&self) -> io::Result<String> {
+        self.render_with(&RenderingSettings::default().color(false))
+            .map(|rendered| events::strip_ansi(&rendered))
+    }
+
+    /// Render the recorded [`Event`]s to [`String`] with `rdr`,
+    /// rather than the default [`RenderingSettings`].
+    ///
+    /// The style set on tracks, e.g. by [`Track::set_spacing`], applies
+    /// unless `rdr` sets its own for the same track, even to the default.
+    ///
+    /// ```
+    /// use metro::RenderingSettings;
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_station("Station 1");
+    ///
+    /// let rdr = RenderingSettings::default().color(false);
+    /// let string = metro.render_with(&rdr).unwrap();
+    /// assert_eq!(string.lines().next(), Some("╪        Station 1"));
+    ///
+    /// track.set_spacing(8);
+    /// assert_ne!(metro.render_with(&rdr).unwrap(), string);
+    /// let rdr = rdr.track_spacing(track.id(), 5);
+    /// assert_eq!(metro.render_with(&rdr).unwrap(), string);
+    /// ```
+    ///
+    /// *See [`events::Metro`] for more control over rendering.*
+    ///
+    /// [`Event`]: enum.Event.html
+    /// [`String`]: https://doc.rust-lang.org/stable/std/string/struct.String.html
+    /// [`RenderingSettings`]: struct.RenderingSettings.html
+    /// [`events::Metro`]: events/struct.Metro.html
+    /// [`Track::set_spacing`]: struct.Track.html#method.set_spacing
+    pub fn render_with(&self, rdr: &RenderingSettings) -> io::Result<String> {
+        let mut vec = Vec::new();
+        events::render(
+            &mut vec,
+            &self.state.borrow().events,
+            &self.adapt_settings(rdr.clone()),
+        )?;
+        Ok(events::rendered_to_string(vec))
+    }
+}
+
+/// Convert a `Metro` into an [`events::Metro`] holding its events,
+/// e.g. to configure its rendering further.
+///
+/// ```
+/// use metro::RenderingSettings;
+///
+/// let mut metro = metro::Metro::new();
+/// let mut track = metro.new_track();
+/// track.add_station("Station 1");
+/// drop(track);
+///
+/// let mut events = metro::events::Metro::from(metro);
+/// events.settings_mut().set_color(false);
+/// assert_eq!(events.to_string().unwrap().lines().next(), Some("╪        Station 1"));
+/// ```
+///
+/// [`events::Metro`]: events/struct.Metro.html
+impl<'a> From<Metro<'a>> for events::Metro<'a> {
+    fn from(metro: Metro<'a>) -> Self {
+        let mut converted = events::Metro::from(metro.to_events());
+        *converted.settings_mut() = metro.settings();
+        converted
+    }
+}
+
+/// A `Track` is a single rail of a [`Metro`].
+///
+/// Dropping a `Track` stops it, unless it was
+/// already stopped or joined, or joins it if
+/// [configured so].
+///
+/// [configured so]: struct.Track.html#method.join_on_drop
+/// [`Metro`]: struct.Metro.html
+#[derive(Debug)]
+pub struct Track<'a> {
+    id: TrackId,
+    state: Rc<RefCell<MetroState<'a>>>,
+    done: bool,
+    /// The track to join when dropped, instead of stopping.
+    join_on_drop: Option<TrackId>,
+}
+
+impl<'a> Track<'a> {
+    /// Returns the [`TrackId`] of this track.
+    ///
+    /// [`TrackId`]: struct.TrackId.html
+    pub fn id(&self) -> TrackId {
+        self.id
+    }
+
+    /// Add a station to this track.
+    pub fn add_station<S: Into<Station<'a>>>(&mut self, station: S) {
+        self.push(Event::Station(self.id, station.into()));
+    }
+
+    /// Add a station to this track, returning a handle to update
+    /// it until rendered, e.g. from pending to done.
+    ///
+    /// ```
+    /// use metro::Status;
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// let deploy = track.add_station_handle("Deploying");
+    /// deploy.set_status(Status::Running);
+    /// track.add_station("Notify");
+    /// deploy.update("Deployed");
+    /// deploy.set_status(Status::Done);
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.contains("✓ Deployed"));
+    /// ```
+    pub fn add_station_handle<S: Into<Station<'a>>>(&mut self, station: S) -> StationHandle<'a> {
+        let number = self.state.borrow().recorded();
+        self.add_station(station);
+        StationHandle {
+            state: Rc::clone(&self.state),
+            number,
+        }
+    }
+
+    /// Add a station to this track, at the verbosity `level`.
+    ///
+    /// *See [`Station::with_level`].*
+    ///
+    /// [`Station::with_level`]: struct.Station.html#method.with_level
+    pub fn add_station_at_level<S: Into<Station<'a>>>(&mut self, level: Level, station: S) {
+        self.add_station(station.into().with_level(level));
+    }
+
+    /// Add a station to this track, tagged with every tag of `tags`.
+    ///
+    /// *See [`Station::with_tag`].*
+    ///
+    /// [`Station::with_tag`]: struct.Station.html#method.with_tag
+    pub fn add_station_tagged<S: Into<Station<'a>>>(&mut self, station: S, tags: &[&'a str]) {
+        let station = tags
+            .iter()
+            .fold(station.into(), |station, tag| station.with_tag(*tag));
+        self.add_station(station);
+    }
+
+    /// Add a station to this track rendered as a box titled `title`,
+    /// bordering every line of `lines`, e.g. a command output.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_block("cargo test", ["running 2 tests", "test result: ok"]);
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.contains("│ test result: ok │"));
+    /// ```
+    ///
+    /// *See [`Station::block`].*
+    ///
+    /// [`Station::block`]: struct.Station.html#method.block
+    pub fn add_block<T, I, S>(&mut self, title: T, lines: I)
+    where
+        T: AsRef<str>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.add_station(Station::block(title, lines));
+    }
+
+    /// Add a station to this track embedding `metro`, rendered below
+    /// the text of the station, e.g. for a pipeline of pipelines.
+    ///
+    /// ```
+    /// let mut build = metro::Metro::new();
+    /// let mut steps = build.new_track();
+    /// steps.add_station("Compile");
+    /// steps.add_station("Link");
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_metro("Build", &build);
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.contains("Build\n│          ╪        Compile"));
+    /// ```
+    ///
+    /// *See [`Station::with_nested`].*
+    ///
+    /// [`Station::with_nested`]: struct.Station.html#method.with_nested
+    pub fn add_metro<S: Into<Station<'a>>>(&mut self, station: S, metro: &Metro<'a>) {
+        self.add_station(station.into().with_nested(metro.to_events()));
+    }
+
+    /// Add a station to this track, colored by `severity`, e.g. a log line.
+    ///
+    /// ```
+    /// use metro::Severity;
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_station_with_severity("Connection lost", Severity::Error);
+    /// ```
+    ///
+    /// *See [`Station::with_severity`].*
+    ///
+    /// [`Station::with_severity`]: struct.Station.html#method.with_severity
+    pub fn add_station_with_severity<S: Into<Station<'a>>>(
+        &mut self,
+        station: S,
+        severity: Severity,
+    ) {
+        self.add_station(station.into().with_severity(severity));
+    }
+
+    /// Add a station to this track, with the numeric `badge`, e.g.
+    /// a number of failed tests.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_station_badged("Tests failed", 3);
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.contains("Tests failed (3)"));
+    /// ```
+    ///
+    /// *See [`Station::with_badge`].*
+    ///
+    /// [`Station::with_badge`]: struct.Station.html#method.with_badge
+    pub fn add_station_badged<S: Into<Station<'a>>>(&mut self, station: S, badge: usize) {
+        self.add_station(station.into().with_badge(badge));
+    }
+
+    /// Add a station to this track, whose text is `template`
+    /// with its placeholders resolved from `fields`.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_station_fmt("{hash:.7} {msg}", [("hash", "65d8cb6e1f"), ("msg", "Add bulk insertion")]);
+    /// ```
+    ///
+    /// *See [`Station::with_field`].*
+    ///
+    /// [`Station::with_field`]: struct.Station.html#method.with_field
+    pub fn add_station_fmt<S, I, K, V>(&mut self, template: S, fields: I)
+    where
+        S: Into<Cow<'a, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.add_station(Station::new(template).with_fields(fields));
+    }
+
+    /// Create a new [`Track`] diverging from this track to the right.
+    ///
+    /// [`Track`]: struct.Track.html
+    pub fn split(&mut self) -> Track<'a> {
+        let id = {
+            let mut state = self.state.borrow_mut();
+            let id = state.next_track_id();
+            if state.inherit_style {
+                state.inherit_style(self.id, id);
+            }
+            id
+        };
+        self.push(Event::SplitTrack(self.id, id));
+        Track::new(id, &self.state)
+    }
+
+    /// Create a new [`Track`] diverging from this track to the right,
+    /// labelled `label`, and starting with a `Forked from` station
+    /// naming this track.
+    ///
+    /// If the [style is inherited], the label of this track, if any,
+    /// prefixes `label`.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// main.set_label("main");
+    /// let mut feature = main.split_named("feature");
+    /// feature.add_station("Implement");
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.contains("Forked from main"));
+    /// ```
+    ///
+    /// [`Track`]: struct.Track.html
+    /// [style is inherited]: struct.Metro.html#method.set_inherit_style
+    pub fn split_named<S: Into<String>>(&mut self, label: S) -> Track<'a> {
+        let (parent, label) = {
+            let state = self.state.borrow();
+            let parent = state.label_of(self.id).map(str::to_owned);
+            let label = match (&parent, state.inherit_style) {
+                (Some(parent), true) => format!("{}/{}", parent, label.into()),
+                _ => label.into(),
+            };
+            (parent.unwrap_or_else(|| self.id.to_string()), label)
+        };
+        let mut child = self.split();
+        child.set_label(label);
+        child.add_station(format!("Forked from {}", parent));
+        child
+    }
+
+    /// Keep this track in the leftmost column, like a mainline
+    /// or release branch, whatever the tracks split and joined
+    /// around it. Only one track can be pinned at a time.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// let mut release = main.split();
+    /// release.pin_left();
+    /// release.add_station("v1.0");
+    ///
+    /// assert_eq!(metro.active_tracks_at(1), [release.id(), main.id()]);
+    /// ```
+    pub fn pin_left(&self) {
+        self.state.borrow_mut().pinned = Some(self.id);
+    }
+
+    /// Weigh this track by `weight`, rendering it double-struck if
+    /// heavier than `1`.
+    ///
+    /// *See [`RenderingSettings::track_weight`].*
+    ///
+    /// [`RenderingSettings::track_weight`]: struct.RenderingSettings.html#method.track_weight
+    pub fn set_weight(&self, weight: usize) {
+        let mut state = self.state.borrow_mut();
+        state.weights.retain(|&(t, _)| t != self.id);
+        state.weights.push((self.id, weight));
+    }
+
+    /// Render this track as a bundle of `lanes` adjacent rails.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// let mut workers = main.split();
+    /// workers.set_lanes(2);
+    /// workers.add_station("Process shards");
+    /// workers.join(&main);
+    /// ```
+    ///
+    /// *See [`RenderingSettings::track_lanes`].*
+    ///
+    /// [`RenderingSettings::track_lanes`]: struct.RenderingSettings.html#method.track_lanes
+    pub fn set_lanes(&self, lanes: usize) {
+        let mut state = self.state.borrow_mut();
+        state.lanes.retain(|&(t, _)| t != self.id);
+        state.lanes.push((self.id, lanes));
+    }
+
+    /// Render this track as a ghost track, dotted and dimmed, e.g.
+    /// an upstream branch shown for context. Tracks created
+    /// afterwards are started left of it.
+    ///
+    /// *See [`RenderingSettings::ghost_track`].*
+    ///
+    /// [`RenderingSettings::ghost_track`]: struct.RenderingSettings.html#method.ghost_track
+    pub fn set_ghost(&self, ghost: bool) {
+        let mut state = self.state.borrow_mut();
+        state.ghosts.retain(|&t| t != self.id);
+        if ghost {
+            state.ghosts.push(self.id);
+        }
+    }
+
+    /// Label this track with `label` in headers, e.g. a branch name.
+    ///
+    /// *See [`RenderingSettings::header_every`].*
+    ///
+    /// [`RenderingSettings::header_every`]: struct.RenderingSettings.html#method.header_every
+    pub fn set_label<S: Into<String>>(&self, label: S) {
+        let mut state = self.state.borrow_mut();
+        state.labels.retain(|(t, _)| *t != self.id);
+        state.labels.push((self.id, label.into()));
+    }
+
+    /// Space out the rails right of this track by `spacing` characters,
+    /// e.g. to make the mainline stand out.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// main.set_spacing(8);
+    /// main.add_station("Release");
+    /// ```
+    ///
+    /// *See [`RenderingSettings::track_spacing`].*
+    ///
+    /// [`RenderingSettings::track_spacing`]: struct.RenderingSettings.html#method.track_spacing
+    pub fn set_spacing(&self, spacing: usize) {
+        let mut state = self.state.borrow_mut();
+        state.spacings.retain(|&(t, _)| t != self.id);
+        state.spacings.push((self.id, spacing));
+    }
+
+    /// Join this track into `to_track`.
+    pub fn join(mut self, to_track: &Track<'a>) {
+        self.done = true;
+        self.push(Event::JoinTrack(self.id, to_track.id));
+    }
+
+    /// Add a `Begin label` station to this track, and an `End label`
+    /// station along with the elapsed time when the returned guard is
+    /// dropped, e.g. at the end of a function.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// {
+    ///     let _scope = track.scope("Build");
+    ///     track.add_station("Compile");
+    /// }
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.find("Begin Build").unwrap() < string.find("Compile").unwrap());
+    /// assert!(string.find("Compile").unwrap() < string.find("End Build (").unwrap());
+    ///
+    /// // No `End` station is added once the track is stopped
+    /// let scope = track.scope("Deploy");
+    /// track.stop();
+    /// drop(scope);
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.contains("Begin Deploy") && !string.contains("End Deploy"));
+    /// ```
+    pub fn scope<S: Into<Cow<'a, str>>>(&mut self, label: S) -> ScopeGuard<'a> {
+        let label = label.into();
+        self.add_station(format!("Begin {}", label));
+        ScopeGuard {
+            track_id: self.id,
+            state: Rc::clone(&self.state),
+            label,
+            start: Instant::now(),
+        }
+    }
+
+    /// Join this track into `parent` when it is dropped, instead of
+    /// stopping it, e.g. to join a forked task on every exit path.
+    ///
+    /// ```
+    /// use metro::Event;
+    ///
+    /// let mut metro = metro::Metro::new();
+    /// let mut main = metro.new_track();
+    /// {
+    ///     let mut task = main.split();
+    ///     task.join_on_drop(&main);
+    ///     task.add_station("Task");
+    /// }
+    ///
+    /// let events = metro.to_events();
+    /// assert_eq!(events.last(), Some(&Event::JoinTrack(1.into(), 0.into())));
+    /// ```
+    pub fn join_on_drop(&mut self, parent: &Track<'a>) {
+        self.join_on_drop = Some(parent.id);
+    }
+
+    /// Stop this track because of `reason`, e.g. a failure, rendering
+    /// its end as aborted, with `reason` next to it.
+    ///
+    /// ```
+    /// let mut metro = metro::Metro::new();
+    /// let mut track = metro.new_track();
+    /// track.add_station("Deploy");
+    /// track.abort("Timed out");
+    ///
+    /// let string = metro::strip_ansi(&metro.to_string().unwrap());
+    /// assert!(string.ends_with("✗        Timed out\n"));
+    /// ```
+    ///
+    /// *See [`RenderingSettings::abort_reason`].*
+    ///
+    /// [`RenderingSettings::abort_reason`]: struct.RenderingSettings.html#method.abort_reason
+    pub fn abort<S: Into<String>>(mut self, reason: S) {
+        self.done = true;
+        self.state
+            .borrow_mut()
+            .abort_reasons
+            .push((self.id, reason.into()));
+        self.push(Event::StopTrack(self.id));
+    }
+
+    /// Stop this track.
+    pub fn stop(mut self) {
+        self.done = true;
+        self.push(Event::StopTrack(self.id));
+    }
+
+    /// Create the handle of `id`, the only one.
+    fn new(id: TrackId, state: &Rc<RefCell<MetroState<'a>>>) -> Self {
+        state.borrow_mut().handles.push(id);
+        Self {
+            id,
+            state: Rc::clone(state),
+            done: false,
+            join_on_drop: None,
+        }
+    }
+
+    fn push(&self, event: Event<'a>) {
+        self.state.borrow_mut().push(event);
+    }
+}
+
+impl<'a> Drop for Track<'a> {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.state.borrow_mut().handles.retain(|&t| t != id);
+        if self.done {
+            return;
+        }
+        match self.join_on_drop {
+            Some(parent) => self.push(Event::JoinTrack(self.id, parent)),
+            None => self.push(Event::StopTrack(self.id)),
+        }
+    }
+}
+
+/// Adds the `End` station of a [`Track::scope`] when dropped,
+/// if the track is still alive.
+///
+/// [`Track::scope`]: struct.Track.html#method.scope
+#[derive(Debug)]
+pub struct ScopeGuard<'a> {
+    track_id: TrackId,
+    state: Rc<RefCell<MetroState<'a>>>,
+    label: Cow<'a, str>,
+    start: Instant,
+}
+
+impl<'a> Drop for ScopeGuard<'a> {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        if !state.is_alive(self.track_id) {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        let text = format!("End {} ({:.3}s)", self.label, elapsed.as_secs_f64());
+        let station = Station::new(text).with_duration(elapsed);
+        state.push(Event::Station(self.track_id, station));
+    }
+}
+
+/// A handle to a station added by [`Track::add_station_handle`],
+/// to update it until rendered.
+///
+/// Updates of stations [evicted] already are ignored.
+///
+/// [`Track::add_station_handle`]: struct.Track.html#method.add_station_handle
+/// [evicted]: struct.Metro.html#method.with_max_events
+#[derive(Debug)]
+pub struct StationHandle<'a> {
+    state: Rc<RefCell<MetroState<'a>>>,
+    /// The number of events recorded before the station.
+    number: usize,
+}
+
+impl<'a> StationHandle<'a> {
+    /// Replace the text of the station.
+    pub fn update<S: Into<Cow<'a, str>>>(&self, text: S) {
+        if let Some(station) = self.state.borrow_mut().station_mut(self.number) {
+            station.set_text(text.into());
+        }
+    }
+
+    /// Set the status of the station.
+    ///
+    /// *See [`Station::with_status`].*
+    ///
+    /// [`Station::with_status`]: struct.Station.html#method.with_status
+    pub fn set_status(&self, status: Status) {
+        if let Some(station) = self.state.borrow_mut().station_mut(self.number) {
+            station.set_status(status);
+        }
+    }
+}
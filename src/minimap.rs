@@ -0,0 +1,44 @@
+use crate::events::{layout, Event, RenderingSettings};
+
+/// The shades of the minimap, from the sparsest to the densest.
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+/// *See [`Metro::minimap`](struct.Metro.html#method.minimap).*
+pub(crate) fn minimap(
+    events: &[Event],
+    rdr: &RenderingSettings,
+    width: usize,
+    height: usize,
+) -> String {
+    let rows = layout(events, rdr);
+    if rows.is_empty() || width == 0 || height == 0 {
+        return String::new();
+    }
+
+    // The number of tracks, and the share of rows starting a
+    // station, among the rows summarized by each line
+    let lines = rows
+        .chunks(rows.len().div_ceil(height))
+        .map(|chunk| {
+            let tracks = chunk.iter().map(|row| row.rails.len()).max();
+            let stations = chunk.iter().filter(|row| row.starts_station).count();
+            (tracks.unwrap_or(0), stations as f64 / chunk.len() as f64)
+        })
+        .collect::<Vec<_>>();
+    let max_tracks = lines.iter().map(|&(tracks, _)| tracks).max().unwrap_or(0);
+    let max_density = lines.iter().map(|&(_, density)| density).fold(0., f64::max);
+
+    let mut minimap = String::with_capacity(lines.len() * (width + 1));
+    for (tracks, density) in lines {
+        let length = (tracks * width).div_ceil(max_tracks.max(1));
+        let shade = if max_density > 0. {
+            SHADES[(density / max_density * (SHADES.len() - 1) as f64).ceil() as usize]
+        } else {
+            SHADES[0]
+        };
+        minimap.extend(std::iter::repeat_n(shade, length));
+        minimap.extend(std::iter::repeat_n(' ', width - length));
+        minimap.push('\n');
+    }
+    minimap
+}
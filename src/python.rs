@@ -0,0 +1,213 @@
+//! Python bindings, enabled by the `python` feature.
+//!
+//! ```python
+//! import metro
+//!
+//! m = metro.Metro()
+//! track1 = m.new_track()
+//! track1.add_station("Station 1")
+//! track2 = track1.split()
+//! track2.add_station("Station 2")
+//! track2.join(track1)
+//! print(m.to_string(splat=3, color=False))
+//! ```
+//!
+//! Events can be rendered directly too:
+//!
+//! ```python
+//! import metro
+//! from metro import Event
+//!
+//! events = [
+//!     Event.station(0, "Station 1"),
+//!     Event.split_track(0, 1),
+//!     Event.station(1, "Station 2"),
+//!     Event.join_track(1, 0),
+//! ]
+//! print(metro.to_string(events, color=False))
+//! ```
+
+use crate::events::{self, RenderingSettings};
+use crate::{Event, Metro, Track};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+#[pyclass(name = "Metro", unsendable)]
+struct PyMetro {
+    metro: Metro<'static>,
+}
+
+#[pymethods]
+impl PyMetro {
+    #[new]
+    fn new() -> Self {
+        Self {
+            metro: Metro::new(),
+        }
+    }
+
+    fn new_track(&mut self) -> PyTrack {
+        PyTrack {
+            track: Some(self.metro.new_track()),
+        }
+    }
+
+    fn add_station(&mut self, text: String) {
+        self.metro.add_station(text);
+    }
+
+    #[pyo3(signature = (splat = None, color = None))]
+    fn to_string(&self, splat: Option<usize>, color: Option<bool>) -> PyResult<String> {
+        Ok(self.metro.render_with(&settings(splat, color))?)
+    }
+
+    fn __str__(&self) -> PyResult<String> {
+        self.to_string(None, None)
+    }
+}
+
+/// As in Rust, a track is stopped when it is garbage collected,
+/// unless it was explicitly stopped or joined before.
+#[pyclass(name = "Track", unsendable)]
+struct PyTrack {
+    track: Option<Track<'static>>,
+}
+
+impl PyTrack {
+    fn track(&mut self) -> PyResult<&mut Track<'static>> {
+        self.track
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("track was already stopped or joined"))
+    }
+
+    fn take(&mut self) -> PyResult<Track<'static>> {
+        self.track
+            .take()
+            .ok_or_else(|| PyValueError::new_err("track was already stopped or joined"))
+    }
+}
+
+#[pymethods]
+impl PyTrack {
+    #[getter]
+    fn id(&mut self) -> PyResult<usize> {
+        Ok(self.track()?.id().into())
+    }
+
+    fn add_station(&mut self, text: String) -> PyResult<()> {
+        self.track()?.add_station(text);
+        Ok(())
+    }
+
+    fn split(&mut self) -> PyResult<PyTrack> {
+        Ok(PyTrack {
+            track: Some(self.track()?.split()),
+        })
+    }
+
+    fn join(&mut self, mut to_track: PyRefMut<'_, PyTrack>) -> PyResult<()> {
+        let to_track = to_track.track()?;
+        self.take()?.join(to_track);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> PyResult<()> {
+        self.take()?.stop();
+        Ok(())
+    }
+}
+
+#[pyclass(name = "Event", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyEvent {
+    event: Event<'static>,
+}
+
+#[pymethods]
+impl PyEvent {
+    #[staticmethod]
+    fn start_track(track_id: usize) -> Self {
+        Event::StartTrack(track_id.into()).into()
+    }
+
+    #[staticmethod]
+    fn start_tracks(track_ids: Vec<usize>) -> Self {
+        let track_ids = track_ids.into_iter().map(Into::into).collect::<Vec<_>>();
+        Event::StartTracks(track_ids.into()).into()
+    }
+
+    #[staticmethod]
+    fn stop_track(track_id: usize) -> Self {
+        Event::StopTrack(track_id.into()).into()
+    }
+
+    #[staticmethod]
+    fn station(track_id: usize, text: String) -> Self {
+        Event::station(track_id, text).into()
+    }
+
+    #[staticmethod]
+    fn shared_station(track_ids: Vec<usize>, text: String) -> Self {
+        let track_ids = track_ids.into_iter().map(Into::into).collect::<Vec<_>>();
+        Event::SharedStation(track_ids.into(), text.into()).into()
+    }
+
+    #[staticmethod]
+    fn split_track(from_track_id: usize, new_track_id: usize) -> Self {
+        Event::SplitTrack(from_track_id.into(), new_track_id.into()).into()
+    }
+
+    #[staticmethod]
+    fn join_track(from_track_id: usize, to_track_id: usize) -> Self {
+        Event::JoinTrack(from_track_id.into(), to_track_id.into()).into()
+    }
+
+    #[staticmethod]
+    fn link(from_track_id: usize, to_track_id: usize) -> Self {
+        Event::Link(from_track_id.into(), to_track_id.into()).into()
+    }
+
+    #[staticmethod]
+    fn no_event() -> Self {
+        Event::NoEvent.into()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.event)
+    }
+}
+
+impl From<Event<'static>> for PyEvent {
+    fn from(event: Event<'static>) -> Self {
+        Self { event }
+    }
+}
+
+/// Renders `events`, defining a default track with ID `0`.
+#[pyfunction]
+#[pyo3(signature = (events, splat = None, color = None))]
+fn to_string(events: Vec<PyEvent>, splat: Option<usize>, color: Option<bool>) -> PyResult<String> {
+    let mut metro = events::Metro::with_settings(settings(splat, color));
+    metro.extend(events.into_iter().map(|event| event.event));
+    Ok(metro.to_string()?)
+}
+
+fn settings(splat: Option<usize>, color: Option<bool>) -> RenderingSettings {
+    let mut rdr = RenderingSettings::default();
+    if let Some(splat) = splat {
+        rdr = rdr.splat(splat);
+    }
+    if let Some(color) = color {
+        rdr = rdr.color(color);
+    }
+    rdr
+}
+
+#[pymodule]
+fn metro(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMetro>()?;
+    m.add_class::<PyTrack>()?;
+    m.add_class::<PyEvent>()?;
+    m.add_function(wrap_pyfunction!(to_string, m)?)?;
+    Ok(())
+}
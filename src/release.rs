@@ -0,0 +1,196 @@
+//! Release trains: a mainline, and the maintenance branches of
+//! its releases, receiving backports and cherry-picks.
+//!
+//! ```
+//! use metro::release::Train;
+//!
+//! let events = Train::new("main")
+//!     .release("main", "v1.0")
+//!     .branch("main", "1.x")
+//!     .change("main", "Add plugins")
+//!     .change("main", "Fix crash on exit")
+//!     .backport("1.x", "Fix crash on exit")
+//!     .release("1.x", "v1.0.1")
+//!     .release("main", "v2.0")
+//!     .end_of_life("1.x")
+//!     .into_events();
+//!
+//! let string = metro::strip_ansi(&metro::to_string(&events).unwrap());
+//! assert!(string.contains("Fix crash on exit (backport)"));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::events::{Event, TrackId};
+use crate::station::Station;
+use crate::styled::StyledText;
+
+/// A release train, declared in chronological order.
+///
+/// Every branch is a track, started by [`new`] for the mainline and
+/// by [`branch`] for maintenance branches. Branches referenced before
+/// being started are started as needed, by an [`Event::StartTrack`]
+/// unconnected to any other branch.
+///
+/// *See the [module documentation](index.html) for an example.*
+///
+/// [`new`]: #method.new
+/// [`branch`]: #method.branch
+/// [`Event::StartTrack`]: ../enum.Event.html#variant.StartTrack
+#[derive(Clone, Debug)]
+pub struct Train<'a> {
+    events: Vec<Event<'a>>,
+    mainline: TrackId,
+    /// The track of every live branch, by name.
+    branches: Vec<(Cow<'a, str>, TrackId)>,
+    next_id: usize,
+}
+
+impl<'a> Train<'a> {
+    /// Create a new `Train`, with the `mainline` branch.
+    pub fn new<S: Into<Cow<'a, str>>>(mainline: S) -> Self {
+        let mut train = Self {
+            events: Vec::new(),
+            mainline: TrackId(0),
+            branches: Vec::new(),
+            next_id: 0,
+        };
+        train.mainline = train.track(mainline.into());
+        train
+    }
+
+    /// Release `version` from `branch`, rendered in bold, and with
+    /// `version` as [station ID], e.g. for [`Event::Reference`]s.
+    ///
+    /// [station ID]: ../struct.Station.html#method.with_id
+    /// [`Event::Reference`]: ../enum.Event.html#variant.Reference
+    pub fn release<B, S>(mut self, branch: B, version: S) -> Self
+    where
+        B: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        let track_id = self.track(branch.into());
+        let version = version.into();
+        let text = StyledText::new().push(version.as_ref()).bold();
+        self.events.push(Event::Station(
+            track_id,
+            Station::from(text).with_id(version),
+        ));
+        self
+    }
+
+    /// Add a change to `branch`, e.g. a merged pull request.
+    pub fn change<B, S>(mut self, branch: B, description: S) -> Self
+    where
+        B: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        let track_id = self.track(branch.into());
+        self.events
+            .push(Event::Station(track_id, Station::new(description)));
+        self
+    }
+
+    /// Start the maintenance branch `name` from the tip of `from`,
+    /// e.g. right after a release.
+    pub fn branch<B, S>(mut self, from: B, name: S) -> Self
+    where
+        B: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        let parent = self.track(from.into());
+        let name = name.into();
+        let track_id = self.new_track(name.clone());
+        self.events.push(Event::SplitTrack(parent, track_id));
+        let text = StyledText::new().push(format!("Branch {}", name)).dim();
+        self.events.push(Event::Station(track_id, text.into()));
+        self
+    }
+
+    /// Port a change of the mainline to `branch`, linking them.
+    ///
+    /// Ignored once the mainline reached its [end of life], as it has
+    /// no changes left to port.
+    ///
+    /// ```
+    /// use metro::release::Train;
+    ///
+    /// let train = Train::new("main").branch("main", "1.x").end_of_life("main");
+    /// assert_eq!(
+    ///     train.clone().backport("1.x", "Fix crash").into_events(),
+    ///     train.into_events(),
+    /// );
+    /// ```
+    ///
+    /// [end of life]: #method.end_of_life
+    pub fn backport<B, S>(mut self, branch: B, description: S) -> Self
+    where
+        B: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        if !self.branches.iter().any(|&(_, t)| t == self.mainline) {
+            return self;
+        }
+        let track_id = self.track(branch.into());
+        self.events.push(Event::Link(self.mainline, track_id));
+        let text = format!("{} (backport)", description.into());
+        self.events.push(Event::Station(track_id, text.into()));
+        self
+    }
+
+    /// Pick a change of `from` onto `to`, linking them.
+    pub fn cherry_pick<B, T, S>(mut self, from: B, to: T, description: S) -> Self
+    where
+        B: Into<Cow<'a, str>>,
+        T: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        let from = from.into();
+        let source = self.track(from.clone());
+        let track_id = self.track(to.into());
+        self.events.push(Event::Link(source, track_id));
+        let text = format!("{} (cherry-picked from {})", description.into(), from);
+        self.events.push(Event::Station(track_id, text.into()));
+        self
+    }
+
+    /// End the maintenance of `branch`, stopping its track.
+    pub fn end_of_life<B: Into<Cow<'a, str>>>(mut self, branch: B) -> Self {
+        let branch = branch.into();
+        let track_id = self.track(branch.clone());
+        let text = StyledText::new()
+            .push(format!("{} end of life", branch))
+            .dim();
+        self.events.push(Event::Station(track_id, text.into()));
+        self.events.push(Event::StopTrack(track_id));
+        self.branches.retain(|&(_, t)| t != track_id);
+        self
+    }
+
+    /// Returns the events of this train, e.g. to render
+    /// them with [`to_string`].
+    ///
+    /// [`to_string`]: ../fn.to_string.html
+    pub fn into_events(self) -> Vec<Event<'a>> {
+        self.events
+    }
+
+    /// Returns the track of `branch`, starting it if needed.
+    fn track(&mut self, branch: Cow<'a, str>) -> TrackId {
+        match self.branches.iter().find(|(name, _)| *name == branch) {
+            Some(&(_, track_id)) => track_id,
+            None => {
+                let track_id = self.new_track(branch);
+                self.events.push(Event::StartTrack(track_id));
+                track_id
+            }
+        }
+    }
+
+    fn new_track(&mut self, branch: Cow<'a, str>) -> TrackId {
+        let track_id = TrackId(self.next_id);
+        self.next_id += 1;
+        self.branches.push((branch, track_id));
+        track_id
+    }
+}
@@ -0,0 +1,61 @@
+use crate::events::{self, visible_width, write_json_escaped, Event};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+/// Render `&[`[`Event`]`]` to [`<W: io::Write>`] row by row,
+/// flushing and then sleeping for `delay` after each row.
+///
+/// This is useful for producing animated demos
+/// of graphs evolving over time.
+///
+/// *See also [`to_asciicast`].*
+///
+/// [`Event`]: enum.Event.html
+/// [`to_asciicast`]: fn.to_asciicast.html
+///
+/// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+pub fn replay<W: Write>(events: &[Event], delay: Duration, mut w: W) -> io::Result<()> {
+    let rendered = events::to_string(events)?;
+    for line in rendered.lines() {
+        writeln!(w, "{}", line)?;
+        w.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+/// Write `&[`[`Event`]`]` as an [asciicast v2] recording to
+/// [`<W: io::Write>`], where each row appears `delay` after
+/// the previous one.
+///
+/// The recording can be played back with `asciinema play`.
+///
+/// *See also [`replay`].*
+///
+/// [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+///
+/// [`Event`]: enum.Event.html
+/// [`replay`]: fn.replay.html
+///
+/// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+pub fn to_asciicast<W: Write>(events: &[Event], delay: Duration, mut w: W) -> io::Result<()> {
+    let rendered = events::to_string(events)?;
+    let width = rendered.lines().map(visible_width).max().unwrap_or(0);
+    let height = rendered.lines().count();
+
+    writeln!(
+        w,
+        r#"{{"version": 2, "width": {}, "height": {}}}"#,
+        width.max(1),
+        height.max(1)
+    )?;
+    for (i, line) in rendered.lines().enumerate() {
+        let time = delay.as_secs_f64() * i as f64;
+        write!(w, "[{:.6}, \"o\", \"", time)?;
+        write_json_escaped(&mut w, line)?;
+        writeln!(w, "\\r\\n\"]")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,581 @@
+use crate::events::{active_tracks_at, Event, RenderingSettings, TrackId};
+use std::collections::HashMap;
+
+/// The track of stations not tied to any track.
+const DETACHED: TrackId = TrackId(usize::MAX);
+
+/// Simplify `&[`[`Event`]`]` to its `mainline` track, like
+/// `git log --first-parent`.
+///
+/// Every other track is hidden. Instead, each time a track joins
+/// the mainline, a single station summarizing it is added to the
+/// mainline, counting the stations of the branch it is part of,
+/// i.e. of the tracks split from it and from the tracks split from
+/// them, and so on.
+///
+/// Stations not tied to any track, i.e. on the track `usize::MAX`,
+/// and references, are kept.
+///
+/// ```
+/// use metro::Event;
+///
+/// let events = [
+///     Event::station(0, "Start"),
+///     Event::SplitTrack(0.into(), 1.into()),
+///     Event::station(1, "Work"),
+///     Event::SplitTrack(1.into(), 2.into()),
+///     Event::station(2, "More work"),
+///     Event::JoinTrack(2.into(), 1.into()),
+///     Event::JoinTrack(1.into(), 0.into()),
+///     Event::station(0, "Release"),
+/// ];
+///
+/// let simplified = metro::first_parent(&events, 0.into());
+/// let texts = simplified
+///     .iter()
+///     .filter_map(|event| match event {
+///         Event::Station(_, station) => Some(station.text()),
+///         _ => None,
+///     })
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(texts, ["Start", "Merged track 1 (2 stations)", "Release"]);
+/// ```
+///
+/// Stations of the default track are hidden too, when it isn't the mainline:
+///
+/// ```
+/// use metro::Event;
+///
+/// let events = [
+///     Event::station(0, "a"),
+///     Event::SplitTrack(0.into(), 1.into()),
+///     Event::station(1, "b"),
+///     Event::station(0, "c"),
+///     Event::JoinTrack(1.into(), 0.into()),
+/// ];
+///
+/// let simplified = metro::first_parent(&events, 1.into());
+/// assert_eq!(
+///     simplified,
+///     [
+///         Event::StartTrack(1.into()),
+///         Event::station(1, "b"),
+///         Event::StopTrack(1.into()),
+///     ]
+/// );
+/// ```
+///
+/// [`Event`]: enum.Event.html
+pub fn first_parent<'a>(events: &[Event<'a>], mainline: TrackId) -> Vec<Event<'a>> {
+    // The track each hidden track was split from
+    // the mainline as, directly or not
+    let mut branches: HashMap<TrackId, TrackId> = HashMap::new();
+    // The number of stations of each branch not summarized yet
+    let mut stations: HashMap<TrackId, usize> = HashMap::new();
+    let mut simplified = Vec::new();
+
+    for event in events {
+        match event {
+            Event::StartTrack(track_id) => {
+                if *track_id == mainline {
+                    simplified.push(event.clone());
+                } else {
+                    branches.entry(*track_id).or_insert(*track_id);
+                }
+            }
+            Event::StartTracks(track_ids) => {
+                if track_ids.contains(&mainline) {
+                    simplified.push(Event::StartTrack(mainline));
+                }
+                for track_id in track_ids.iter().filter(|t| **t != mainline) {
+                    branches.entry(*track_id).or_insert(*track_id);
+                }
+            }
+            Event::StopTrack(track_id) => {
+                if *track_id == mainline {
+                    simplified.push(event.clone());
+                }
+            }
+            Event::SharedStation(track_ids, station) => {
+                if track_ids.contains(&mainline) {
+                    simplified.push(Event::Station(mainline, station.clone()));
+                } else if let Some(branch) = track_ids.iter().find_map(|t| branches.get(t)) {
+                    *stations.entry(*branch).or_default() += 1;
+                } else if track_ids.iter().all(|t| *t == DETACHED) {
+                    simplified.push(event.clone());
+                }
+            }
+            Event::Station(track_id, _) | Event::StationKv(track_id, _) => {
+                if *track_id == mainline || *track_id == DETACHED {
+                    simplified.push(event.clone());
+                } else if let Some(branch) = branches.get(track_id) {
+                    *stations.entry(*branch).or_default() += 1;
+                }
+            }
+            Event::SplitTrack(parent, child) => {
+                if *child == mainline {
+                    simplified.push(Event::StartTrack(mainline));
+                } else {
+                    let branch = branches.get(parent).copied().unwrap_or(*child);
+                    branches.insert(*child, branch);
+                }
+            }
+            Event::JoinTrack(child, target) => {
+                if *child == mainline {
+                    simplified.push(Event::StopTrack(mainline));
+                } else if *target == mainline {
+                    let count = branches
+                        .get(child)
+                        .and_then(|branch| stations.remove(branch))
+                        .unwrap_or(0);
+                    let text = format!(
+                        "Merged track {} ({} station{})",
+                        child.0,
+                        count,
+                        if count == 1 { "" } else { "s" }
+                    );
+                    simplified.push(Event::Station(mainline, text.into()));
+                }
+            }
+            Event::NoEvent
+            | Event::Reference(..)
+            | Event::UpdateStation(..)
+            | Event::SetStatus(..) => simplified.push(event.clone()),
+            // Links always involve another, hidden, track
+            Event::Link(..) => {}
+        }
+    }
+
+    simplified
+}
+
+/// Shorten `&[`[`Event`]`]` to about `max_rows` rows, e.g. to preview
+/// a huge history at a glance.
+///
+/// Every split, join, and other structural event is kept, along with
+/// as many stations as fit in the remaining rows, spread evenly over
+/// the history. Within each run of consecutive stations, as many of
+/// the first ones as of the last ones are kept, and the others are
+/// replaced by a single station counting them, even if no station of
+/// the run fits.
+///
+/// Rows are counted one per event, ignoring the rows spacing them out
+/// and assuming stations fit on a single line. Structural events, and
+/// a station for every run of stations, are always kept, so the result
+/// can still exceed `max_rows`.
+///
+/// ```
+/// use metro::Event;
+///
+/// let mut events = vec![Event::StartTrack(0.into())];
+/// events.extend((0..100).map(|i| Event::station(0, format!("Commit {}", i))));
+///
+/// let summarized = metro::summarize(&events, 6);
+/// assert_eq!(summarized.len(), 6);
+/// assert!(summarized.contains(&Event::station(0, "⋮ 96 stations elided")));
+/// ```
+///
+/// Runs of stations which don't fit are still counted:
+///
+/// ```
+/// use metro::Event;
+///
+/// let mut events = Vec::new();
+/// for _ in 0..5 {
+///     events.extend((0..3).map(|i| Event::station(0, format!("Step {}", i))));
+///     events.push(Event::NoEvent);
+/// }
+///
+/// let summarized = metro::summarize(&events, 7);
+/// let elided = Event::station(0, "⋮ 3 stations elided");
+/// assert_eq!(summarized.iter().filter(|event| **event == elided).count(), 5);
+/// ```
+///
+/// [`Event`]: enum.Event.html
+pub fn summarize<'a>(events: &[Event<'a>], max_rows: usize) -> Vec<Event<'a>> {
+    let is_station = |event: &Event| {
+        matches!(
+            event,
+            Event::Station(..) | Event::SharedStation(..) | Event::StationKv(..)
+        )
+    };
+    let stations = events.iter().filter(|event| is_station(event)).count();
+    let budget = max_rows.saturating_sub(events.len() - stations);
+    if stations <= budget {
+        return events.to_vec();
+    }
+
+    let mut summarized = Vec::with_capacity(max_rows);
+    // The number of stations before the current run
+    let mut seen = 0;
+    let mut rest = events;
+    while let Some(first) = rest.first() {
+        let len = rest.iter().take_while(|event| is_station(event)).count();
+        if len == 0 {
+            summarized.push(first.clone());
+            rest = &rest[1..];
+            continue;
+        }
+        let (run, after) = rest.split_at(len);
+        rest = after;
+
+        // Spread the budget evenly, whatever the length of runs
+        let quota = (seen + len) * budget / stations - seen * budget / stations;
+        seen += len;
+        if quota >= len {
+            summarized.extend_from_slice(run);
+        } else {
+            // As many stations on both sides of the elision
+            let kept = quota.saturating_sub(1) / 2;
+            let elided = &run[kept..len - kept];
+            summarized.extend_from_slice(&run[..kept]);
+            summarized.push(elision(elided));
+            summarized.extend_from_slice(&run[len - kept..]);
+        }
+    }
+
+    summarized
+}
+
+/// A station standing for the `elided` stations, on their
+/// track if they share one, else not tied to any track.
+fn elision<'a>(elided: &[Event]) -> Event<'a> {
+    let track_of = |event: &Event| match event {
+        Event::Station(track_id, _) | Event::StationKv(track_id, _) => Some(*track_id),
+        _ => None,
+    };
+    let track_id = track_of(&elided[0])
+        .filter(|track_id| {
+            elided
+                .iter()
+                .all(|event| track_of(event) == Some(*track_id))
+        })
+        .unwrap_or(DETACHED);
+    Event::Station(
+        track_id,
+        format!("⋮ {} stations elided", elided.len()).into(),
+    )
+}
+
+/// Collapse the identical stations following each other on a track
+/// of `&[`[`Event`]`]` into the first one, suffixed by `×N` where `N`
+/// is their number, e.g. for messages repeated in a loop.
+///
+/// Stations of other tracks can come in between, but any other
+/// event of the track, e.g. a split, ends the repetition.
+///
+/// ```
+/// use metro::Event;
+///
+/// let events = [
+///     Event::station(0, "Retry"),
+///     Event::station(1, "Waiting"),
+///     Event::station(0, "Retry"),
+///     Event::station(0, "Retry"),
+///     Event::station(0, "Connected"),
+/// ];
+///
+/// assert_eq!(
+///     metro::dedup(&events),
+///     [
+///         Event::station(0, "Retry ×3"),
+///         Event::station(1, "Waiting"),
+///         Event::station(0, "Connected"),
+///     ]
+/// );
+/// ```
+///
+/// [`Event`]: enum.Event.html
+pub fn dedup<'a>(events: &[Event<'a>]) -> Vec<Event<'a>> {
+    // The index of the last station of each track, while it can repeat
+    let mut last: HashMap<TrackId, usize> = HashMap::new();
+    // Each event kept, along with its number of repetitions
+    let mut deduped: Vec<(Event<'a>, usize)> = Vec::with_capacity(events.len());
+
+    for event in events {
+        if let Event::Station(track_id, station) = event {
+            if let Some(&index) = last.get(track_id) {
+                if let (Event::Station(_, first), count) = &mut deduped[index] {
+                    if first == station {
+                        *count += 1;
+                        continue;
+                    }
+                }
+            }
+            last.insert(*track_id, deduped.len());
+        } else {
+            event.for_each_track_id(|track_id| {
+                last.remove(&track_id);
+            });
+        }
+        deduped.push((event.clone(), 1));
+    }
+
+    deduped
+        .into_iter()
+        .map(|(event, count)| match event {
+            Event::Station(track_id, station) if count > 1 => {
+                Event::Station(track_id, station.with_suffix(&format!(" ×{}", count)))
+            }
+            event => event,
+        })
+        .collect()
+}
+
+/// Extract from `&[`[`Event`]`]` the paths from the station with ID
+/// `from` to the station with ID `to`, e.g. to answer "how did we get
+/// from A to B" in a big graph.
+///
+/// Only the events between both stations, on tracks leading from
+/// `from` to `to` through splits, joins, links, and shared stations,
+/// are kept.
+///
+/// Returns `None` if either station doesn't exist, or if `to`
+/// comes before `from`.
+///
+/// ```
+/// use metro::{Event, Station};
+///
+/// let events = [
+///     Event::station(0, Station::new("Start").with_id("a")),
+///     Event::SplitTrack(0.into(), 1.into()),
+///     Event::SplitTrack(0.into(), 2.into()),
+///     Event::station(1, "Unrelated"),
+///     Event::station(2, "Fix"),
+///     Event::JoinTrack(2.into(), 0.into()),
+///     Event::station(0, Station::new("Release").with_id("b")),
+/// ];
+///
+/// let slice = metro::slice_between(&events, "a", "b").unwrap();
+/// assert!(slice.contains(&Event::station(2, "Fix")));
+/// assert!(!slice.contains(&Event::station(1, "Unrelated")));
+/// ```
+///
+/// Paths can start on a branch, and join tracks alive all along:
+///
+/// ```
+/// use metro::{Event, RenderingSettings, Station};
+///
+/// let events = [
+///     Event::StartTrack(5.into()),
+///     Event::SplitTrack(5.into(), 6.into()),
+///     Event::station(6, Station::new("Fix").with_id("a")),
+///     Event::JoinTrack(6.into(), 5.into()),
+///     Event::station(5, Station::new("Release").with_id("b")),
+/// ];
+///
+/// let slice = metro::slice_between(&events, "a", "b").unwrap();
+/// let rdr = RenderingSettings::default()
+///     .color(false)
+///     .splat(1)
+///     .implicit_root(false);
+/// let mut metro = metro::events::Metro::with_settings(rdr);
+/// metro.extend(slice);
+///
+/// assert_eq!(
+///     metro.to_string().unwrap(),
+///     "│ ╪    Fix\n│ │ \n├─┘ \n╪     Release\n│ \n",
+/// );
+/// ```
+///
+/// [`Event`]: enum.Event.html
+pub fn slice_between<'a>(events: &[Event<'a>], from: &str, to: &str) -> Option<Vec<Event<'a>>> {
+    let position = |id: &str| {
+        events.iter().position(|event| match event {
+            Event::Station(_, station) | Event::SharedStation(_, station) => {
+                station.id() == Some(id)
+            }
+            _ => false,
+        })
+    };
+    let (start, end) = (position(from)?, position(to)?);
+    if end < start {
+        return None;
+    }
+    // The tracks alive at `from`, from left to right
+    let alive = active_tracks_at(events, &RenderingSettings::default(), start);
+    let events = &events[start..=end];
+
+    // The index from which each track can be reached from `from`...
+    let mut reached: HashMap<TrackId, usize> = HashMap::new();
+    for (i, event) in events.iter().enumerate() {
+        if i == 0 {
+            event.for_each_track_id(|track_id| {
+                reached.insert(track_id, 0);
+            });
+            continue;
+        }
+        for (source, target) in edges(event) {
+            if reached.contains_key(&source) {
+                reached.entry(target).or_insert(i);
+            }
+        }
+    }
+    // ...and the index until which it can reach `to`
+    let mut reaching: HashMap<TrackId, usize> = HashMap::new();
+    for (i, event) in events.iter().enumerate().rev() {
+        if i == events.len() - 1 {
+            event.for_each_track_id(|track_id| {
+                reaching.insert(track_id, i);
+            });
+            continue;
+        }
+        for (source, target) in edges(event) {
+            if reaching.contains_key(&target) {
+                reaching.entry(source).or_insert(i);
+            }
+        }
+    }
+    let on_path = |track_id: &TrackId, i: usize| {
+        matches!(
+            (reached.get(track_id), reaching.get(track_id)),
+            (Some(from), Some(until)) if *from <= i && i <= *until
+        )
+    };
+
+    let mut slice = Vec::new();
+    // Tracks on the paths alive at `from` are started first, even
+    // when they only join the paths later on
+    let mut started = alive
+        .into_iter()
+        .filter(|track_id| reached.contains_key(track_id) && reaching.contains_key(track_id))
+        .collect::<Vec<_>>();
+    events[0].for_each_track_id(|track_id| {
+        if !started.contains(&track_id) {
+            started.push(track_id);
+        }
+    });
+    slice.extend(started.into_iter().map(Event::StartTrack));
+    for (i, event) in events.iter().enumerate() {
+        match event {
+            Event::Station(track_id, _) | Event::StationKv(track_id, _) => {
+                if on_path(track_id, i) {
+                    slice.push(event.clone());
+                }
+            }
+            Event::SharedStation(track_ids, station) => {
+                let track_ids = track_ids
+                    .iter()
+                    .copied()
+                    .filter(|track_id| on_path(track_id, i))
+                    .collect::<Vec<_>>();
+                if !track_ids.is_empty() {
+                    slice.push(Event::SharedStation(track_ids.into(), station.clone()));
+                }
+            }
+            Event::SplitTrack(a, b) | Event::JoinTrack(a, b) | Event::Link(a, b) => {
+                if on_path(a, i) && on_path(b, i) {
+                    slice.push(event.clone());
+                }
+            }
+            Event::StopTrack(track_id) => {
+                if on_path(track_id, i) {
+                    slice.push(event.clone());
+                }
+            }
+            Event::StartTrack(track_id) => {
+                if on_path(track_id, i) {
+                    slice.push(event.clone());
+                }
+            }
+            Event::StartTracks(track_ids) => {
+                let track_ids = track_ids
+                    .iter()
+                    .copied()
+                    .filter(|track_id| on_path(track_id, i))
+                    .collect::<Vec<_>>();
+                if !track_ids.is_empty() {
+                    slice.push(Event::StartTracks(track_ids.into()));
+                }
+            }
+            Event::NoEvent | Event::Reference(..) => {}
+            // Updates of stations outside the paths do nothing
+            Event::UpdateStation(..) | Event::SetStatus(..) => slice.push(event.clone()),
+        }
+    }
+
+    Some(slice)
+}
+
+/// The `(source, target)` connections between tracks made by `event`.
+fn edges(event: &Event) -> Vec<(TrackId, TrackId)> {
+    match event {
+        Event::SplitTrack(parent, child) => vec![(*parent, *child)],
+        Event::JoinTrack(child, target) => vec![(*child, *target)],
+        Event::Link(from, to) => vec![(*from, *to)],
+        Event::SharedStation(track_ids, _) => track_ids
+            .iter()
+            .flat_map(|a| track_ids.iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| a != b)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Normalize `&[`[`Event`]`]`, so that graphs with the same structure
+/// have the same events, however they were built.
+///
+/// Track IDs are renumbered in order of first use, except for the
+/// default track `0` and for [`TrackId`]s whose value is `usize::MAX`.
+/// The tracks of shared stations are sorted, and `StartTracks` of a
+/// single track are replaced by `StartTrack`.
+///
+/// *See also [`graphs_equal`].*
+///
+/// ```
+/// use metro::Event;
+///
+/// let a = [Event::SplitTrack(0.into(), 7.into()), Event::station(7, "Fix")];
+/// let b = [Event::SplitTrack(0.into(), 1.into()), Event::station(1, "Fix")];
+///
+/// assert_eq!(metro::canonical(&a), b);
+/// ```
+///
+/// [`Event`]: enum.Event.html
+/// [`TrackId`]: struct.TrackId.html
+/// [`graphs_equal`]: fn.graphs_equal.html
+pub fn canonical<'a>(events: &[Event<'a>]) -> Vec<Event<'a>> {
+    let mut ids: HashMap<TrackId, TrackId> = HashMap::new();
+    ids.insert(TrackId(0), TrackId(0));
+    let mut rename = |track_id: TrackId| {
+        if track_id.0 == usize::MAX {
+            return track_id;
+        }
+        let next = TrackId(ids.len());
+        *ids.entry(track_id).or_insert(next)
+    };
+
+    events
+        .iter()
+        .map(|event| match event.map_track_ids(&mut rename) {
+            Event::StartTracks(track_ids) if track_ids.len() == 1 => {
+                Event::StartTrack(track_ids[0])
+            }
+            Event::SharedStation(track_ids, station) => {
+                let mut track_ids = track_ids.into_owned();
+                track_ids.sort();
+                Event::SharedStation(track_ids.into(), station)
+            }
+            event => event,
+        })
+        .collect()
+}
+
+/// Whether `a` and `b` have the same [`canonical`] form, i.e.
+/// the same structure and stations, whatever their track IDs.
+///
+/// ```
+/// use metro::Event;
+///
+/// let a = [Event::StartTrack(3.into()), Event::station(3, "Build")];
+/// let b = [Event::StartTracks(vec![1.into()].into()), Event::station(1, "Build")];
+///
+/// assert!(metro::graphs_equal(&a, &b));
+/// assert!(!metro::graphs_equal(&a, &b[..1]));
+/// ```
+///
+/// [`canonical`]: fn.canonical.html
+pub fn graphs_equal(a: &[Event], b: &[Event]) -> bool {
+    a.len() == b.len() && canonical(a) == canonical(b)
+}
@@ -0,0 +1,472 @@
+use crate::events::{self, Event, Incremental, Patches, RenderingSettings, Stations, TrackId};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Anything [`Event`]s can be emitted to, e.g. to write
+/// instrumentation code once, and point it at either a
+/// batch rendering or a live one.
+///
+/// ```
+/// use metro::{Event, EventSink};
+///
+/// fn build<'a, S: EventSink<'a>>(sink: &mut S) {
+///     sink.emit(Event::station(0, "Compile"));
+///     sink.emit(Event::station(0, "Link"));
+/// }
+///
+/// let mut metro = metro::events::Metro::default();
+/// build(&mut metro);
+///
+/// let mut live = metro::LiveRenderer::new(Vec::new());
+/// build(&mut live);
+///
+/// assert_eq!(live.finish().unwrap(), metro.to_vec().unwrap());
+/// ```
+///
+/// [`Event`]: enum.Event.html
+pub trait EventSink<'a> {
+    fn emit(&mut self, event: Event<'a>);
+}
+
+impl<'a> EventSink<'a> for events::Metro<'a> {
+    fn emit(&mut self, event: Event<'a>) {
+        self.push(event);
+    }
+}
+
+impl<'a> EventSink<'a> for Vec<Event<'a>> {
+    fn emit(&mut self, event: Event<'a>) {
+        self.push(event);
+    }
+}
+
+impl<'a, S: EventSink<'a> + ?Sized> EventSink<'a> for &mut S {
+    fn emit(&mut self, event: Event<'a>) {
+        (**self).emit(event);
+    }
+}
+
+/// Render [`Event`]s as they are emitted, writing every row to
+/// [`<W: io::Write>`] as soon as it is laid out, e.g. to follow
+/// a long-running process.
+///
+/// Rows are only laid out and written once, with the events emitted
+/// so far, e.g. their text is only padded for the tracks started so
+/// far.
+///
+/// [`Event`]: enum.Event.html
+///
+/// [`<W: io::Write>`]: https://doc.rust-lang.org/stable/std/io/trait.Write.html
+#[derive(Debug)]
+pub struct LiveRenderer<'a, W: Write> {
+    w: W,
+    rdr: RenderingSettings,
+    events: Vec<Event<'a>>,
+    /// The last updates of every station updated so far.
+    patches: Patches,
+    /// What rows refer to of the stations written so far.
+    stations: Stations,
+    /// The state of rendering after the events emitted so far.
+    state: Incremental,
+    /// The first error writing to `w`, after which nothing is written.
+    error: Option<io::Error>,
+    in_place: bool,
+    /// The number of lines on screen, which can be redrawn in place.
+    height: usize,
+    /// The state of rendering before the lines on screen, along with
+    /// the index of the first event written after it.
+    scrolled: (Incremental, usize),
+    /// The lines on screen, to redraw them in place.
+    output: String,
+    /// Whether to write the summary when finished,
+    /// rather than below every row written.
+    summary: bool,
+}
+
+impl<'a, W: Write> LiveRenderer<'a, W> {
+    /// Create a new `LiveRenderer` writing to `w`, using the default
+    /// [`RenderingSettings`].
+    ///
+    /// [`RenderingSettings`]: struct.RenderingSettings.html
+    pub fn new(w: W) -> Self {
+        Self::with_settings(w, RenderingSettings::default())
+    }
+
+    /// Create a new `LiveRenderer` writing to `w`, using `rdr`.
+    ///
+    /// The [summary] is written once, when [finished].
+    ///
+    /// [summary]: struct.RenderingSettings.html#method.summary
+    /// [finished]: #method.finish
+    pub fn with_settings(w: W, mut rdr: RenderingSettings) -> Self {
+        let summary = rdr.get_summary();
+        rdr.set_summary(false);
+        let state = Incremental::new(&rdr);
+        Self {
+            w,
+            rdr,
+            events: Vec::new(),
+            patches: Patches::default(),
+            stations: Stations::default(),
+            scrolled: (state.clone(), 0),
+            state,
+            error: None,
+            in_place: false,
+            height: 24,
+            output: String::new(),
+            summary,
+        }
+    }
+
+    /// Redraw the rows of stations [updated] after they were written,
+    /// by moving the cursor of the terminal `w` writes to, instead of
+    /// only applying updates to the rows written afterwards.
+    ///
+    /// Rows are redrawn from the first one changed to the last one,
+    /// and only the rows still on [screen] can be redrawn.
+    ///
+    /// ```
+    /// use metro::{Event, EventSink, LiveRenderer, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut live = LiveRenderer::with_settings(Vec::new(), rdr).in_place(true);
+    /// live.emit(Event::station(0, Station::new("Deploying").with_id("deploy")));
+    /// live.emit(Event::station(0, "Notify"));
+    /// live.emit(Event::UpdateStation("deploy".into(), "Deployed".into()));
+    ///
+    /// let output = String::from_utf8(live.finish().unwrap()).unwrap();
+    /// assert!(output.ends_with("\x1b[4F\x1b[J╪    Deployed\n│ \n╪    Notify\n│ \n"));
+    /// ```
+    ///
+    /// [updated]: enum.Event.html#variant.UpdateStation
+    /// [screen]: #method.height
+    pub fn in_place(mut self, in_place: bool) -> Self {
+        self.in_place = in_place;
+        self
+    }
+
+    /// Sets the height of the terminal `w` writes to, in lines, above
+    /// which rows can't be [redrawn in place]. Defaults to `24`.
+    ///
+    /// ```
+    /// use metro::{Event, EventSink, LiveRenderer, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut live = LiveRenderer::with_settings(Vec::new(), rdr)
+    ///     .in_place(true)
+    ///     .height(4);
+    /// live.emit(Event::station(0, Station::new("Deploying").with_id("deploy")));
+    /// live.emit(Event::station(0, "Notify"));
+    /// live.emit(Event::station(0, "Archive"));
+    /// live.emit(Event::UpdateStation("deploy".into(), "Deployed".into()));
+    ///
+    /// // Scrolled off screen
+    /// let output = String::from_utf8(live.finish().unwrap()).unwrap();
+    /// assert!(output.ends_with("╪    Archive\n│ \n\x1b[J"));
+    /// ```
+    ///
+    /// [redrawn in place]: #method.in_place
+    pub fn height(mut self, lines: usize) -> Self {
+        self.height = lines;
+        self
+    }
+
+    /// Returns the events emitted so far.
+    pub fn events(&self) -> &[Event<'a>] {
+        &self.events
+    }
+
+    /// Returns the first error writing any row, if any, which
+    /// is then no longer returned by [`finish`].
+    ///
+    /// [`finish`]: #method.finish
+    pub(crate) fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Write the [ruler] below the last row and the [summary], if
+    /// enabled, then flush and return the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error writing any row, if any.
+    ///
+    /// [ruler]: struct.RenderingSettings.html#method.ruler
+    /// [summary]: struct.RenderingSettings.html#method.summary
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.state.finish(&mut self.w, &self.rdr)?;
+        if self.summary {
+            let rdr = self.rdr.clone().summary(true);
+            events::write_summary(&mut self.w, &self.events, &rdr)?;
+        }
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+impl<'a, W: Write> LiveRenderer<'a, W> {
+    /// Write the rows of the last event emitted.
+    fn write_rows(&mut self) -> io::Result<()> {
+        let event = self.events.last().unwrap();
+        let mut bytes = Vec::new();
+        let (patches, stations) = (&self.patches, &mut self.stations);
+        self.state
+            .write(&mut bytes, event, patches, stations, &self.rdr)?;
+        self.w.write_all(&bytes)?;
+        if self.in_place {
+            self.output.push_str(&String::from_utf8_lossy(&bytes));
+            self.scroll()?;
+        }
+        Ok(())
+    }
+
+    /// Forget the lines scrolled off screen, which can't be redrawn,
+    /// along with the events they were written for.
+    fn scroll(&mut self) -> io::Result<()> {
+        let mut lines = self.output.matches('\n').count();
+        let (state, next) = &mut self.scrolled;
+        while lines > self.height && *next < self.events.len() {
+            // Only the lines are counted, e.g. relative times change
+            let mut bytes = Vec::new();
+            let event = &self.events[*next];
+            state.write(
+                &mut bytes,
+                event,
+                &self.patches,
+                &mut self.stations,
+                &self.rdr,
+            )?;
+            *next += 1;
+            let scrolled = bytes.iter().filter(|&&b| b == b'\n').count().min(lines);
+            let end = match scrolled.checked_sub(1) {
+                Some(last) => self.output.match_indices('\n').nth(last).unwrap().0 + 1,
+                None => 0,
+            };
+            self.output.drain(..end);
+            lines -= scrolled;
+        }
+        Ok(())
+    }
+
+    /// Redraw every line on screen from the first one that changed.
+    fn redraw(&mut self) -> io::Result<()> {
+        let (scrolled, next) = &self.scrolled;
+        let mut state = scrolled.clone();
+        let mut bytes = Vec::new();
+        for event in self.events[*next..].iter() {
+            state.write(
+                &mut bytes,
+                event,
+                &self.patches,
+                &mut self.stations,
+                &self.rdr,
+            )?;
+        }
+        let output = String::from_utf8_lossy(&bytes).into_owned();
+
+        // The length of the lines that didn't change
+        let unchanged = self
+            .output
+            .split_inclusive('\n')
+            .zip(output.split_inclusive('\n'))
+            .take_while(|(written, line)| written == line)
+            .map(|(written, _)| written.len())
+            .sum::<usize>();
+        let changed = self.output[unchanged..].matches('\n').count();
+        if changed > 0 {
+            // Move to the start of the first line that changed
+            write!(self.w, "\x1b[{}F", changed)?;
+        }
+        write!(self.w, "\x1b[J")?;
+        self.w.write_all(&output.as_bytes()[unchanged..])?;
+
+        self.state = state;
+        self.output = output;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> EventSink<'a> for LiveRenderer<'a, W> {
+    fn emit(&mut self, event: Event<'a>) {
+        let update = matches!(event, Event::UpdateStation(..) | Event::SetStatus(..));
+        self.patches.note(&event);
+        self.events.push(event);
+        if self.error.is_some() {
+            return;
+        }
+
+        let written = match update && self.in_place {
+            true => self.redraw(),
+            false => self.write_rows(),
+        };
+        if let Err(error) = written.and_then(|_| self.w.flush()) {
+            self.error = Some(error);
+        }
+    }
+}
+
+/// Limit the stations emitted to `S: EventSink` to `rate` per track
+/// every `interval`, e.g. to instrument a hot loop. The stations over
+/// the rate are dropped, or [sampled], and replaced by a single
+/// `Skipped N events` station, emitted before the next station or
+/// event of their track, or when [flushed].
+///
+/// ```
+/// use metro::{Event, EventSink, Throttle};
+/// use std::time::Duration;
+///
+/// let mut throttle = Throttle::new(Vec::new(), 2, Duration::from_secs(60));
+/// for i in 0..10 {
+///     throttle.emit(Event::station(0, format!("Iteration {}", i)));
+/// }
+/// throttle.emit(Event::StopTrack(0.into()));
+///
+/// assert_eq!(
+///     throttle.into_inner(),
+///     [
+///         Event::station(0, "Iteration 0"),
+///         Event::station(0, "Iteration 1"),
+///         Event::station(0, "Skipped 8 events"),
+///         Event::StopTrack(0.into()),
+///     ]
+/// );
+/// ```
+///
+/// [sampled]: #method.sample
+/// [flushed]: #method.flush
+#[derive(Debug)]
+pub struct Throttle<S> {
+    sink: S,
+    rate: usize,
+    interval: Duration,
+    sample: Option<usize>,
+    windows: HashMap<TrackId, Window>,
+}
+
+/// The stations of a track in the current interval.
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    emitted: usize,
+    skipped: usize,
+}
+
+impl<S> Throttle<S> {
+    /// Create a new `Throttle` emitting to `sink` at most `rate`
+    /// stations per track every `interval`.
+    pub fn new(sink: S, rate: usize, interval: Duration) -> Self {
+        Self {
+            sink,
+            rate,
+            interval,
+            sample: None,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Keep one station out of every `every` over the rate, instead
+    /// of dropping them all.
+    ///
+    /// ```
+    /// use metro::{Event, EventSink, Throttle};
+    /// use std::time::Duration;
+    ///
+    /// let mut throttle = Throttle::new(Vec::new(), 1, Duration::from_secs(60)).sample(5);
+    /// for i in 0..11 {
+    ///     throttle.emit(Event::station(0, format!("Iteration {}", i)));
+    /// }
+    ///
+    /// let events = throttle.into_inner();
+    /// assert_eq!(events[1], Event::station(0, "Skipped 4 events"));
+    /// assert_eq!(events[2], Event::station(0, "Iteration 5"));
+    /// assert_eq!(events.len(), 5);
+    /// ```
+    pub fn sample(mut self, every: usize) -> Self {
+        self.sample = Some(every.max(1));
+        self
+    }
+}
+
+impl<'a, S: EventSink<'a>> Throttle<S> {
+    /// Emit a `Skipped N events` station on every track with
+    /// stations skipped since its last station.
+    pub fn flush(&mut self) {
+        let mut track_ids = self.windows.keys().copied().collect::<Vec<_>>();
+        track_ids.sort();
+        for track_id in track_ids {
+            self.report(track_id);
+        }
+    }
+
+    /// [Flush] and return the sink.
+    ///
+    /// [Flush]: #method.flush
+    pub fn into_inner(mut self) -> S {
+        self.flush();
+        self.sink
+    }
+
+    /// Emit a station counting the stations of `track_id` skipped so far, if any.
+    fn report(&mut self, track_id: TrackId) {
+        let skipped = match self.windows.get_mut(&track_id) {
+            Some(window) => std::mem::take(&mut window.skipped),
+            None => return,
+        };
+        if skipped > 0 {
+            let text = format!(
+                "Skipped {} event{}",
+                skipped,
+                if skipped == 1 { "" } else { "s" }
+            );
+            self.sink.emit(Event::Station(track_id, text.into()));
+        }
+    }
+}
+
+impl<'a, S: EventSink<'a>> EventSink<'a> for Throttle<S> {
+    fn emit(&mut self, event: Event<'a>) {
+        let track_id = match &event {
+            Event::Station(track_id, _) | Event::StationKv(track_id, _) => *track_id,
+            _ => {
+                let mut track_ids = Vec::new();
+                event.for_each_track_id(|track_id| track_ids.push(track_id));
+                for track_id in track_ids {
+                    self.report(track_id);
+                    self.windows.remove(&track_id);
+                }
+                self.sink.emit(event);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let window = self.windows.entry(track_id).or_insert(Window {
+            start: now,
+            emitted: 0,
+            skipped: 0,
+        });
+        if now.duration_since(window.start) >= self.interval {
+            window.start = now;
+            window.emitted = 0;
+            self.report(track_id);
+        }
+
+        let window = self.windows.get_mut(&track_id).unwrap();
+        if window.emitted < self.rate {
+            window.emitted += 1;
+        } else if self
+            .sample
+            .is_some_and(|every| (window.skipped + 1) % every == 0)
+        {
+            self.report(track_id);
+        } else {
+            window.skipped += 1;
+            return;
+        }
+        self.sink.emit(event);
+    }
+}
@@ -0,0 +1,464 @@
+use crate::events::{visible_width, Event};
+use crate::styled::StyledText;
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime};
+
+/// The payload of an [`Event::Station`]: its text,
+/// along with optional metadata.
+///
+/// A `Station` can be created from anything convertible
+/// into a `Cow<str>`, so plain strings can be used wherever
+/// a `Station` is expected.
+///
+/// ```
+/// use metro::{Event, Station};
+/// use std::time::SystemTime;
+///
+/// let event = Event::station(0, Station::new("Build").with_time(SystemTime::now()));
+/// ```
+///
+/// [`Event::Station`]: enum.Event.html#variant.Station
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Station<'a> {
+    text: Cow<'a, str>,
+    time: Option<SystemTime>,
+    duration: Option<Duration>,
+    id: Option<Cow<'a, str>>,
+    fields: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    tags: Vec<Cow<'a, str>>,
+    level: Option<Level>,
+    styled: Option<StyledText>,
+    link: Option<Cow<'a, str>>,
+    badge: Option<usize>,
+    severity: Option<Severity>,
+    status: Option<Status>,
+    nested: Vec<Event<'a>>,
+}
+
+/// The verbosity level of a [`Station`], mirroring log levels.
+///
+/// *See [`Station::with_level`].*
+///
+/// [`Station`]: struct.Station.html
+/// [`Station::with_level`]: struct.Station.html#method.with_level
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// The severity of a [`Station`], e.g. of a log line, rendering
+/// its text in the color of the severity.
+///
+/// *See [`Station::with_severity`].*
+///
+/// [`Station`]: struct.Station.html
+/// [`Station::with_severity`]: struct.Station.html#method.with_severity
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+/// The status of a [`Station`], e.g. of a pipeline step,
+/// rendered as a marker before its text.
+///
+/// *See [`Station::with_status`].*
+///
+/// [`Station`]: struct.Station.html
+/// [`Station::with_status`]: struct.Station.html#method.with_status
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Status {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl Status {
+    /// The marker rendered before the text of a station.
+    pub(crate) fn marker(self) -> &'static str {
+        match self {
+            Status::Pending => "○",
+            Status::Running => "◐",
+            Status::Done => "✓",
+            Status::Failed => "✗",
+        }
+    }
+}
+
+impl<'a> Station<'a> {
+    pub fn new<S: Into<Cow<'a, str>>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a station rendered as a box titled `title`, bordering
+    /// every line of `lines`, e.g. a diff hunk or a stack trace.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::block("Output", ["ok", "2 passed"])));
+    ///
+    /// assert_eq!(
+    ///     metro.to_string().unwrap(),
+    ///     "╪    ┌─ Output ─┐\n\
+    ///      │    │ ok       │\n\
+    ///      │    │ 2 passed │\n\
+    ///      │    └──────────┘\n\
+    ///      │ \n"
+    /// );
+    /// ```
+    pub fn block<T, I, S>(title: T, lines: I) -> Self
+    where
+        T: AsRef<str>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let title = title.as_ref();
+        let lines = lines.into_iter().collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|line| visible_width(line.as_ref()))
+            .max()
+            .unwrap_or(0)
+            .max(visible_width(title) + 2);
+
+        let mut text = format!(
+            "┌─ {} {}┐\n",
+            title,
+            "─".repeat(width - visible_width(title) - 1)
+        );
+        for line in lines.iter() {
+            let line = line.as_ref();
+            let pad = width - visible_width(line);
+            text.push_str(&format!("│ {}{} │\n", line, " ".repeat(pad)));
+        }
+        text.push_str(&format!("└{}┘", "─".repeat(width + 2)));
+        Self::new(text)
+    }
+
+    /// Set the point in time at which this station was reached.
+    pub fn with_time(mut self, time: SystemTime) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Set how long the stage represented by this station took.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set an ID identifying this station in [`Event::Reference`]s.
+    ///
+    /// [`Event::Reference`]: enum.Event.html#variant.Reference
+    pub fn with_id<S: Into<Cow<'a, str>>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Link this station to `url`, e.g. the page of a CI job.
+    ///
+    /// With [`color`], the text of the station is rendered as a
+    /// [terminal hyperlink], and as a link in the HTML of [`to_markdown`].
+    /// Exports, e.g. [`to_d2`], keep the link of every station.
+    ///
+    /// ```
+    /// use metro::{Event, Station};
+    ///
+    /// let mut metro = metro::events::Metro::default();
+    /// metro.push(Event::station(0, Station::new("Build").with_link("https://ci.example.com/1")));
+    ///
+    /// let markdown = metro.to_markdown(true).unwrap();
+    /// #[cfg(feature = "color")]
+    /// assert!(markdown.contains(r#"<a href="https://ci.example.com/1">"#));
+    /// assert!(metro.render_plain().unwrap().contains("Build"));
+    /// ```
+    ///
+    /// [`color`]: struct.RenderingSettings.html#method.color
+    /// [terminal hyperlink]: https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda
+    /// [`to_markdown`]: events/struct.Metro.html#method.to_markdown
+    /// [`to_d2`]: events/struct.Metro.html#method.to_d2
+    pub fn with_link<S: Into<Cow<'a, str>>>(mut self, url: S) -> Self {
+        self.link = Some(url.into());
+        self
+    }
+
+    /// Set a numeric badge, rendered as `(badge)` after the text of this
+    /// station, in bold if [`color`] is enabled, e.g. for a number of
+    /// failed tests.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Tests failed").with_badge(3)));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    Tests failed (3)\n│ \n");
+    /// ```
+    ///
+    /// [`color`]: struct.RenderingSettings.html#method.color
+    pub fn with_badge(mut self, badge: usize) -> Self {
+        self.badge = Some(badge);
+        self
+    }
+
+    /// Set the severity of this station, rendering its text in the
+    /// color of `severity` if [`color`] is enabled.
+    ///
+    /// *See [`RenderingSettings::severity_color`].*
+    ///
+    /// [`color`]: struct.RenderingSettings.html#method.color
+    /// [`RenderingSettings::severity_color`]: struct.RenderingSettings.html#method.severity_color
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Set the status of this station, rendered as a marker before
+    /// its text, e.g. to show that a step is still running.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station, Status};
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Deploy").with_status(Status::Done)));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    ✓ Deploy\n│ \n");
+    /// ```
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Embed `events` in this station, rendered as a metro of their
+    /// own below its text, indented, e.g. for a pipeline of pipelines.
+    ///
+    /// The embedded metro is rendered with the settings of the metro
+    /// embedding it, without row numbers, rulers, headers nor pages.
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let build = vec![
+    ///     Event::station(0, "Compile"),
+    ///     Event::station(0, "Link"),
+    /// ];
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, Station::new("Build").with_nested(build)));
+    /// metro.push(Event::station(0, "Deploy"));
+    ///
+    /// let string = metro.to_string().unwrap();
+    /// assert!(string.starts_with("╪    Build\n│      ╪    Compile\n│      │ \n│      ╪    Link\n"));
+    /// ```
+    pub fn with_nested<I: IntoIterator<Item = Event<'a>>>(mut self, events: I) -> Self {
+        self.nested.extend(events);
+        self
+    }
+
+    /// Add a field, making the text of this station a template
+    /// in which `{name}` placeholders are replaced by their value
+    /// when rendering.
+    ///
+    /// A placeholder can truncate the value to `N` characters with
+    /// `{name:.N}`, or pad it to `N` characters with `{name:N}`.
+    /// Braces are escaped by doubling them.
+    ///
+    /// *See also [`RenderingSettings::station_format`].*
+    ///
+    /// ```
+    /// use metro::{Event, RenderingSettings, Station};
+    ///
+    /// let station = Station::new("{hash:.7} {msg}")
+    ///     .with_field("hash", "4f8c8e5a0b1c2d3e")
+    ///     .with_field("msg", "Fix rendering");
+    ///
+    /// let rdr = RenderingSettings::default().color(false).splat(1);
+    /// let mut metro = metro::events::Metro::with_settings(rdr);
+    /// metro.push(Event::station(0, station));
+    ///
+    /// assert_eq!(metro.to_string().unwrap(), "╪    4f8c8e5 Fix rendering\n│ \n");
+    /// ```
+    ///
+    /// [`RenderingSettings::station_format`]: struct.RenderingSettings.html#method.station_format
+    pub fn with_field<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add every `(name, value)` field of `fields`.
+    ///
+    /// *See [`Station::with_field`].*
+    ///
+    /// [`Station::with_field`]: #method.with_field
+    pub fn with_fields<I, K, V>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        fields.into_iter().fold(self, |station, (name, value)| {
+            station.with_field(name, value)
+        })
+    }
+
+    /// Tag this station, e.g. as `"verbose"`, so it can be
+    /// hidden when rendering.
+    ///
+    /// *See [`RenderingSettings::hide_tags`].*
+    ///
+    /// [`RenderingSettings::hide_tags`]: struct.RenderingSettings.html#method.hide_tags
+    pub fn with_tag<S: Into<Cow<'a, str>>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Set the verbosity level of this station, so it can
+    /// be hidden when rendering at a higher level.
+    /// Stations without a level are always rendered.
+    ///
+    /// *See [`RenderingSettings::min_level`].*
+    ///
+    /// [`RenderingSettings::min_level`]: struct.RenderingSettings.html#method.min_level
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Returns the text of this station, which is
+    /// a template if the station has fields.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn time(&self) -> Option<SystemTime> {
+        self.time
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the `(name, value)` of every field.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> + Clone + '_ {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value.as_ref()))
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &str> + '_ {
+        self.tags.iter().map(|tag| tag.as_ref())
+    }
+
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
+    pub fn badge(&self) -> Option<usize> {
+        self.badge
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+
+    /// Returns the events embedded in this station, if any.
+    pub fn nested(&self) -> &[Event<'a>] {
+        &self.nested
+    }
+
+    /// Returns the styled text of this station, if it was created
+    /// from a [`StyledText`], whose plain text is [`text`].
+    ///
+    /// [`StyledText`]: struct.StyledText.html
+    /// [`text`]: #method.text
+    pub fn styled(&self) -> Option<&StyledText> {
+        self.styled.as_ref()
+    }
+
+    pub(crate) fn has_fields(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    /// Replace the text of this station, and its styled text if any.
+    pub(crate) fn set_text(&mut self, text: Cow<'a, str>) {
+        self.text = text;
+        self.styled = None;
+    }
+
+    /// Style the text of this station as `styled`, whose
+    /// plain text must be the text of this station.
+    pub(crate) fn with_styled(mut self, styled: StyledText) -> Self {
+        self.styled = Some(styled);
+        self
+    }
+
+    pub(crate) fn set_status(&mut self, status: Status) {
+        self.status = Some(status);
+    }
+
+    /// Append `suffix` to the text of this station, styled or not.
+    pub(crate) fn with_suffix(mut self, suffix: &str) -> Self {
+        self.text = format!("{}{}", self.text, suffix).into();
+        self.styled = self.styled.map(|styled| styled.push(suffix));
+        self
+    }
+}
+
+impl<'a> From<&'a str> for Station<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl<'a> From<String> for Station<'a> {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Station<'a> {
+    fn from(text: Cow<'a, str>) -> Self {
+        Self::new(text)
+    }
+}
+
+impl<'a> From<StyledText> for Station<'a> {
+    fn from(text: StyledText) -> Self {
+        Self {
+            text: text.to_plain().into(),
+            styled: Some(text),
+            ..Default::default()
+        }
+    }
+}
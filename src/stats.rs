@@ -0,0 +1,59 @@
+use crate::events::{for_each_tracks, layout, Event, Rail, RenderingSettings, TrackId};
+use std::collections::HashMap;
+
+/// Statistics about the shape of a graph.
+///
+/// *See [`Metro::stats`].*
+///
+/// [`Metro::stats`]: struct.Metro.html#method.stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of tracks started, including the default track `0`.
+    pub tracks_created: usize,
+    /// The largest number of tracks alive at the same time.
+    pub max_concurrent_tracks: usize,
+    /// The number of stations on each track.
+    pub stations_per_track: HashMap<TrackId, usize>,
+    /// The number of stations not tied to any track.
+    pub detached_stations: usize,
+    /// The height of the rendered graph, in rows.
+    pub height: usize,
+    /// The width of the rendered graph, in rails, excluding any text.
+    pub width: usize,
+}
+
+pub(crate) fn stats(events: &[Event], rdr: &RenderingSettings) -> Stats {
+    let rows = layout(events, rdr);
+
+    let mut stats = Stats {
+        tracks_created: rdr.initial_tracks().len(),
+        height: rows.len(),
+        ..Default::default()
+    };
+
+    let mut previous = rdr.initial_tracks();
+    for_each_tracks(events, rdr, |_, tracks| {
+        stats.tracks_created += tracks.iter().filter(|t| !previous.contains(t)).count();
+        previous = tracks.to_vec();
+    });
+
+    for row in rows.iter() {
+        stats.width = stats.width.max(row.rails.len());
+        if row.starts_station {
+            let mut stations = row
+                .rails
+                .iter()
+                .filter(|(rail, _)| matches!(rail, Rail::Station | Rail::SharedStation))
+                .peekable();
+            if stations.peek().is_none() {
+                stats.detached_stations += 1;
+            }
+            for (_, track_id) in stations {
+                *stats.stations_per_track.entry(*track_id).or_default() += 1;
+            }
+        }
+    }
+    stats.max_concurrent_tracks = stats.width;
+
+    stats
+}
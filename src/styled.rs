@@ -0,0 +1,144 @@
+use std::fmt::Write;
+
+/// Text made of spans, each with its own color and attributes, e.g.
+/// to emphasize part of the text of a [`Station`].
+///
+/// Spans are added with [`push`], and styled by the methods following
+/// it, which style the last span. Styles are only rendered with
+/// [`color`], and are kept by the HTML of [`to_markdown`].
+///
+/// ```
+/// use metro::{Event, StyledText};
+///
+/// let text = StyledText::new()
+///     .push("Deploy ")
+///     .push("api")
+///     .bold()
+///     .push(" ok")
+///     .color([0, 200, 0]);
+///
+/// assert_eq!(text.to_plain(), "Deploy api ok");
+///
+/// let event = Event::station(0, text);
+/// ```
+///
+/// [`Station`]: struct.Station.html
+/// [`push`]: #method.push
+/// [`color`]: struct.RenderingSettings.html#method.color
+/// [`to_markdown`]: events/struct.Metro.html#method.to_markdown
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StyledText {
+    spans: Vec<Span>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) struct Span {
+    pub(crate) text: String,
+    pub(crate) color: Option<[u8; 3]>,
+    pub(crate) bold: bool,
+    pub(crate) dim: bool,
+    pub(crate) italic: bool,
+    pub(crate) underline: bool,
+}
+
+impl StyledText {
+    /// Create a new, empty `StyledText`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an unstyled span of `text`.
+    pub fn push<S: Into<String>>(mut self, text: S) -> Self {
+        self.spans.push(Span {
+            text: text.into(),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Color the last span as `[r, g, b]`.
+    pub fn color(self, color: [u8; 3]) -> Self {
+        self.style(|span| span.color = Some(color))
+    }
+
+    /// Render the last span in bold.
+    pub fn bold(self) -> Self {
+        self.style(|span| span.bold = true)
+    }
+
+    /// Render the last span dimmed.
+    pub fn dim(self) -> Self {
+        self.style(|span| span.dim = true)
+    }
+
+    /// Render the last span in italics.
+    pub fn italic(self) -> Self {
+        self.style(|span| span.italic = true)
+    }
+
+    /// Render the last span underlined.
+    pub fn underline(self) -> Self {
+        self.style(|span| span.underline = true)
+    }
+
+    /// Returns the text of every span, without any style.
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    pub(crate) fn from_spans(spans: Vec<Span>) -> Self {
+        Self { spans }
+    }
+
+    pub(crate) fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    fn style<F: FnOnce(&mut Span)>(mut self, f: F) -> Self {
+        if let Some(span) = self.spans.last_mut() {
+            f(span);
+        }
+        self
+    }
+
+    /// Returns the text of every span, styled with ANSI escape sequences.
+    pub(crate) fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for span in self.spans.iter() {
+            let mut start = String::new();
+            let mut end = String::new();
+            if let Some([r, g, b]) = span.color {
+                write!(start, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                end.push_str("\x1b[39m");
+            }
+            if span.bold {
+                start.push_str("\x1b[1m");
+            }
+            if span.dim {
+                start.push_str("\x1b[2m");
+            }
+            if span.bold || span.dim {
+                end.push_str("\x1b[22m");
+            }
+            if span.italic {
+                start.push_str("\x1b[3m");
+                end.push_str("\x1b[23m");
+            }
+            if span.underline {
+                start.push_str("\x1b[4m");
+                end.push_str("\x1b[24m");
+            }
+
+            // Styles are reset at line breaks, not to bleed into the rails
+            for (i, line) in span.text.split('\n').enumerate() {
+                if i > 0 {
+                    ansi.push('\n');
+                }
+                if !line.is_empty() {
+                    write!(ansi, "{}{}{}", start, line, end).unwrap();
+                }
+            }
+        }
+        ansi
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+/// Resolve the placeholders of `template` with `fields`.
+///
+/// A placeholder is a field name in braces, optionally followed
+/// by a colon and either a `.N` precision, truncating the value to
+/// `N` characters, or an `N` width, padding it to `N` characters:
+/// `{hash:.7}`, `{author:12}`. Braces are escaped by doubling them.
+///
+/// Placeholders of unknown fields are kept as is. Every resolved
+/// value is passed through `style`, along with its field name.
+pub(crate) fn resolve<'f, I, F>(template: &str, fields: I, style: F) -> String
+where
+    I: Iterator<Item = (&'f str, &'f str)> + Clone,
+    F: Fn(&str, &str) -> String,
+{
+    let mut resolved = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        resolved.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+
+        if rest.starts_with(brace) {
+            resolved.push_str(brace);
+            rest = &rest[1..];
+            continue;
+        }
+        let end = match rest.find('}') {
+            Some(end) if brace == "{" => end,
+            _ => {
+                resolved.push_str(brace);
+                continue;
+            }
+        };
+
+        let placeholder = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let value = fields.clone().find(|(n, _)| *n == name).map(|(_, v)| v);
+        match (value, parse_spec(spec)) {
+            (Some(value), Some(spec)) => {
+                let value = spec.apply(value);
+                resolved.push_str(&style(name, &value));
+            }
+            _ => write!(resolved, "{{{}}}", placeholder).unwrap(),
+        }
+    }
+    resolved.push_str(rest);
+
+    resolved
+}
+
+enum Spec {
+    None,
+    Precision(usize),
+    Width(usize),
+}
+
+fn parse_spec(spec: &str) -> Option<Spec> {
+    if spec.is_empty() {
+        Some(Spec::None)
+    } else if let Some(precision) = spec.strip_prefix('.') {
+        precision.parse().ok().map(Spec::Precision)
+    } else {
+        spec.parse().ok().map(Spec::Width)
+    }
+}
+
+impl Spec {
+    fn apply(&self, value: &str) -> String {
+        match *self {
+            Spec::None => value.to_owned(),
+            Spec::Precision(precision) => value.chars().take(precision).collect(),
+            Spec::Width(width) => format!("{:width$}", value, width = width),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::dag;
+use crate::events::Event;
+use crate::station::Station;
+
+/// Lay out a thread of messages as events, e.g. e-mails or comments,
+/// one station per item, where replies split into tracks.
+///
+/// Items are identified by their [station ID], and `parent` returns
+/// the ID of the item an item replies to, if any. Items replying to
+/// an item that is not given, e.g. in a partial archive, start new
+/// threads.
+///
+/// Items are rendered in order of their [time], items without a time
+/// first, and otherwise in the given order, except that replies
+/// always come after the items they reply to.
+///
+/// ```
+/// use metro::Station;
+///
+/// // Replies are numbered after the comment they reply to
+/// let comments = ["1", "1.1", "1.2", "1.1.1", "2"]
+///     .iter()
+///     .map(|id| Station::new(format!("Comment #{}", id)).with_id(*id));
+///
+/// let events = metro::from_threaded(comments, |comment| {
+///     let (parent, _) = comment.id()?.rsplit_once('.')?;
+///     Some(parent.to_owned())
+/// });
+///
+/// let mut metro = metro::events::Metro::from(events);
+/// let string = metro::strip_ansi(&metro.to_string().unwrap());
+/// // In the given order, not depth-first
+/// assert!(string.find("#1.2").unwrap() < string.find("#1.1.1").unwrap());
+/// ```
+///
+/// [station ID]: struct.Station.html#method.with_id
+/// [time]: struct.Station.html#method.with_time
+pub fn from_threaded<'a, I, F, K>(items: I, mut parent: F) -> Vec<Event<'a>>
+where
+    I: IntoIterator<Item = Station<'a>>,
+    F: FnMut(&Station<'a>) -> Option<K>,
+    K: AsRef<str>,
+{
+    let mut items = items.into_iter().collect::<Vec<_>>();
+    // Stable, to keep the given order of items sent at the same time
+    items.sort_by_key(Station::time);
+
+    let ids = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((item.id()?.to_owned(), i)))
+        .collect::<HashMap<_, _>>();
+    let edges = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let parent = parent(item)?;
+            Some((*ids.get(parent.as_ref())?, i))
+        })
+        .collect::<Vec<_>>();
+
+    dag::layout(items, &edges)
+}
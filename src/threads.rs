@@ -0,0 +1,124 @@
+use crate::channel::{channel, MetroCollector, MetroSender};
+use crate::events::{Event, TrackId};
+use crate::station::Station;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// The sender of every thread track, once collected.
+static SENDER: OnceLock<MetroSender> = OnceLock::new();
+/// The ID of the next thread track.
+static NEXT_TRACK_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static TRACK: RefCell<Option<ThreadState>> = const { RefCell::new(None) };
+}
+
+/// The track of a thread, stopped when the thread exits.
+struct ThreadState {
+    track_id: TrackId,
+    started: bool,
+}
+
+impl Drop for ThreadState {
+    fn drop(&mut self) {
+        if let Some(sender) = SENDER.get().filter(|_| self.started) {
+            sender.send(Event::StopTrack(self.track_id));
+        }
+    }
+}
+
+/// Start collecting the events of [thread tracks], from every thread.
+/// Returns `None` if they are already collected.
+///
+/// The collector is never disconnected, so its events are read with
+/// [`MetroCollector::events`].
+///
+/// ```
+/// use std::thread;
+///
+/// let mut collector = metro::collect_threads().unwrap();
+/// metro::station!("Main thread");
+/// thread::spawn(|| metro::station!("Worker {}", 1)).join().unwrap();
+///
+/// let string = metro::to_string(&collector.events()).unwrap();
+/// assert!(string.contains("Worker 1"));
+/// ```
+///
+/// [thread tracks]: fn.thread_track.html
+/// [`MetroCollector::events`]: struct.MetroCollector.html#method.events
+pub fn collect_threads() -> Option<MetroCollector> {
+    let (sender, collector) = channel();
+    SENDER.set(sender).ok()?;
+    Some(collector)
+}
+
+/// Returns the track of the current thread, assigned the first time
+/// it is needed, which is started when [collected], and stopped when
+/// the thread exits.
+///
+/// *See also [`station!`].*
+///
+/// [collected]: fn.collect_threads.html
+/// [`station!`]: macro.station.html
+pub fn thread_track() -> ThreadTrack {
+    TRACK.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state.get_or_insert_with(|| ThreadState {
+            track_id: TrackId(NEXT_TRACK_ID.fetch_add(1, Ordering::Relaxed)),
+            started: false,
+        });
+        if let Some(sender) = SENDER.get().filter(|_| !state.started) {
+            sender.send(Event::StartTrack(state.track_id));
+            state.started = true;
+        }
+        ThreadTrack {
+            track_id: state.track_id,
+        }
+    })
+}
+
+/// The track of a thread.
+///
+/// *See [`thread_track`].*
+///
+/// [`thread_track`]: fn.thread_track.html
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadTrack {
+    track_id: TrackId,
+}
+
+impl ThreadTrack {
+    /// Returns the ID of this track, e.g. to [`Event::Link`] it.
+    ///
+    /// [`Event::Link`]: enum.Event.html#variant.Link
+    pub fn id(&self) -> TrackId {
+        self.track_id
+    }
+
+    /// Add a station to this track, if [collected].
+    ///
+    /// [collected]: fn.collect_threads.html
+    pub fn add_station<S: Into<Station<'static>>>(&self, station: S) {
+        if let Some(sender) = SENDER.get() {
+            sender.send(Event::Station(self.track_id, station.into()));
+        }
+    }
+}
+
+/// Add a station to the [track of the current thread], with the
+/// arguments of [`format!`].
+///
+/// ```
+/// let id = 42;
+/// metro::station!("Handle request {}", id);
+/// ```
+///
+/// [track of the current thread]: fn.thread_track.html
+/// [`format!`]: https://doc.rust-lang.org/stable/std/macro.format.html
+#[macro_export]
+macro_rules! station {
+    ($($arg:tt)*) => {
+        $crate::thread_track().add_station(format!($($arg)*))
+    };
+}